@@ -1,5 +1,5 @@
 use clap::{App, Arg};
-use easy_fs::{BlockDevice, EasyFileSystem};
+use easy_fs::{block_cache_sync_all, BlockDevice, EasyFileSystem};
 use std::fs::{read_dir, File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::sync::Arc;
@@ -7,6 +7,9 @@ use std::sync::Mutex;
 
 const BLOCK_SZ: usize = 512;
 
+/// 用户名:密码:uid:gid:登录后执行的程序
+const PASSWD: &str = "root:root:0:0:user_shell\nuser:user:1000:1000:user_shell\n";
+
 struct BlockFile(Mutex<File>);
 
 impl BlockDevice for BlockFile {
@@ -61,6 +64,8 @@ fn easy_fs_pack() -> std::io::Result<()> {
     // 16MiB, at most 4095 files
     let efs = EasyFileSystem::create(block_file, 16 * 2048, 1);
     let root_inode = Arc::new(EasyFileSystem::root_inode(&efs));
+    // 所有文件都在根目录下，任何用户都可以在其中创建文件
+    root_inode.set_mode(0o777);
     let apps: Vec<_> = read_dir(src_path)
         .unwrap()
         .into_iter()
@@ -79,7 +84,15 @@ fn easy_fs_pack() -> std::io::Result<()> {
         let inode = root_inode.create(app.as_str()).unwrap();
         // write data to easy-fs
         inode.write_at(0, all_data.as_slice());
+        // 应用属于 root，所有用户都可以执行
+        inode.set_mode(0o755);
     }
+    // 只有 root 可以读取保存密码的文件
+    let passwd = root_inode.create("passwd").unwrap();
+    passwd.write_at(0, PASSWD.as_bytes());
+    passwd.set_mode(0o600);
+    // 写入只修改了块缓存，打包结束前全部写回镜像
+    block_cache_sync_all();
     // list apps
     for app in root_inode.ls() {
         println!("{}", app);
@@ -150,5 +163,15 @@ fn efs_test() -> std::io::Result<()> {
     random_str_test(1000 * BLOCK_SZ);
     random_str_test(2000 * BLOCK_SZ);
 
+    // owner and permission bits survive reopening the file
+    assert_eq!(filea.owner(), (0, 0));
+    assert_eq!(filea.mode(), 0o644);
+    assert_eq!(root_inode.mode(), 0o755);
+    filea.set_owner(1000, 100);
+    filea.set_mode(0o600);
+    let filea = root_inode.find("filea").unwrap();
+    assert_eq!(filea.owner(), (1000, 100));
+    assert_eq!(filea.mode(), 0o600);
+
     Ok(())
 }
@@ -94,12 +94,34 @@ const BLOCK_CACHE_SIZE: usize = 16;
 pub struct BlockCacheManager {
     // 对 BlockCache 进行管理
     queue: VecDeque<(usize, Arc<Mutex<BlockCache>>)>,
+    // 命中缓存的次数
+    hits: usize,
+    // 需要从磁盘读入的次数
+    misses: usize,
+}
+
+/// Statistics of the global block cache
+/// 块缓存的使用情况
+#[derive(Debug, Clone, Copy)]
+pub struct BlockCacheStats {
+    /// 最多缓存的块数
+    pub capacity: usize,
+    /// 当前缓存的块数
+    pub cached: usize,
+    /// 被修改过尚未写回的块数
+    pub dirty: usize,
+    /// 命中次数
+    pub hits: usize,
+    /// 未命中次数
+    pub misses: usize,
 }
 
 impl BlockCacheManager {
     pub fn new() -> Self {
         Self {
             queue: VecDeque::new(),
+            hits: 0,
+            misses: 0,
         }
     }
 
@@ -109,8 +131,10 @@ impl BlockCacheManager {
         block_device: Arc<dyn BlockDevice>,
     ) -> Arc<Mutex<BlockCache>> {
         if let Some(pair) = self.queue.iter().find(|pair| pair.0 == block_id) {
+            self.hits += 1;
             Arc::clone(&pair.1)
         } else {
+            self.misses += 1;
             // 判断管理器保存的块缓存数量是否已经达到上限
             if self.queue.len() == BLOCK_CACHE_SIZE {
                 // from front to tail
@@ -166,3 +190,20 @@ pub fn block_cache_sync_all() {
         cache.lock().sync();
     }
 }
+/// Get statistics of the block cache
+/// 正在被使用的块无法加锁，统计脏块时跳过它们
+pub fn block_cache_stats() -> BlockCacheStats {
+    let manager = BLOCK_CACHE_MANAGER.lock();
+    let dirty = manager
+        .queue
+        .iter()
+        .filter(|(_, cache)| cache.try_lock().map_or(false, |cache| cache.modified))
+        .count();
+    BlockCacheStats {
+        capacity: BLOCK_CACHE_SIZE,
+        cached: manager.queue.len(),
+        dirty,
+        hits: manager.hits,
+        misses: manager.misses,
+    }
+}
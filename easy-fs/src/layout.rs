@@ -4,9 +4,11 @@ use alloc::vec::Vec;
 use core::fmt::{Debug, Formatter, Result};
 
 /// Magic number for sanity check
-const EFS_MAGIC: u32 = 0x3b800001;
+/// DiskInode 加入属主和权限位后布局发生了变化，旧的镜像无法再被识别
+const EFS_MAGIC: u32 = 0x3b800002;
 /// The max number of direct inodes
-const INODE_DIRECT_COUNT: usize = 28;
+/// 为属主和权限位腾出空间，使 DiskInode 仍然是 128 字节
+const INODE_DIRECT_COUNT: usize = 26;
 /// The max length of inode name
 const NAME_LENGTH_LIMIT: usize = 27;
 /// The max number of indirect1 inodes
@@ -83,6 +85,10 @@ impl SuperBlock {
 pub enum DiskInodeType {
     File,
     Directory,
+    /// bind 在文件系统中创建的本地套接字，本身不保存数据
+    Socket,
+    /// mkfifo 创建的命名管道，数据保存在内核的管道缓冲区中
+    Fifo,
 }
 
 /// A indirect block
@@ -100,7 +106,7 @@ pub struct DiskInode {
     pub size: u32,
     /// 文件内容的数据块索引
     // 当文件很小的时候，只需用到直接索引。最多可以指向
-    // INODE_DIRECT_COUNT 个数据块，当取值为 28 的时候，可以找到 14kb 的内容
+    // INODE_DIRECT_COUNT 个数据块，当取值为 26 的时候，可以找到 13kb 的内容
     pub direct: [u32; INODE_DIRECT_COUNT],
     /// 目录内容的数据块索引
     // 当文件比较大的时候，直接索引 direct 装满，还需要用到一级间接索引 indirect1
@@ -108,11 +114,17 @@ pub struct DiskInode {
     // 来指向数据块区域中一个保存该文件内容的数据块，因此，最多能够索引 512/4 = 128 个数据块，对应
     // 64KiB 的内容。
     pub indirect1: u32,
-    // 当文件大小超过直接索引和一级索引支持的容量上限 78KiB 的时候，就需要用到二级间接索引
+    // 当文件大小超过直接索引和一级索引支持的容量上限 77KiB 的时候，就需要用到二级间接索引
     // indirect2 。它指向一个位于数据块区域中的二级索引块。二级索引块中的每个 u32 指向一个不
     // 同的一级索引块，这些一级索引块也位于数据块区域中。因此，通过二级间接索引最多能够索引
     // 128 × 64KiB = 8MiB 的内容
     pub indirect2: u32,
+    /// 属主的用户 id
+    pub uid: u32,
+    /// 属主的组 id
+    pub gid: u32,
+    /// 权限位，按属主、属组、其他用户的顺序各占 rwx 三位
+    pub mode: u16,
     /// 表示节点的类型
     type_: DiskInodeType,
 }
@@ -120,11 +132,20 @@ pub struct DiskInode {
 impl DiskInode {
     /// Initialize a disk inode, as well as all direct inodes under it
     /// indirect1 and indirect2 block are allocated only when they are needed
+    /// 新节点属于 root，文件的权限为 0o644，目录为 0o755，套接字为 0o666，命名管道为 0o644
     pub fn initialize(&mut self, type_: DiskInodeType) {
         self.size = 0;
         self.direct.iter_mut().for_each(|v| *v = 0);
         self.indirect1 = 0;
         self.indirect2 = 0;
+        self.uid = 0;
+        self.gid = 0;
+        self.mode = match type_ {
+            DiskInodeType::File => 0o644,
+            DiskInodeType::Directory => 0o755,
+            DiskInodeType::Socket => 0o666,
+            DiskInodeType::Fifo => 0o644,
+        };
         self.type_ = type_;
     }
     /// Whether this inode is a directory
@@ -139,6 +160,16 @@ impl DiskInode {
         self.type_ == DiskInodeType::File
     }
 
+    /// 判断是否为套接字
+    pub fn is_socket(&self) -> bool {
+        self.type_ == DiskInodeType::Socket
+    }
+
+    /// 判断是否为命名管道
+    pub fn is_fifo(&self) -> bool {
+        self.type_ == DiskInodeType::Fifo
+    }
+
     /// Return block number correspond to size.
     /// 用来计算容纳自身 size 字节的内容需要多少数据块
     pub fn data_blocks(&self) -> u32 {
@@ -1,6 +1,5 @@
 use super::{
-    block_cache_sync_all, get_block_cache, BlockDevice, DirEntry, DiskInode, DiskInodeType,
-    EasyFileSystem, DIRENT_SZ,
+    get_block_cache, BlockDevice, DirEntry, DiskInode, DiskInodeType, EasyFileSystem, DIRENT_SZ,
 };
 use alloc::string::String;
 use alloc::sync::Arc;
@@ -8,6 +7,7 @@ use alloc::vec::Vec;
 use spin::{Mutex, MutexGuard};
 /// Virtual filesystem layer over easy-fs
 /// Inode 暴露给文件系统调用者，能构直接对文件和目录进行操作
+/// 修改只写入块缓存，由调用者通过 block_cache_sync_all 定期写回磁盘
 pub struct Inode {
     /// 该 Inode 对应的 DiskInode 保存在磁盘上的 block_id
     block_id: usize,
@@ -106,6 +106,20 @@ impl Inode {
     /// Create inode under current inode by name
     /// 在根目录下创建一文件该方法只有根目录的 Inode 会调用
     pub fn create(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::File)
+    }
+    /// Create a socket inode under current inode by name
+    /// 在根目录下创建一个套接字节点，供本地套接字绑定路径使用
+    pub fn create_socket(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::Socket)
+    }
+    /// Create a fifo inode under current inode by name
+    /// 在根目录下创建一个命名管道节点
+    pub fn create_fifo(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::Fifo)
+    }
+    /// 在根目录下创建一个 type_ 类型的节点，同名的节点已经存在时返回 None
+    fn create_inode(&self, name: &str, type_: DiskInodeType) -> Option<Arc<Inode>> {
         let mut fs = self.fs.lock();
         // 检查文件是否已经在根目录下
         let op = |root_inode: &DiskInode| {
@@ -128,7 +142,7 @@ impl Inode {
         get_block_cache(new_inode_block_id as usize, Arc::clone(&self.block_device))
             .lock()
             .modify(new_inode_block_offset, |new_inode: &mut DiskInode| {
-                new_inode.initialize(DiskInodeType::File);
+                new_inode.initialize(type_);
             });
         // 将待创建文件的目录项插入到根目录的内容中，使得之后可以索引到
         self.modify_disk_inode(|root_inode| {
@@ -147,7 +161,6 @@ impl Inode {
         });
 
         let (block_id, block_offset) = fs.get_disk_inode_pos(new_inode_id);
-        block_cache_sync_all();
         // return inode
         Some(Arc::new(Self::new(
             block_id,
@@ -190,9 +203,46 @@ impl Inode {
             self.increase_size((offset + buf.len()) as u32, disk_inode, &mut fs);
             disk_inode.write_at(offset, buf, &self.block_device)
         });
-        block_cache_sync_all();
         size
     }
+    /// Get the owner of current inode
+    /// 返回属主的 (uid, gid)
+    pub fn owner(&self) -> (u32, u32) {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| (disk_inode.uid, disk_inode.gid))
+    }
+    /// Whether current inode is a socket created by create_socket
+    pub fn is_socket(&self) -> bool {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.is_socket())
+    }
+    /// Whether current inode is a fifo created by create_fifo
+    pub fn is_fifo(&self) -> bool {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.is_fifo())
+    }
+    /// 节点在磁盘上的位置 (block_id, block_offset)，同一个节点的不同 Inode 返回相同的值
+    pub fn disk_pos(&self) -> (usize, usize) {
+        (self.block_id, self.block_offset)
+    }
+    /// Get the permission bits of current inode
+    pub fn mode(&self) -> u16 {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.mode)
+    }
+    /// Change the owner of current inode
+    pub fn set_owner(&self, uid: u32, gid: u32) {
+        let _fs = self.fs.lock();
+        self.modify_disk_inode(|disk_inode| {
+            disk_inode.uid = uid;
+            disk_inode.gid = gid;
+        });
+    }
+    /// Change the permission bits of current inode
+    pub fn set_mode(&self, mode: u16) {
+        let _fs = self.fs.lock();
+        self.modify_disk_inode(|disk_inode| disk_inode.mode = mode & 0o777);
+    }
     /// Clear the data in current inode
     /// 清空 Inode
     pub fn clear(&self) {
@@ -205,6 +255,5 @@ impl Inode {
                 fs.dealloc_data(data_block);
             }
         });
-        block_cache_sync_all();
     }
 }
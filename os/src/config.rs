@@ -1,7 +1,23 @@
 //! const used in os
 
 pub const USER_STACK_SIZE: usize = 4096 * 2;
+/// 线程可以申请的最大用户栈
+pub const MAX_USER_STACK_SIZE: usize = 4096 * 16;
+/// 每个线程默认的 TLS 块大小，tp 寄存器指向它的起始地址
+pub const USER_TLS_SIZE: usize = 4096;
+/// 线程可以申请的最大 TLS 块
+pub const MAX_USER_TLS_SIZE: usize = 4096 * 4;
+/// 没有其他就绪线程时不再设置固定的时间片中断，只在最早的定时器到期时触发
+pub const TICKLESS: bool = true;
 pub const KERNEL_STACK_SIZE: usize = 4096 * 2;
+/// 写回内核线程把块缓存中的脏块写回磁盘的周期，单位为毫秒
+pub const WRITEBACK_INTERVAL_MS: usize = 500;
+/// 线程的默认优先级，数值越大越优先
+pub const DEFAULT_PRIORITY: usize = 16;
+/// sys_set_priority 允许设置的最低优先级
+pub const MIN_PRIORITY: usize = 2;
+/// sys_set_priority 允许设置的最高优先级，只有 root 可以设置高于 DEFAULT_PRIORITY 的优先级
+pub const MAX_PRIORITY: usize = 32;
 //pub const MAX_APP_SIZE: usize = 4;
 //pub const APP_BASE_ADDRESS: usize = 0x80400000;
 //pub const APP_SIZE_LIMIT: usize = 0x20000;
@@ -13,6 +29,9 @@ pub const PAGE_SIZE_BITS: usize = 0xc;
 /// 页面大小 4096
 pub const PAGE_SIZE: usize = 0x1000;
 
+/// 管道缓冲区的容量
+pub const PIPE_BUFFER_SIZE: usize = PAGE_SIZE;
+
 /// 物理内存的终止物理地址
 pub const MEMORY_END: usize = 0x8800_0000;
 
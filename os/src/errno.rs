@@ -0,0 +1,76 @@
+//! 错误码
+//!
+//! 取值与 Linux 相同。系统调用出错时返回错误码的相反数，内核中以 `Result<_, isize>`
+//! 传递的错误也同样是负的错误码，可以直接作为系统调用的返回值
+#![allow(unused)]
+
+/// 操作不允许
+pub const EPERM: isize = 1;
+/// 文件或者目录不存在
+pub const ENOENT: isize = 2;
+/// 进程或者线程不存在
+pub const ESRCH: isize = 3;
+/// 等待被信号打断
+pub const EINTR: isize = 4;
+/// 读写出错
+pub const EIO: isize = 5;
+/// 设备或者地址不存在，例如以非阻塞方式只写打开没有读端的命名管道
+pub const ENXIO: isize = 6;
+/// 参数列表过长
+pub const E2BIG: isize = 7;
+/// 不是合法的可执行文件
+pub const ENOEXEC: isize = 8;
+/// 文件描述符不存在，或者没有以需要的方式打开
+pub const EBADF: isize = 9;
+/// 没有可以等待的子进程
+pub const ECHILD: isize = 10;
+/// 资源暂时不可用，稍后重试
+pub const EAGAIN: isize = 11;
+/// 内存不足
+pub const ENOMEM: isize = 12;
+/// 没有权限
+pub const EACCES: isize = 13;
+/// 用户地址非法
+pub const EFAULT: isize = 14;
+/// 资源正忙
+pub const EBUSY: isize = 16;
+/// 文件已经存在
+pub const EEXIST: isize = 17;
+/// 参数非法
+pub const EINVAL: isize = 22;
+/// 打开的文件数量超过进程的上限
+pub const EMFILE: isize = 24;
+/// 磁盘空间不足
+pub const ENOSPC: isize = 28;
+/// 对端已经关闭
+pub const EPIPE: isize = 32;
+/// 会导致死锁
+pub const EDEADLK: isize = 35;
+/// 不支持的系统调用
+pub const ENOSYS: isize = 38;
+/// 等待的对象已经被销毁
+pub const EIDRM: isize = 43;
+/// 文件描述符不是套接字
+pub const ENOTSOCK: isize = 88;
+/// 数据报套接字没有指定目的地址
+pub const EDESTADDRREQ: isize = 89;
+/// 消息过长
+pub const EMSGSIZE: isize = 90;
+/// 套接字的类型不匹配
+pub const EPROTOTYPE: isize = 91;
+/// 不支持的协议
+pub const EPROTONOSUPPORT: isize = 93;
+/// 套接字不支持这个操作
+pub const EOPNOTSUPP: isize = 95;
+/// 不支持的地址族
+pub const EAFNOSUPPORT: isize = 97;
+/// 地址已经被使用
+pub const EADDRINUSE: isize = 98;
+/// 套接字已经连接
+pub const EISCONN: isize = 106;
+/// 套接字还没有连接
+pub const ENOTCONN: isize = 107;
+/// 等待超时
+pub const ETIMEDOUT: isize = 110;
+/// 对方没有在监听
+pub const ECONNREFUSED: isize = 111;
@@ -0,0 +1,67 @@
+//! 命名管道（FIFO）
+//!
+//! FIFO 在 easy-fs 中只是一个节点，数据保存在内核的管道缓冲区中。同一个节点的所有打开共享一个缓冲区，
+//! 因此没有亲缘关系的进程也可以通过文件名通信。所有端都关闭之后缓冲区被释放，其中未读的数据被丢弃
+
+use super::pipe::{Pipe, PipeRingBuffer};
+use super::{File, OpenFlags};
+use crate::errno::ENXIO;
+use crate::sync::UPSafeCell;
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use easy_fs::Inode;
+use lazy_static::*;
+
+/// 节点在磁盘上的位置到共享的管道缓冲区的映射
+type FifoBuffers = BTreeMap<(usize, usize), Weak<UPSafeCell<PipeRingBuffer>>>;
+
+lazy_static! {
+    static ref FIFO_BUFFERS: UPSafeCell<FifoBuffers> = unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+/// 找到 inode 对应的管道缓冲区，还没有被打开过时新建一个
+fn fifo_buffer(inode: &Inode) -> Arc<UPSafeCell<PipeRingBuffer>> {
+    let mut buffers = FIFO_BUFFERS.exclusive_access();
+    // 顺便清理所有端都已经关闭的缓冲区
+    buffers.retain(|_, buffer| buffer.strong_count() > 0);
+    let key = inode.disk_pos();
+    if let Some(buffer) = buffers.get(&key).and_then(|buffer| buffer.upgrade()) {
+        return buffer;
+    }
+    let buffer = Arc::new(unsafe { UPSafeCell::new(PipeRingBuffer::new()) });
+    buffers.insert(key, Arc::downgrade(&buffer));
+    buffer
+}
+
+/// 打开命名管道的一端
+/// 只读或只写打开时阻塞，直到另一端也被打开过；以读写方式打开不会阻塞。
+/// 非阻塞模式下只读打开立即返回，只写打开在没有读端时返回 ENXIO
+pub fn open_fifo(inode: &Inode, flags: OpenFlags) -> Result<Arc<Pipe>, isize> {
+    let (readable, writable) = flags.read_write();
+    let nonblock = flags.contains(OpenFlags::NONBLOCK);
+    let buffer = fifo_buffer(inode);
+    if nonblock && !readable && buffer.exclusive_access().all_read_ends_closed() {
+        return Err(-ENXIO);
+    }
+    let pipe = Arc::new(Pipe::new(Arc::clone(&buffer), readable, writable));
+    pipe.set_nonblock(nonblock);
+    if nonblock || (readable && writable) {
+        return Ok(pipe);
+    }
+    // 记下打开时另一端的打开次数，另一端被打开过之后即使又被关闭了也不再等待
+    let (read_opens, write_opens) = buffer.exclusive_access().opens();
+    loop {
+        let ring_buffer = buffer.exclusive_access();
+        let (now_read_opens, now_write_opens) = ring_buffer.opens();
+        let arrived = if readable {
+            !ring_buffer.all_write_ends_closed() || now_write_opens != write_opens
+        } else {
+            !ring_buffer.all_read_ends_closed() || now_read_opens != read_opens
+        };
+        drop(ring_buffer);
+        if arrived {
+            return Ok(pipe);
+        }
+        PipeRingBuffer::wait_for_opener(&buffer);
+    }
+}
@@ -4,10 +4,14 @@
 //!
 //! `UPSafeCell<OSInodeInner>` -> `OSInode`: for static `ROOT_INODE`,we
 //! need to wrap `OSInodeInner` into `UPSafeCell`
+use super::fifo;
+use super::pipe::Pipe;
 use super::File;
 use crate::drivers::BLOCK_DEVICE;
+use crate::errno::*;
 use crate::mm::UserBuffer;
 use crate::sync::UPSafeCell;
+use crate::task::{Credentials, MAY_READ, MAY_WRITE};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use bitflags::*;
@@ -56,6 +60,15 @@ impl OSInode {
         }
         v
     }
+    /// 检查 cred 是否拥有该文件 access 要求的权限
+    pub fn permits(&self, cred: &Credentials, access: u16) -> bool {
+        permits(&self.inner.exclusive_access().inode, cred, access)
+    }
+}
+
+fn permits(inode: &Inode, cred: &Credentials, access: u16) -> bool {
+    let (uid, gid) = inode.owner();
+    cred.permits(uid, gid, inode.mode(), access)
 }
 
 lazy_static! {
@@ -86,6 +99,8 @@ bitflags! {
         const CREATE = 1 << 9;
         ///Clear file and return an empty one
         const TRUNC = 1 << 10;
+        ///读写时不阻塞
+        const NONBLOCK = 1 << 11;
     }
 }
 
@@ -93,9 +108,11 @@ impl OpenFlags {
     /// Do not check validity for simplicity
     /// Return (readable, writable)
     pub fn read_write(&self) -> (bool, bool) {
-        if self.is_empty() {
+        // NONBLOCK 与读写权限无关
+        let flags = *self - Self::NONBLOCK;
+        if flags.is_empty() {
             (true, false)
-        } else if self.contains(Self::WRONLY) {
+        } else if flags.contains(Self::WRONLY) {
             (false, true)
         } else {
             (true, true)
@@ -104,29 +121,129 @@ impl OpenFlags {
 }
 ///Open file with flags
 ///可根据文件名打开一个根目录下的文件
-pub fn open_file(name: &str, flags: OpenFlags) -> Option<Arc<OSInode>> {
+///打开已有文件需要 flags 对应的读写权限，创建文件需要根目录的写权限，新文件属于 cred 的有效用户和组
+///失败时返回负的错误码
+pub fn open_file(name: &str, flags: OpenFlags, cred: &Credentials) -> Result<Arc<OSInode>, isize> {
     let (readable, writable) = flags.read_write();
-    if flags.contains(OpenFlags::CREATE) {
-        // 只有 flags 参数包含 CREATE 标志位才允许创建文件
-        if let Some(inode) = ROOT_INODE.find(name) {
+    let access = open_access(flags);
+    if let Some(inode) = ROOT_INODE.find(name) {
+        // 套接字节点只能通过 connect 使用，命名管道由 open_fifo 打开
+        if inode.is_socket() || inode.is_fifo() {
+            return Err(-ENXIO);
+        }
+        if !permits(&inode, cred, access) {
+            return Err(-EACCES);
+        }
+        if flags.intersects(OpenFlags::CREATE | OpenFlags::TRUNC) {
             // clear size
             // 文件存在则清空
             inode.clear();
-            Some(Arc::new(OSInode::new(readable, writable, inode)))
-        } else {
-            // create file
-            // 文件不存在创建
-            ROOT_INODE
-                .create(name)
-                .map(|inode| Arc::new(OSInode::new(readable, writable, inode)))
+        }
+        Ok(Arc::new(OSInode::new(readable, writable, inode)))
+    } else if flags.contains(OpenFlags::CREATE) {
+        // 只有 flags 参数包含 CREATE 标志位才允许创建文件
+        if !permits(&ROOT_INODE, cred, MAY_WRITE) {
+            return Err(-EACCES);
+        }
+        // create file
+        // 文件不存在创建
+        match ROOT_INODE.create(name) {
+            Some(inode) => {
+                inode.set_owner(cred.euid, cred.egid);
+                Ok(Arc::new(OSInode::new(readable, writable, inode)))
+            }
+            None => Err(-ENOSPC),
         }
     } else {
-        ROOT_INODE.find(name).map(|inode| {
-            if flags.contains(OpenFlags::TRUNC) {
-                inode.clear();
+        Err(-ENOENT)
+    }
+}
+
+/// 按 flags 打开文件需要的权限
+fn open_access(flags: OpenFlags) -> u16 {
+    let (readable, writable) = flags.read_write();
+    let mut access = 0;
+    if readable {
+        access |= MAY_READ;
+    }
+    // 清空文件同样需要写权限
+    if writable || flags.intersects(OpenFlags::CREATE | OpenFlags::TRUNC) {
+        access |= MAY_WRITE;
+    }
+    access
+}
+
+/// 判断根目录下的 name 是否为命名管道
+pub fn is_fifo(name: &str) -> bool {
+    match ROOT_INODE.find(name) {
+        Some(inode) => inode.is_fifo(),
+        None => false,
+    }
+}
+
+/// 打开根目录下的命名管道，需要 flags 对应的读写权限，CREATE 和 TRUNC 被忽略
+/// 打开过程可能阻塞，见 fifo::open_fifo
+pub fn open_fifo(name: &str, flags: OpenFlags, cred: &Credentials) -> Result<Arc<Pipe>, isize> {
+    let flags = flags - (OpenFlags::CREATE | OpenFlags::TRUNC);
+    match ROOT_INODE.find(name) {
+        Some(inode) if inode.is_fifo() => {
+            if !permits(&inode, cred, open_access(flags)) {
+                return Err(-EACCES);
             }
-            Arc::new(OSInode::new(readable, writable, inode))
-        })
+            fifo::open_fifo(&inode, flags)
+        }
+        _ => Err(-ENOENT),
+    }
+}
+
+/// 在根目录下创建权限为 mode 的命名管道，需要根目录的写权限
+pub fn create_fifo(name: &str, mode: u16, cred: &Credentials) -> Result<(), isize> {
+    if !permits(&ROOT_INODE, cred, MAY_WRITE) {
+        return Err(-EACCES);
+    }
+    if ROOT_INODE.find(name).is_some() {
+        return Err(-EEXIST);
+    }
+    match ROOT_INODE.create_fifo(name) {
+        Some(inode) => {
+            inode.set_owner(cred.euid, cred.egid);
+            inode.set_mode(mode);
+            Ok(())
+        }
+        None => Err(-ENOSPC),
+    }
+}
+
+/// 为 bind 在根目录下创建套接字节点，创建需要根目录的写权限
+/// 同名的套接字节点已经存在时（之前绑定的套接字已经关闭）需要它的写权限才能复用，
+/// 同名的其他节点已经存在时返回 EADDRINUSE
+pub fn bind_socket_inode(name: &str, cred: &Credentials) -> Result<(), isize> {
+    match ROOT_INODE.find(name) {
+        Some(inode) if !inode.is_socket() => Err(-EADDRINUSE),
+        Some(inode) if !permits(&inode, cred, MAY_WRITE) => Err(-EACCES),
+        Some(_) => Ok(()),
+        None => {
+            if !permits(&ROOT_INODE, cred, MAY_WRITE) {
+                return Err(-EACCES);
+            }
+            match ROOT_INODE.create_socket(name) {
+                Some(inode) => {
+                    inode.set_owner(cred.euid, cred.egid);
+                    Ok(())
+                }
+                None => Err(-ENOSPC),
+            }
+        }
+    }
+}
+
+/// connect 需要 name 是套接字节点，并且有它的写权限
+pub fn check_socket_inode(name: &str, cred: &Credentials) -> Result<(), isize> {
+    match ROOT_INODE.find(name) {
+        Some(inode) if !inode.is_socket() => Err(-ECONNREFUSED),
+        Some(inode) if !permits(&inode, cred, MAY_WRITE) => Err(-EACCES),
+        Some(_) => Ok(()),
+        None => Err(-ENOENT),
     }
 }
 
@@ -1,9 +1,34 @@
 //！ File system
+mod fifo;
 mod inode;
+mod mqueue;
 mod pipe;
+mod procfs;
+mod socket;
 mod stdio;
+mod writeback;
 
 use crate::mm::UserBuffer;
+use crate::task::TaskControlBlock;
+use alloc::sync::Arc;
+use bitflags::*;
+
+bitflags! {
+    /// poll 关心和返回的事件，取值与 Linux 相同
+    pub struct PollEvents: u16 {
+        /// 有数据可读，或者读不会阻塞（例如对端已经关闭）
+        const IN = 1 << 0;
+        /// 写不会阻塞
+        const OUT = 1 << 2;
+        /// 出错，例如读端已经关闭的管道写端，总是会返回
+        const ERR = 1 << 3;
+        /// 对端已经关闭，总是会返回
+        const HUP = 1 << 4;
+        /// 文件描述符不存在，总是会返回
+        const NVAL = 1 << 5;
+    }
+}
+
 /// File trait
 /// 操作系统内核就可把能读写并持久存储的数据按文件来进行管理，
 /// 并把文件分配给进程.这个接口在内存和存储设备之间建立了数据交换的通道
@@ -21,8 +46,47 @@ pub trait File: Send + Sync {
     /// write 指的是将缓冲区中的数据写入文件，最多将缓冲区中的数据全部写入，
     /// 并返回直接写入的字节数
     fn write(&self, buf: UserBuffer) -> usize;
+    /// 文件是消息队列时返回它的描述符，供 mq_timedsend 等系统调用使用
+    fn as_mqueue(&self) -> Option<&MqDescriptor> {
+        None
+    }
+    /// 文件是套接字时返回它，供 bind、sendmsg 等系统调用使用
+    fn as_socket(&self) -> Option<&Socket> {
+        None
+    }
+    /// 查询当前就绪的事件，默认可读的文件总是可读，可写的文件总是可写
+    fn poll(&self) -> PollEvents {
+        let mut events = PollEvents::empty();
+        if self.readable() {
+            events |= PollEvents::IN;
+        }
+        if self.writable() {
+            events |= PollEvents::OUT;
+        }
+        events
+    }
+    /// 把正在 poll 的线程加入文件的等待队列，文件状态变化时唤醒它
+    /// 返回 false 表示文件没有等待队列，poll 需要定期重新查询
+    fn register_poller(&self, _task: &Arc<TaskControlBlock>) -> bool {
+        false
+    }
+    /// poll 返回前把线程从等待队列中移除
+    fn unregister_poller(&self, _task: &Arc<TaskControlBlock>) {}
+    /// 是否处于非阻塞模式，此时会阻塞的 read/write 返回 EAGAIN
+    fn nonblock(&self) -> bool {
+        false
+    }
+    /// 设置非阻塞模式，从不阻塞的文件（例如普通文件）忽略这个设置
+    fn set_nonblock(&self, _nonblock: bool) {}
 }
 
-pub use inode::{list_apps, open_file, OSInode, OpenFlags};
+pub use inode::{
+    bind_socket_inode, check_socket_inode, create_fifo, is_fifo, list_apps, open_fifo, open_file,
+    OSInode, OpenFlags,
+};
+pub use mqueue::{mq_open, mq_unlink, MqAttr, MqDescriptor};
 pub use pipe::make_pipe;
+pub use procfs::{is_proc_path, open_proc};
+pub use socket::{Socket, SocketType, AF_UNIX, SCM_MAX_FD, SOCK_DGRAM, SOCK_STREAM};
 pub use stdio::{Stdin, Stdout};
+pub use writeback::writeback_start;
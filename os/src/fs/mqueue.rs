@@ -0,0 +1,319 @@
+//! POSIX 风格的消息队列
+//!
+//! 消息队列按名字打开，所有进程共享同一个命名空间。与管道不同，它保留消息的边界，
+//! 并且优先级高的消息先被接收，优先级相同的消息按发送顺序接收。
+//! 打开的消息队列以文件的形式放在文件描述符表中，随 fork 继承，随进程退出关闭
+
+use super::{File, OpenFlags, PollEvents};
+use crate::errno::*;
+use crate::mm::UserBuffer;
+use crate::sync::{block_current_until, remove_waiter, take_waiter, wake_all_waiters, UPSafeCell};
+use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use lazy_static::*;
+
+/// 创建时没有指定属性使用的容量
+pub const MQ_MAXMSG_DEFAULT: usize = 10;
+/// 创建时没有指定属性使用的单条消息最大长度
+pub const MQ_MSGSIZE_DEFAULT: usize = 256;
+/// 容量的上限
+pub const MQ_MAXMSG_MAX: usize = 64;
+/// 单条消息最大长度的上限
+pub const MQ_MSGSIZE_MAX: usize = 4096;
+/// 消息优先级的范围是 [0, MQ_PRIO_MAX)
+pub const MQ_PRIO_MAX: u32 = 32;
+
+/// 消息队列的属性，与用户态的布局相同
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MqAttr {
+    /// 打开时的标志，目前只有 OpenFlags::NONBLOCK 有意义
+    pub flags: usize,
+    /// 最多容纳的消息数量
+    pub maxmsg: usize,
+    /// 单条消息的最大长度
+    pub msgsize: usize,
+    /// 当前的消息数量
+    pub curmsgs: usize,
+}
+
+/// 消息队列本身，被所有打开它的描述符共享
+pub struct MessageQueue {
+    maxmsg: usize,
+    msgsize: usize,
+    inner: UPSafeCell<MessageQueueInner>,
+}
+
+struct MessageQueueInner {
+    /// 按优先级从高到低排列的消息
+    messages: VecDeque<(u32, Vec<u8>)>,
+    /// 等待消息的线程
+    recv_waiters: VecDeque<Arc<TaskControlBlock>>,
+    /// 等待空位的线程
+    send_waiters: VecDeque<Arc<TaskControlBlock>>,
+    /// 正在 poll 的线程，消息数量变化时被唤醒
+    pollers: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl MessageQueue {
+    fn new(maxmsg: usize, msgsize: usize) -> Self {
+        Self {
+            maxmsg,
+            msgsize,
+            inner: unsafe {
+                UPSafeCell::new(MessageQueueInner {
+                    messages: VecDeque::new(),
+                    recv_waiters: VecDeque::new(),
+                    send_waiters: VecDeque::new(),
+                    pollers: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// 当前线程加入 waiters 选出的等待队列并阻塞，直到被唤醒或者到达 expire 时刻，返回是否超时
+    fn wait(
+        &self,
+        expire: Option<usize>,
+        waiters: fn(&mut MessageQueueInner) -> &mut VecDeque<Arc<TaskControlBlock>>,
+    ) -> bool {
+        let task = current_task().unwrap();
+        waiters(&mut self.inner.exclusive_access()).push_back(Arc::clone(&task));
+        match expire {
+            Some(expire) => block_current_until(&task, expire),
+            None => block_current_and_run_next(),
+        }
+        remove_waiter(waiters(&mut self.inner.exclusive_access()), &task)
+    }
+
+    /// 发送一条消息，队列已满时阻塞，直到有空位或者到达 expire 时刻
+    /// 消息过长返回 EMSGSIZE，优先级非法返回 EINVAL，非阻塞模式下队列已满返回 EAGAIN，超时返回 ETIMEDOUT
+    fn send(
+        &self,
+        msg: Vec<u8>,
+        prio: u32,
+        nonblock: bool,
+        expire: Option<usize>,
+    ) -> Result<(), isize> {
+        if msg.len() > self.msgsize {
+            return Err(-EMSGSIZE);
+        }
+        if prio >= MQ_PRIO_MAX {
+            return Err(-EINVAL);
+        }
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if inner.messages.len() < self.maxmsg {
+                // 插到所有优先级不低于它的消息之后
+                let pos = inner
+                    .messages
+                    .iter()
+                    .position(|&(p, _)| p < prio)
+                    .unwrap_or(inner.messages.len());
+                inner.messages.insert(pos, (prio, msg));
+                if let Some(task) = take_waiter(&mut inner.recv_waiters) {
+                    wakeup_task(task);
+                }
+                wake_all_waiters(&mut inner.pollers);
+                return Ok(());
+            }
+            drop(inner);
+            if nonblock {
+                return Err(-EAGAIN);
+            }
+            // 被唤醒时空位可能已经被其他发送者占用，重新检查
+            if self.wait(expire, |inner| &mut inner.send_waiters) {
+                return Err(-ETIMEDOUT);
+            }
+        }
+    }
+
+    /// 接收优先级最高的一条消息写入 buf，返回消息的长度和优先级
+    /// buf 比单条消息的最大长度短时返回 EMSGSIZE，其余错误码与 send 相同
+    fn receive(
+        &self,
+        buf: UserBuffer,
+        nonblock: bool,
+        expire: Option<usize>,
+    ) -> Result<(usize, u32), isize> {
+        if buf.len() < self.msgsize {
+            return Err(-EMSGSIZE);
+        }
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if let Some((prio, msg)) = inner.messages.pop_front() {
+                if let Some(task) = take_waiter(&mut inner.send_waiters) {
+                    wakeup_task(task);
+                }
+                wake_all_waiters(&mut inner.pollers);
+                drop(inner);
+                for (byte_ref, &byte) in buf.into_iter().zip(msg.iter()) {
+                    unsafe {
+                        *byte_ref = byte;
+                    }
+                }
+                return Ok((msg.len(), prio));
+            }
+            drop(inner);
+            if nonblock {
+                return Err(-EAGAIN);
+            }
+            if self.wait(expire, |inner| &mut inner.recv_waiters) {
+                return Err(-ETIMEDOUT);
+            }
+        }
+    }
+}
+
+/// 打开的消息队列，fork 后父子进程共享同一个描述符
+pub struct MqDescriptor {
+    queue: Arc<MessageQueue>,
+    readable: bool,
+    writable: bool,
+    /// 队列满或者空时立即返回 EAGAIN 而不阻塞，可以通过 fcntl 修改
+    nonblock: AtomicBool,
+}
+
+impl MqDescriptor {
+    /// 发送一条优先级为 prio 的消息，expire 为 None 时一直等待
+    pub fn send(&self, msg: Vec<u8>, prio: u32, expire: Option<usize>) -> Result<(), isize> {
+        self.queue.send(msg, prio, self.nonblock(), expire)
+    }
+
+    /// 接收一条消息，返回消息的长度和优先级，expire 为 None 时一直等待
+    pub fn receive(&self, buf: UserBuffer, expire: Option<usize>) -> Result<(usize, u32), isize> {
+        self.queue.receive(buf, self.nonblock(), expire)
+    }
+
+    pub fn attr(&self) -> MqAttr {
+        MqAttr {
+            flags: if self.nonblock() {
+                OpenFlags::NONBLOCK.bits() as usize
+            } else {
+                0
+            },
+            maxmsg: self.queue.maxmsg,
+            msgsize: self.queue.msgsize,
+            curmsgs: self.queue.inner.exclusive_access().messages.len(),
+        }
+    }
+}
+
+impl File for MqDescriptor {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+
+    fn writable(&self) -> bool {
+        self.writable
+    }
+
+    /// 接收一条消息，出错时返回 0
+    fn read(&self, buf: UserBuffer) -> usize {
+        self.receive(buf, None).map_or(0, |(len, _)| len)
+    }
+
+    /// 把整个缓冲区作为一条优先级为 0 的消息发送，出错时返回 0
+    fn write(&self, buf: UserBuffer) -> usize {
+        let msg: Vec<u8> = buf
+            .into_iter()
+            .map(|byte_ref| unsafe { *byte_ref })
+            .collect();
+        let len = msg.len();
+        self.send(msg, 0, None).map_or(0, |_| len)
+    }
+
+    fn as_mqueue(&self) -> Option<&MqDescriptor> {
+        Some(self)
+    }
+
+    /// 有消息时可读，没有满时可写
+    fn poll(&self) -> PollEvents {
+        let len = self.queue.inner.exclusive_access().messages.len();
+        let mut events = PollEvents::empty();
+        if self.readable && len > 0 {
+            events |= PollEvents::IN;
+        }
+        if self.writable && len < self.queue.maxmsg {
+            events |= PollEvents::OUT;
+        }
+        events
+    }
+
+    fn register_poller(&self, task: &Arc<TaskControlBlock>) -> bool {
+        self.queue
+            .inner
+            .exclusive_access()
+            .pollers
+            .push_back(Arc::clone(task));
+        true
+    }
+
+    fn unregister_poller(&self, task: &Arc<TaskControlBlock>) {
+        remove_waiter(&mut self.queue.inner.exclusive_access().pollers, task);
+    }
+
+    fn nonblock(&self) -> bool {
+        self.nonblock.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblock(&self, nonblock: bool) {
+        self.nonblock.store(nonblock, Ordering::Relaxed);
+    }
+}
+
+lazy_static! {
+    /// 消息队列的命名空间，被删除的队列在最后一个描述符关闭后释放
+    static ref MQ_NAMESPACE: UPSafeCell<BTreeMap<String, Arc<MessageQueue>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+/// 名字以 '/' 开头，之后不能再有 '/'
+fn is_valid_name(name: &str) -> bool {
+    name.len() > 1 && name.starts_with('/') && !name[1..].contains('/')
+}
+
+/// 按名字打开消息队列，flags 含有 CREATE 且队列不存在时按 attr 创建，attr 为 None 时使用默认属性
+/// 名字或者属性非法时返回 EINVAL，队列不存在且不允许创建时返回 ENOENT
+pub fn mq_open(
+    name: &str,
+    flags: OpenFlags,
+    attr: Option<MqAttr>,
+) -> Result<Arc<MqDescriptor>, isize> {
+    if !is_valid_name(name) {
+        return Err(-EINVAL);
+    }
+    let mut namespace = MQ_NAMESPACE.exclusive_access();
+    let queue = match namespace.get(name) {
+        Some(queue) => Arc::clone(queue),
+        None if flags.contains(OpenFlags::CREATE) => {
+            let (maxmsg, msgsize) = match attr {
+                Some(attr) => (attr.maxmsg, attr.msgsize),
+                None => (MQ_MAXMSG_DEFAULT, MQ_MSGSIZE_DEFAULT),
+            };
+            if !(1..=MQ_MAXMSG_MAX).contains(&maxmsg) || !(1..=MQ_MSGSIZE_MAX).contains(&msgsize) {
+                return Err(-EINVAL);
+            }
+            let queue = Arc::new(MessageQueue::new(maxmsg, msgsize));
+            namespace.insert(String::from(name), Arc::clone(&queue));
+            queue
+        }
+        None => return Err(-ENOENT),
+    };
+    let (readable, writable) = flags.read_write();
+    Ok(Arc::new(MqDescriptor {
+        queue,
+        readable,
+        writable,
+        nonblock: AtomicBool::new(flags.contains(OpenFlags::NONBLOCK)),
+    }))
+}
+
+/// 从命名空间中删除消息队列，已经打开它的描述符仍然可以使用，名字不存在时返回 false
+pub fn mq_unlink(name: &str) -> bool {
+    MQ_NAMESPACE.exclusive_access().remove(name).is_some()
+}
@@ -1,9 +1,15 @@
 /// 管道是一种进程间通信机制，由操作系统提供，并可通过直接编程或在shell程序的帮助下轻松地把不同进程（目前是父子进程之间或子子进程之间）的输入和输出对接起来。
 /// 将管道看成一个有一定缓冲区大小的字节队列，它分为读和写两端，需要通过不同的文件描述符来访问。读端只能用来从管道中读取，而写端只能用来将数据写入管道。
-use super::File;
-use crate::task::suspend_current_and_run_next;
+use super::{File, PollEvents};
+use crate::config::PIPE_BUFFER_SIZE;
+use crate::sync::{remove_waiter, wake_all_waiters};
+use crate::task::{block_current_and_run_next, current_task, TaskControlBlock};
 use crate::{mm::UserBuffer, sync::UPSafeCell};
-use alloc::sync::{Arc, Weak};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 /// 管道
 pub struct Pipe {
@@ -13,123 +19,150 @@ pub struct Pipe {
     writeable: bool,
     /// 找到管道所在的管道自身
     buffer: Arc<UPSafeCell<PipeRingBuffer>>,
+    /// 非阻塞模式，由 fork 和 dup 得到的描述符共享
+    nonblock: AtomicBool,
 }
 
-const RING_BUFFER_SIZE: usize = 32;
-
-/// 用来记录缓冲区状态
-#[derive(Copy, Clone, PartialEq)]
-enum RingBufferStatus {
-    // 缓冲区已满，不能继续写入
-    Full,
-    // 缓冲区为空，不能从里面读取
-    Empty,
-    // 除了 FULL 和 EMPTY 之外的其他状态
-    Normal,
-}
-
-/// 管道自身，带有一定大小缓冲区的字节队列
+/// 管道自身，容量为 PIPE_BUFFER_SIZE 的循环字节队列
 pub struct PipeRingBuffer {
-    // arr，head，tail 用来维护一个循环队列
-    // arr 存放数据的数组
-    arr: [u8; RING_BUFFER_SIZE],
-    // 队列头
+    // 存放数据的数组
+    arr: Vec<u8>,
+    // 队头，下一个被读取的字节的位置
     head: usize,
-    // 队列尾
-    tail: usize,
-    // 可写状态
-    status: RingBufferStatus,
-    // 保存了它的写端的一个弱引用计数，这是由于在某些情况下需要确认该管道所有的写端是否都已经被关闭了，
-    // 通过这个字段很容易确认这一点。
-    write_end: Option<Weak<Pipe>>,
+    // 队列中的字节数，用它区分队列为空和已满
+    len: usize,
+    // 读端和写端的数量，用来确认管道的某一端是否已经全部被关闭了。
+    // 匿名管道各只有一个，FIFO 每被打开一次就多一个
+    read_ends: usize,
+    write_ends: usize,
+    // 读端和写端累计被打开的次数，阻塞在 open 中的 FIFO 据此发现另一端曾经被打开过
+    read_opens: usize,
+    write_opens: usize,
+    // 阻塞在 open 中等待另一端的 FIFO 打开者
+    openers: VecDeque<Arc<TaskControlBlock>>,
+    // 等待数据的读者
+    readers: VecDeque<Arc<TaskControlBlock>>,
+    // 等待空间的写者
+    writers: VecDeque<Arc<TaskControlBlock>>,
+    // 正在 poll 这个管道的线程，读写或者关闭一端时被唤醒
+    pollers: VecDeque<Arc<TaskControlBlock>>,
 }
 
 impl PipeRingBuffer {
     pub fn new() -> Self {
         Self {
-            arr: [0; RING_BUFFER_SIZE],
+            arr: vec![0; PIPE_BUFFER_SIZE],
             head: 0,
-            tail: 0,
-            status: RingBufferStatus::Empty,
-            write_end: None,
+            len: 0,
+            read_ends: 0,
+            write_ends: 0,
+            read_opens: 0,
+            write_opens: 0,
+            openers: VecDeque::new(),
+            readers: VecDeque::new(),
+            writers: VecDeque::new(),
+            pollers: VecDeque::new(),
         }
     }
 
-    pub fn set_write_end(&mut self, write_end: &Arc<Pipe>) {
-        self.write_end = Some(Arc::downgrade(write_end));
+    /// 把队列中的数据复制到 dst，队尾绕回数组开头时分两段复制，返回复制的字节数
+    pub fn read_into(&mut self, dst: &mut [u8]) -> usize {
+        let n = dst.len().min(self.len);
+        let first = n.min(PIPE_BUFFER_SIZE - self.head);
+        dst[..first].copy_from_slice(&self.arr[self.head..self.head + first]);
+        dst[first..n].copy_from_slice(&self.arr[..n - first]);
+        self.head = (self.head + n) % PIPE_BUFFER_SIZE;
+        self.len -= n;
+        n
     }
 
-    /// 从管道符中读取一个字节，将读端和写端的文件描述符写回到应用地址空间
-    /// 仅仅通过比较队头和队尾是否相同不能确定循环队列是否为空，因为它既有可能表示队列为空，也有可能表示队列已满。
-    /// 因此需要在 read_byte 的同时进行状态更新。
-    pub fn read_byte(&mut self) -> u8 {
-        self.status = RingBufferStatus::Normal;
-        let c = self.arr[self.head];
-        // 更新循环队列队头的位置，并比较队头和队尾是否相同，如果相同的话则说明管道的状态变为空 EMPTY
-        self.head = (self.head + 1) % RING_BUFFER_SIZE;
-        if self.head == self.tail {
-            self.status = RingBufferStatus::Empty;
-        }
-        c
-    }
-
-    pub fn write_byte(&mut self, byte: u8) {
-        self.status = RingBufferStatus::Normal;
-        self.arr[self.tail] = byte;
-        self.tail = (self.tail + 1) % RING_BUFFER_SIZE;
-        if self.tail == self.head {
-            self.status = RingBufferStatus::Full;
-        }
+    /// 把 src 中的数据复制到队尾，最多填满队列，返回复制的字节数
+    pub fn write_from(&mut self, src: &[u8]) -> usize {
+        let n = src.len().min(self.available_write());
+        let tail = (self.head + self.len) % PIPE_BUFFER_SIZE;
+        let first = n.min(PIPE_BUFFER_SIZE - tail);
+        self.arr[tail..tail + first].copy_from_slice(&src[..first]);
+        self.arr[..n - first].copy_from_slice(&src[first..n]);
+        self.len += n;
+        n
     }
 
     /// 计算管道中还有多少个字符可以读取
-    /// 因为队头和队尾相等可能表示队列为空或队列已满
-    /// 如果队列为空的话直接返回 0，否则根据队头和队尾的相对位置进行计算
     pub fn available_read(&self) -> usize {
-        // 首先判断队列是否为空
-        if self.status == RingBufferStatus::Empty {
-            0
-        } else {
-            if self.tail > self.head {
-                self.tail - self.head
-            } else {
-                self.tail + RING_BUFFER_SIZE - self.head
-            }
-        }
+        self.len
+    }
+
+    pub fn available_write(&self) -> usize {
+        PIPE_BUFFER_SIZE - self.len
     }
 
     /// 判断管道的所有写端是否都被关闭了
-    /// 通过尝试将管道中保存的写端的弱引用计数升级为强引用计数来实现的
-    /// 如果升级失败的话，说明管道写端的强引用计数为 0 ，也就意味着管道所有写端都被关闭了，
-    /// 从而管道中的数据不会再得到补充，待管道中仅剩的数据被读取完毕之后，管道就可以被销毁了。
+    /// 如果是的话，管道中的数据不会再得到补充，待管道中仅剩的数据被读取完毕之后，管道就可以被销毁了。
     pub fn all_write_ends_closed(&self) -> bool {
-        self.write_end.as_ref().unwrap().upgrade().is_none()
+        self.write_ends == 0
     }
 
-    pub fn available_write(&self) -> usize {
-        if self.status == RingBufferStatus::Full {
-            0
-        } else {
-            RING_BUFFER_SIZE - self.available_read()
-        }
+    /// 判断管道的所有读端是否都被关闭了，此时写入的数据不会再被读取
+    pub fn all_read_ends_closed(&self) -> bool {
+        self.read_ends == 0
+    }
+
+    /// 读端和写端累计被打开的次数
+    pub fn opens(&self) -> (usize, usize) {
+        (self.read_opens, self.write_opens)
+    }
+
+    /// 当前线程阻塞在 open 中，直到管道的某一端被打开
+    pub fn wait_for_opener(buffer: &UPSafeCell<Self>) {
+        buffer
+            .exclusive_access()
+            .openers
+            .push_back(current_task().unwrap());
+        block_current_and_run_next();
+    }
+
+    /// 读走了数据，唤醒等待空间的写者和正在 poll 的线程
+    fn notify_writers(&mut self) {
+        wake_all_waiters(&mut self.writers);
+        wake_all_waiters(&mut self.pollers);
+    }
+
+    /// 写入了数据，唤醒等待数据的读者和正在 poll 的线程
+    fn notify_readers(&mut self) {
+        wake_all_waiters(&mut self.readers);
+        wake_all_waiters(&mut self.pollers);
     }
 }
 
 impl Pipe {
-    pub fn read_end_with_buffer(buffer: Arc<UPSafeCell<PipeRingBuffer>>) -> Self {
+    /// 在 buffer 上打开新的一端，以读写方式打开的 FIFO 同时是读端和写端
+    /// 唤醒阻塞在 open 中等待这一端的 FIFO 打开者
+    pub fn new(buffer: Arc<UPSafeCell<PipeRingBuffer>>, readable: bool, writeable: bool) -> Self {
+        let mut ring_buffer = buffer.exclusive_access();
+        if readable {
+            ring_buffer.read_ends += 1;
+            ring_buffer.read_opens = ring_buffer.read_opens.wrapping_add(1);
+        }
+        if writeable {
+            ring_buffer.write_ends += 1;
+            ring_buffer.write_opens = ring_buffer.write_opens.wrapping_add(1);
+        }
+        wake_all_waiters(&mut ring_buffer.openers);
+        drop(ring_buffer);
         Self {
-            readable: true,
-            writeable: false,
+            readable,
+            writeable,
             buffer,
+            nonblock: AtomicBool::new(false),
         }
     }
 
+    pub fn read_end_with_buffer(buffer: Arc<UPSafeCell<PipeRingBuffer>>) -> Self {
+        Self::new(buffer, true, false)
+    }
+
     pub fn write_end_with_buffer(buffer: Arc<UPSafeCell<PipeRingBuffer>>) -> Self {
-        Self {
-            readable: false,
-            writeable: true,
-            buffer,
-        }
+        Self::new(buffer, false, true)
     }
 }
 
@@ -138,7 +171,6 @@ pub fn make_pipe() -> (Arc<Pipe>, Arc<Pipe>) {
     let buffer = Arc::new(unsafe { UPSafeCell::new(PipeRingBuffer::new()) });
     let read_end = Arc::new(Pipe::read_end_with_buffer(buffer.clone()));
     let write_end = Arc::new(Pipe::write_end_with_buffer(buffer.clone()));
-    buffer.exclusive_access().set_write_end(&write_end);
     (read_end, write_end)
 }
 
@@ -152,70 +184,139 @@ impl File for Pipe {
     }
 
     /// 从文件中最多读取应用缓冲区大小那么多字符。这可能超出了循环队列的大小，或者由于尚未有进程从管道的写端写入足够
-    /// 的字符，因此我们需要将整个读取的过程放在一个循环中，当循环队列中不存在足够字符的时候暂时进行任务切换，等待循
-    /// 环队列中的字符得到补充之后再继续读取。
+    /// 的字符，因此我们需要将整个读取的过程放在一个循环中，当循环队列中不存在足够字符的时候在等待队列中阻塞，
+    /// 等待写者补充字符之后再继续读取。所有写端都关闭后返回已经读到的字节数。
     fn read(&self, buf: UserBuffer) -> usize {
         assert!(self.readable);
         let want_to_read = buf.len();
-        // 将传入的应用缓冲区的 buf 转化为一个能够逐字节对于缓冲区进行访问的迭代器，
-        let mut buff_iter = buf.into_iter();
+        let mut buffers = buf.buffers;
+        // 当前正在填充的应用缓冲区片段，以及片段内的偏移
+        let mut index = 0usize;
+        let mut offset = 0usize;
         // 用来维护实际有多少字节从管道读入应用的缓冲区
         let mut already_read = 0usize;
         loop {
             let mut ring_buffer = self.buffer.exclusive_access();
-            let loop_read = ring_buffer.available_read();
-            if loop_read == 0 {
-                if ring_buffer.all_write_ends_closed() {
-                    return already_read;
+            let mut loop_read = 0usize;
+            while index < buffers.len() && ring_buffer.available_read() > 0 {
+                let n = ring_buffer.read_into(&mut buffers[index][offset..]);
+                offset += n;
+                loop_read += n;
+                if offset == buffers[index].len() {
+                    index += 1;
+                    offset = 0;
                 }
-                drop(ring_buffer);
-                suspend_current_and_run_next();
-                continue;
             }
-            // 如果 loop_read 不为 0 ，在这一轮次中管道中就有 loop_read 个字节可以读取
-            for _ in 0..loop_read {
-                // 按顺序取出用于访问缓冲区中一个字节的裸指针
-                if let Some(byte_ref) = buff_iter.next() {
-                    unsafe {
-                        // 从管道中读取
-                        *byte_ref = ring_buffer.read_byte();
-                    }
-                    already_read += 1;
-                    if already_read == want_to_read {
-                        return want_to_read;
-                    }
-                } else {
-                    return already_read;
-                }
+            already_read += loop_read;
+            if loop_read > 0 {
+                ring_buffer.notify_writers();
             }
+            // 非阻塞模式下只返回已经读到的数据
+            if already_read == want_to_read
+                || ring_buffer.all_write_ends_closed()
+                || self.nonblock()
+            {
+                return already_read;
+            }
+            ring_buffer.readers.push_back(current_task().unwrap());
+            drop(ring_buffer);
+            block_current_and_run_next();
         }
     }
 
+    /// 把应用缓冲区中的数据全部写入管道，管道满时在等待队列中阻塞
+    /// 所有读端都关闭后返回已经写入的字节数，一个字节都没有写入时由 sys_write 返回 EPIPE
     fn write(&self, buf: UserBuffer) -> usize {
         assert!(self.writable());
         let want_to_write = buf.len();
-        let mut buf_iter = buf.into_iter();
+        let buffers = buf.buffers;
+        let mut index = 0usize;
+        let mut offset = 0usize;
         let mut already_write = 0usize;
         loop {
             let mut ring_buffer = self.buffer.exclusive_access();
-            let loop_write = ring_buffer.available_write();
-            if loop_write == 0 {
-                drop(ring_buffer);
-                suspend_current_and_run_next();
-                continue;
+            if ring_buffer.all_read_ends_closed() {
+                return already_write;
             }
-            // write at most loop_write bytes
-            for _ in 0..loop_write {
-                if let Some(byte_ref) = buf_iter.next() {
-                    ring_buffer.write_byte(unsafe { *byte_ref });
-                    already_write += 1;
-                    if already_write == want_to_write {
-                        return want_to_write;
-                    }
-                } else {
-                    return already_write;
+            let mut loop_write = 0usize;
+            while index < buffers.len() && ring_buffer.available_write() > 0 {
+                let n = ring_buffer.write_from(&buffers[index][offset..]);
+                offset += n;
+                loop_write += n;
+                if offset == buffers[index].len() {
+                    index += 1;
+                    offset = 0;
                 }
             }
+            already_write += loop_write;
+            if loop_write > 0 {
+                ring_buffer.notify_readers();
+            }
+            // 非阻塞模式下管道满了就返回已经写入的字节数
+            if already_write == want_to_write || self.nonblock() {
+                return already_write;
+            }
+            ring_buffer.writers.push_back(current_task().unwrap());
+            drop(ring_buffer);
+            block_current_and_run_next();
+        }
+    }
+
+    /// 读端有数据可读时可读，所有写端都关闭后返回 HUP；
+    /// 写端有空间时可写，所有读端都关闭后返回 ERR
+    fn poll(&self) -> PollEvents {
+        let ring_buffer = self.buffer.exclusive_access();
+        let mut events = PollEvents::empty();
+        if self.readable {
+            if ring_buffer.available_read() > 0 {
+                events |= PollEvents::IN;
+            }
+            if ring_buffer.all_write_ends_closed() {
+                events |= PollEvents::HUP;
+            }
+        }
+        if self.writeable {
+            if ring_buffer.all_read_ends_closed() {
+                events |= PollEvents::ERR;
+            } else if ring_buffer.available_write() > 0 {
+                events |= PollEvents::OUT;
+            }
+        }
+        events
+    }
+
+    fn register_poller(&self, task: &Arc<TaskControlBlock>) -> bool {
+        self.buffer
+            .exclusive_access()
+            .pollers
+            .push_back(Arc::clone(task));
+        true
+    }
+
+    fn unregister_poller(&self, task: &Arc<TaskControlBlock>) {
+        remove_waiter(&mut self.buffer.exclusive_access().pollers, task);
+    }
+
+    fn nonblock(&self) -> bool {
+        self.nonblock.load(Ordering::Relaxed)
+    }
+
+    fn set_nonblock(&self, nonblock: bool) {
+        self.nonblock.store(nonblock, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Pipe {
+    /// 关闭一端时唤醒另一端的所有等待者：读者读到文件结尾，写者得到 EPIPE，poll 看到 HUP 或 ERR
+    fn drop(&mut self) {
+        let mut ring_buffer = self.buffer.exclusive_access();
+        if self.readable {
+            ring_buffer.read_ends -= 1;
+        }
+        if self.writeable {
+            ring_buffer.write_ends -= 1;
         }
+        ring_buffer.notify_readers();
+        ring_buffer.notify_writers();
     }
 }
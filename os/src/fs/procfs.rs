@@ -0,0 +1,339 @@
+//! procfs: 只读的虚拟文件系统，用于从用户态查看进程和内核的状态
+//!
+//! 文件内容在打开时生成，之后的读取都返回同一份快照
+//!
+//! ```text
+//! /proc                 进程号列表以及全局文件名
+//! /proc/<pid>           进程目录下的文件名
+//! /proc/<pid>/status    进程名、父进程、状态、线程数与 CPU 时间
+//! /proc/<pid>/threads   每个线程的状态与资源使用统计
+//! /proc/<pid>/fd        文件描述符表
+//! /proc/<pid>/maps      地址空间中的逻辑段
+//! /proc/meminfo         物理页帧与内核堆的使用情况
+//! /proc/uptime          启动以来的时间与空闲时间
+//! /proc/blockcache      块缓存统计
+//! /proc/sched           就绪队列
+//! /proc/syscalls        每个系统调用的调用次数
+//! ```
+use super::{File, OpenFlags};
+use crate::config::PAGE_SIZE;
+use crate::errno::{EACCES, ENOENT};
+use crate::mm::{frame_stats, heap_stats, MapPermission, UserBuffer};
+use crate::sync::UPSafeCell;
+use crate::syscall::syscall_stats;
+use crate::task::{idle_time, pid2process, ready_tasks, ProcessControlBlock, TaskStatus, INITPROC};
+use crate::timer::{get_time, ticks_to_us};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use easy_fs::block_cache_stats;
+
+/// 全局文件
+const GLOBAL_FILES: [&str; 5] = ["meminfo", "uptime", "blockcache", "sched", "syscalls"];
+/// 每个进程目录下的文件
+const PROCESS_FILES: [&str; 4] = ["status", "threads", "fd", "maps"];
+
+/// procfs 中被打开的文件，保存打开时生成的内容
+pub struct ProcFile {
+    data: Vec<u8>,
+    offset: UPSafeCell<usize>,
+}
+
+impl ProcFile {
+    fn new(data: String) -> Self {
+        Self {
+            data: data.into_bytes(),
+            offset: unsafe { UPSafeCell::new(0) },
+        }
+    }
+}
+
+impl File for ProcFile {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, mut buf: UserBuffer) -> usize {
+        let mut offset = self.offset.exclusive_access();
+        let mut total_read_size = 0usize;
+        for slice in buf.buffers.iter_mut() {
+            let len = slice.len().min(self.data.len() - *offset);
+            if len == 0 {
+                break;
+            }
+            slice[..len].copy_from_slice(&self.data[*offset..*offset + len]);
+            *offset += len;
+            total_read_size += len;
+        }
+        total_read_size
+    }
+    fn write(&self, _buf: UserBuffer) -> usize {
+        panic!("Cannot write to procfs!");
+    }
+}
+
+/// 路径是否位于 /proc 下
+pub fn is_proc_path(path: &str) -> bool {
+    let path = path.trim_start_matches('/');
+    path == "proc" || path.starts_with("proc/")
+}
+
+/// 打开 procfs 中的文件，只允许只读打开，失败时返回负的错误码
+pub fn open_proc(path: &str, flags: OpenFlags) -> Result<Arc<ProcFile>, isize> {
+    if !flags.is_empty() {
+        return Err(-EACCES);
+    }
+    let components: Vec<&str> = path
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .skip(1)
+        .collect();
+    let content = match components.as_slice() {
+        [] => proc_root(),
+        ["meminfo"] => meminfo(),
+        ["uptime"] => uptime(),
+        ["blockcache"] => blockcache(),
+        ["sched"] => sched(),
+        ["syscalls"] => syscalls(),
+        [pid] => {
+            find_process(pid).ok_or(-ENOENT)?;
+            list(&PROCESS_FILES)
+        }
+        [pid, name] => {
+            let process = find_process(pid).ok_or(-ENOENT)?;
+            match *name {
+                "status" => status(&process),
+                "threads" => threads(&process),
+                "fd" => fd_table(&process),
+                "maps" => maps(&process),
+                _ => return Err(-ENOENT),
+            }
+        }
+        _ => return Err(-ENOENT),
+    };
+    Ok(Arc::new(ProcFile::new(content)))
+}
+
+/// 从 initproc 开始遍历进程树，包括尚未被回收的僵尸进程
+fn all_processes() -> Vec<Arc<ProcessControlBlock>> {
+    let mut processes = Vec::new();
+    let mut stack = alloc::vec![INITPROC.clone()];
+    while let Some(process) = stack.pop() {
+        stack.extend(process.inner_exclusive_access().children.iter().cloned());
+        processes.push(process);
+    }
+    processes.sort_by_key(|process| process.getpid());
+    processes
+}
+
+fn find_process(pid: &str) -> Option<Arc<ProcessControlBlock>> {
+    let pid: usize = pid.parse().ok()?;
+    pid2process(pid).or_else(|| {
+        all_processes()
+            .into_iter()
+            .find(|process| process.getpid() == pid)
+    })
+}
+
+fn ticks_to_ms(ticks: usize) -> usize {
+    ticks_to_us(ticks) / 1000
+}
+
+fn status_char(status: TaskStatus) -> char {
+    match status {
+        TaskStatus::Ready => 'R',
+        TaskStatus::Running => 'R',
+        TaskStatus::Blocked => 'S',
+    }
+}
+
+fn list(names: &[&str]) -> String {
+    let mut s = String::new();
+    for name in names {
+        writeln!(s, "{}", name).unwrap();
+    }
+    s
+}
+
+fn proc_root() -> String {
+    let mut s = String::new();
+    for process in all_processes() {
+        writeln!(s, "{}", process.getpid()).unwrap();
+    }
+    s + &list(&GLOBAL_FILES)
+}
+
+fn status(process: &Arc<ProcessControlBlock>) -> String {
+    let inner = process.inner_exclusive_access();
+    let ppid = inner
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade())
+        .map_or(0, |parent| parent.getpid());
+    // 只要有一个线程可以运行，进程就处于运行状态
+    let state = if inner.is_zombie {
+        'Z'
+    } else if inner
+        .tasks
+        .iter()
+        .flatten()
+        .any(|task| status_char(task.inner_exclusive_access().task_status) == 'R')
+    {
+        'R'
+    } else {
+        'S'
+    };
+    let usage = inner.usage();
+    let mut s = String::new();
+    writeln!(s, "Name:\t{}", inner.name).unwrap();
+    writeln!(s, "Pid:\t{}", process.getpid()).unwrap();
+    writeln!(s, "PPid:\t{}", ppid).unwrap();
+    writeln!(s, "State:\t{}", state).unwrap();
+    writeln!(s, "Uid:\t{}\t{}", inner.cred.uid, inner.cred.euid).unwrap();
+    writeln!(s, "Gid:\t{}\t{}", inner.cred.gid, inner.cred.egid).unwrap();
+    writeln!(s, "Threads:\t{}", inner.live_thread_count()).unwrap();
+    writeln!(s, "FDSize:\t{}", inner.fd_table.iter().flatten().count()).unwrap();
+    writeln!(s, "VmSize:\t{} kB", inner.memory_set.mapped_size() / 1024).unwrap();
+    writeln!(s, "Utime:\t{} ms", ticks_to_ms(usage.utime)).unwrap();
+    writeln!(s, "Stime:\t{} ms", ticks_to_ms(usage.stime)).unwrap();
+    if inner.is_zombie {
+        writeln!(s, "ExitCode:\t{}", inner.exit_code).unwrap();
+    }
+    s
+}
+
+fn threads(process: &Arc<ProcessControlBlock>) -> String {
+    let inner = process.inner_exclusive_access();
+    let mut s = String::from("TID\tSTATE\tUTIME\tSTIME\tNVCSW\tNIVCSW\tPRIO\n");
+    for (tid, task) in inner.tasks.iter().enumerate() {
+        let task_inner = match task {
+            Some(task) => task.inner_exclusive_access(),
+            None => continue,
+        };
+        // 已经退出但尚未被 waittid 回收的线程
+        let state = if task_inner.exit_code.is_some() {
+            'Z'
+        } else {
+            status_char(task_inner.task_status)
+        };
+        let usage = task_inner.usage;
+        writeln!(
+            s,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            tid,
+            state,
+            ticks_to_ms(usage.utime),
+            ticks_to_ms(usage.stime),
+            usage.nvcsw,
+            usage.nivcsw,
+            task_inner.effective_priority
+        )
+        .unwrap();
+    }
+    s
+}
+
+fn fd_table(process: &Arc<ProcessControlBlock>) -> String {
+    let inner = process.inner_exclusive_access();
+    let mut s = String::new();
+    for (fd, file) in inner.fd_table.iter().enumerate() {
+        if let Some(file) = file {
+            let r = if file.readable() { 'r' } else { '-' };
+            let w = if file.writable() { 'w' } else { '-' };
+            writeln!(s, "{}\t{}{}", fd, r, w).unwrap();
+        }
+    }
+    s
+}
+
+fn maps(process: &Arc<ProcessControlBlock>) -> String {
+    let inner = process.inner_exclusive_access();
+    let mut s = String::new();
+    for (start, end, perm) in inner.memory_set.maps() {
+        let flag = |bit: MapPermission, c: char| if perm.contains(bit) { c } else { '-' };
+        writeln!(
+            s,
+            "{:#x}-{:#x}\t{}{}{}{}",
+            usize::from(start),
+            usize::from(end),
+            flag(MapPermission::R, 'r'),
+            flag(MapPermission::W, 'w'),
+            flag(MapPermission::X, 'x'),
+            flag(MapPermission::U, 'u')
+        )
+        .unwrap();
+    }
+    s
+}
+
+fn meminfo() -> String {
+    let (free_frames, total_frames) = frame_stats();
+    let (heap_used, heap_total) = heap_stats();
+    let mut s = String::new();
+    writeln!(s, "MemTotal:\t{} kB", total_frames * PAGE_SIZE / 1024).unwrap();
+    writeln!(s, "MemFree:\t{} kB", free_frames * PAGE_SIZE / 1024).unwrap();
+    writeln!(s, "HeapTotal:\t{} kB", heap_total / 1024).unwrap();
+    writeln!(s, "HeapUsed:\t{} kB", heap_used / 1024).unwrap();
+    s
+}
+
+fn uptime() -> String {
+    let uptime = ticks_to_ms(get_time());
+    let idle = ticks_to_ms(idle_time());
+    let mut s = String::new();
+    writeln!(
+        s,
+        "{}.{:03} {}.{:03}",
+        uptime / 1000,
+        uptime % 1000,
+        idle / 1000,
+        idle % 1000
+    )
+    .unwrap();
+    s
+}
+
+fn blockcache() -> String {
+    let stats = block_cache_stats();
+    let mut s = String::new();
+    writeln!(s, "Capacity:\t{}", stats.capacity).unwrap();
+    writeln!(s, "Cached:\t{}", stats.cached).unwrap();
+    writeln!(s, "Dirty:\t{}", stats.dirty).unwrap();
+    writeln!(s, "Hits:\t{}", stats.hits).unwrap();
+    writeln!(s, "Misses:\t{}", stats.misses).unwrap();
+    s
+}
+
+fn sched() -> String {
+    let tasks = ready_tasks();
+    let mut s = String::new();
+    writeln!(s, "Ready:\t{}", tasks.len()).unwrap();
+    writeln!(s, "PID\tTID").unwrap();
+    for task in tasks {
+        let pid = task.process.upgrade().map_or(0, |process| process.getpid());
+        let task_inner = task.inner_exclusive_access();
+        if let Some(res) = task_inner.res.as_ref() {
+            writeln!(s, "{}\t{}", pid, res.tid).unwrap();
+        }
+    }
+    s
+}
+
+fn syscalls() -> String {
+    let (stats, unknown) = syscall_stats();
+    let mut s = String::new();
+    writeln!(s, "Unknown:\t{}", unknown).unwrap();
+    writeln!(s, "NR\tNAME\tARGS\tCALLS").unwrap();
+    for stat in stats {
+        writeln!(
+            s,
+            "{}\t{}\t{}\t{}",
+            stat.id, stat.name, stat.nargs, stat.calls
+        )
+        .unwrap();
+    }
+    s
+}
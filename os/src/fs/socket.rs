@@ -0,0 +1,543 @@
+//! 本地（Unix 域）套接字
+//!
+//! 支持流式和数据报两种类型。socketpair 创建一对已经连接的套接字；也可以把套接字
+//! bind 到 easy-fs 根目录下的路径上，其他进程通过这个路径 connect，不需要有共同的祖先进程。
+//! 发送数据时可以附带文件，接收方在自己的文件描述符表中得到指向同一个文件的新描述符
+
+use super::{bind_socket_inode, check_socket_inode, File, PollEvents};
+use crate::errno::*;
+use crate::mm::UserBuffer;
+use crate::sync::{remove_waiter, take_waiter, wake_all_waiters, UPSafeCell};
+use crate::task::{
+    block_current_and_run_next, current_task, wakeup_task, Credentials, TaskControlBlock,
+};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// 本地套接字的地址族
+pub const AF_UNIX: usize = 1;
+/// 面向连接的字节流
+pub const SOCK_STREAM: usize = 1;
+/// 保留边界的数据报
+pub const SOCK_DGRAM: usize = 2;
+/// 一次最多传递的文件数量
+pub const SCM_MAX_FD: usize = 8;
+/// listen 的 backlog 上限
+const SOMAXCONN: usize = 16;
+/// 每个套接字接收缓冲区的字节数
+const SOCKET_BUFFER_SIZE: usize = 4096;
+
+type FileRef = Arc<dyn File + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketType {
+    Stream,
+    Datagram,
+}
+
+impl SocketType {
+    pub fn from_raw(type_: usize) -> Option<Self> {
+        match type_ {
+            SOCK_STREAM => Some(Self::Stream),
+            SOCK_DGRAM => Some(Self::Datagram),
+            _ => None,
+        }
+    }
+}
+
+/// 一次发送的数据以及附带的文件
+struct Packet {
+    data: Vec<u8>,
+    /// 流式套接字中已经被读取的字节数
+    offset: usize,
+    files: Vec<FileRef>,
+}
+
+/// 套接字的接收缓冲区，对端发送的数据直接放入其中
+struct Channel {
+    inner: UPSafeCell<ChannelInner>,
+}
+
+struct ChannelInner {
+    packets: VecDeque<Packet>,
+    /// 缓冲区中尚未读取的字节数
+    bytes: usize,
+    /// 流式套接字的对端已经关闭，读完剩余的数据后读到文件结尾
+    write_closed: bool,
+    /// 接收方已经关闭，再发送返回错误
+    read_closed: bool,
+    /// 等待数据的线程
+    readers: VecDeque<Arc<TaskControlBlock>>,
+    /// 等待缓冲区空间的线程
+    writers: VecDeque<Arc<TaskControlBlock>>,
+    /// 正在 poll 的线程，包括接收方和以这个缓冲区为对端的发送方
+    pollers: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl ChannelInner {
+    fn push(&mut self, data: Vec<u8>, files: Vec<FileRef>) {
+        self.bytes += data.len();
+        self.packets.push_back(Packet {
+            data,
+            offset: 0,
+            files,
+        });
+        if let Some(task) = take_waiter(&mut self.readers) {
+            wakeup_task(task);
+        }
+        wake_all_waiters(&mut self.pollers);
+    }
+}
+
+impl Channel {
+    fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(ChannelInner {
+                    packets: VecDeque::new(),
+                    bytes: 0,
+                    write_closed: false,
+                    read_closed: false,
+                    readers: VecDeque::new(),
+                    writers: VecDeque::new(),
+                    pollers: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// 当前线程加入 waiters 选出的等待队列并阻塞，直到被唤醒
+    fn wait(&self, waiters: fn(&mut ChannelInner) -> &mut VecDeque<Arc<TaskControlBlock>>) {
+        waiters(&mut self.inner.exclusive_access()).push_back(current_task().unwrap());
+        block_current_and_run_next();
+    }
+
+    /// 流式发送，数据可以分多次放入缓冲区，缓冲区满时阻塞，附带的文件跟随第一段数据
+    /// 返回发送的字节数，接收方已经关闭并且还没有发送任何数据时返回 EPIPE
+    /// 附带文件时至少要发送一个字节，否则接收方读到 0 字节会当作文件结尾，返回 EINVAL
+    fn send_stream(&self, data: &[u8], mut files: Vec<FileRef>) -> Result<usize, isize> {
+        if data.is_empty() && !files.is_empty() {
+            return Err(-EINVAL);
+        }
+        let mut sent = 0;
+        while sent < data.len() {
+            let mut inner = self.inner.exclusive_access();
+            if inner.read_closed {
+                return if sent > 0 { Ok(sent) } else { Err(-EPIPE) };
+            }
+            let space = SOCKET_BUFFER_SIZE - inner.bytes;
+            if space == 0 {
+                drop(inner);
+                self.wait(|inner| &mut inner.writers);
+                continue;
+            }
+            let len = space.min(data.len() - sent);
+            inner.push(data[sent..sent + len].to_vec(), core::mem::take(&mut files));
+            sent += len;
+        }
+        Ok(sent)
+    }
+
+    /// 数据报发送，缓冲区放不下整个数据报时阻塞
+    /// 数据报比缓冲区还大时返回 EMSGSIZE，接收方已经关闭时返回 ECONNREFUSED
+    fn send_datagram(&self, data: &[u8], files: Vec<FileRef>) -> Result<usize, isize> {
+        if data.len() > SOCKET_BUFFER_SIZE {
+            return Err(-EMSGSIZE);
+        }
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if inner.read_closed {
+                return Err(-ECONNREFUSED);
+            }
+            if inner.bytes + data.len() <= SOCKET_BUFFER_SIZE {
+                inner.push(data.to_vec(), files);
+                return Ok(data.len());
+            }
+            drop(inner);
+            self.wait(|inner| &mut inner.writers);
+        }
+    }
+
+    /// 接收数据写入 buf，没有数据时阻塞，返回读取的字节数和附带的文件
+    /// 流式套接字的对端已经关闭并且没有剩余数据时返回 0
+    /// 流式套接字一次可以读取多段数据，但不会越过附带文件的数据段；
+    /// 数据报套接字一次读取一个数据报，超出 buf 的部分被丢弃
+    fn recv(&self, buf: UserBuffer, kind: SocketType) -> (usize, Vec<FileRef>) {
+        let mut inner = loop {
+            let inner = self.inner.exclusive_access();
+            if !inner.packets.is_empty() || inner.write_closed {
+                break inner;
+            }
+            drop(inner);
+            self.wait(|inner| &mut inner.readers);
+        };
+        let mut dst = buf.into_iter();
+        let mut read = 0;
+        let mut files = Vec::new();
+        match kind {
+            SocketType::Datagram => {
+                if let Some(packet) = inner.packets.pop_front() {
+                    inner.bytes -= packet.data.len();
+                    for (byte_ref, &byte) in dst.zip(packet.data.iter()) {
+                        unsafe {
+                            *byte_ref = byte;
+                        }
+                        read += 1;
+                    }
+                    files = packet.files;
+                }
+            }
+            SocketType::Stream => {
+                while let Some(packet) = inner.packets.front_mut() {
+                    if read > 0 && !packet.files.is_empty() {
+                        break;
+                    }
+                    files.append(&mut packet.files);
+                    while packet.offset < packet.data.len() {
+                        match dst.next() {
+                            Some(byte_ref) => {
+                                unsafe {
+                                    *byte_ref = packet.data[packet.offset];
+                                }
+                                packet.offset += 1;
+                                read += 1;
+                            }
+                            None => break,
+                        }
+                    }
+                    if packet.offset < packet.data.len() {
+                        break;
+                    }
+                    inner.packets.pop_front();
+                }
+                inner.bytes -= read;
+            }
+        }
+        // 空出的空间可能够多个发送者使用，让它们重新检查
+        wake_all_waiters(&mut inner.writers);
+        wake_all_waiters(&mut inner.pollers);
+        (read, files)
+    }
+
+    /// 发送方关闭，阻塞的接收者读到文件结尾
+    fn close_write(&self) {
+        let mut inner = self.inner.exclusive_access();
+        inner.write_closed = true;
+        wake_all_waiters(&mut inner.readers);
+        wake_all_waiters(&mut inner.pollers);
+    }
+
+    /// 接收方关闭，丢弃尚未读取的数据，阻塞的发送者返回错误
+    fn close_read(&self) {
+        let mut inner = self.inner.exclusive_access();
+        inner.read_closed = true;
+        inner.bytes = 0;
+        let packets = core::mem::take(&mut inner.packets);
+        wake_all_waiters(&mut inner.writers);
+        wake_all_waiters(&mut inner.pollers);
+        drop(inner);
+        // 数据中附带的文件可能是以这个缓冲区为对端的套接字，释放借用后再关闭它们
+        drop(packets);
+    }
+}
+
+lazy_static! {
+    /// 已经绑定的路径，套接字关闭后自动解除绑定
+    static ref SOCKET_NAMESPACE: UPSafeCell<BTreeMap<String, Weak<Socket>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+/// 本地套接字
+pub struct Socket {
+    kind: SocketType,
+    /// 指向自身，绑定路径时放入命名空间
+    this: Weak<Socket>,
+    /// 本端的接收缓冲区
+    rx: Arc<Channel>,
+    inner: UPSafeCell<SocketInner>,
+}
+
+struct SocketInner {
+    /// 绑定的路径
+    path: Option<String>,
+    /// 调用 listen 之后为等待 accept 的连接数量上限
+    backlog: Option<usize>,
+    /// 已经建立、等待 accept 的连接
+    pending: VecDeque<Arc<Socket>>,
+    /// 阻塞在 accept 中的线程
+    accepters: VecDeque<Arc<TaskControlBlock>>,
+    /// 正在 poll 的线程，有新的连接时被唤醒
+    pollers: VecDeque<Arc<TaskControlBlock>>,
+    /// 对端的接收缓冲区，流式套接字连接后、数据报套接字 connect 后才有
+    peer: Option<Arc<Channel>>,
+}
+
+impl Socket {
+    pub fn new(kind: SocketType) -> Arc<Self> {
+        Arc::new_cyclic(|this| Self {
+            kind,
+            this: this.clone(),
+            rx: Arc::new(Channel::new()),
+            inner: unsafe {
+                UPSafeCell::new(SocketInner {
+                    path: None,
+                    backlog: None,
+                    pending: VecDeque::new(),
+                    accepters: VecDeque::new(),
+                    pollers: VecDeque::new(),
+                    peer: None,
+                })
+            },
+        })
+    }
+
+    /// 创建一对互相连接的套接字
+    pub fn pair(kind: SocketType) -> (Arc<Self>, Arc<Self>) {
+        let a = Self::new(kind);
+        let b = Self::new(kind);
+        a.inner.exclusive_access().peer = Some(Arc::clone(&b.rx));
+        b.inner.exclusive_access().peer = Some(Arc::clone(&a.rx));
+        (a, b)
+    }
+
+    /// 绑定到根目录下的 path，在文件系统中创建套接字节点
+    /// 套接字已经绑定时返回 EINVAL，path 正在被其他套接字使用时返回 EADDRINUSE，
+    /// 不能创建节点时返回 bind_socket_inode 的错误码
+    pub fn bind(&self, path: &str, cred: &Credentials) -> Result<(), isize> {
+        if self.inner.exclusive_access().path.is_some() {
+            return Err(-EINVAL);
+        }
+        let mut namespace = SOCKET_NAMESPACE.exclusive_access();
+        if namespace
+            .get(path)
+            .map_or(false, |socket| socket.upgrade().is_some())
+        {
+            return Err(-EADDRINUSE);
+        }
+        bind_socket_inode(path, cred)?;
+        namespace.insert(String::from(path), self.this.clone());
+        self.inner.exclusive_access().path = Some(String::from(path));
+        Ok(())
+    }
+
+    /// 开始接受连接，只有已经绑定、尚未连接的流式套接字可以调用
+    /// 数据报套接字返回 EOPNOTSUPP，没有绑定或者已经连接返回 EINVAL
+    pub fn listen(&self, backlog: usize) -> Result<(), isize> {
+        let mut inner = self.inner.exclusive_access();
+        if self.kind != SocketType::Stream {
+            return Err(-EOPNOTSUPP);
+        }
+        if inner.path.is_none() || inner.peer.is_some() {
+            return Err(-EINVAL);
+        }
+        inner.backlog = Some(backlog.clamp(1, SOMAXCONN));
+        Ok(())
+    }
+
+    /// 取出一个已经建立的连接，没有连接时阻塞，套接字没有在监听时返回 EINVAL
+    pub fn accept(&self) -> Result<Arc<Socket>, isize> {
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if inner.backlog.is_none() {
+                return Err(-EINVAL);
+            }
+            if let Some(socket) = inner.pending.pop_front() {
+                return Ok(socket);
+            }
+            inner.accepters.push_back(current_task().unwrap());
+            drop(inner);
+            block_current_and_run_next();
+        }
+    }
+
+    /// 连接到绑定在 path 上的套接字
+    /// 流式套接字的连接放入对方的 backlog，对方 accept 之前就可以发送数据；
+    /// 数据报套接字只是记下默认的发送目标，可以重新 connect
+    /// 已经连接的流式套接字返回 EISCONN，正在监听的返回 EINVAL，类型不同返回 EPROTOTYPE，
+    /// 对方没有在监听返回 ECONNREFUSED，backlog 已满返回 EAGAIN，节点的错误见 check_socket_inode
+    pub fn connect(&self, path: &str, cred: &Credentials) -> Result<(), isize> {
+        if self.kind == SocketType::Stream {
+            let inner = self.inner.exclusive_access();
+            if inner.peer.is_some() {
+                return Err(-EISCONN);
+            }
+            if inner.backlog.is_some() {
+                return Err(-EINVAL);
+            }
+        }
+        check_socket_inode(path, cred)?;
+        let target = match SOCKET_NAMESPACE
+            .exclusive_access()
+            .get(path)
+            .and_then(Weak::upgrade)
+        {
+            Some(target) => target,
+            None => return Err(-ECONNREFUSED),
+        };
+        if target.kind != self.kind {
+            return Err(-EPROTOTYPE);
+        }
+        let peer = match self.kind {
+            SocketType::Datagram => Arc::clone(&target.rx),
+            SocketType::Stream => {
+                let mut target_inner = target.inner.exclusive_access();
+                match target_inner.backlog {
+                    Some(backlog) if target_inner.pending.len() < backlog => {}
+                    Some(_) => return Err(-EAGAIN),
+                    None => return Err(-ECONNREFUSED),
+                }
+                // 由 accept 取出的服务端套接字
+                let server = Self::new(SocketType::Stream);
+                server.inner.exclusive_access().peer = Some(Arc::clone(&self.rx));
+                let peer = Arc::clone(&server.rx);
+                target_inner.pending.push_back(server);
+                if let Some(task) = take_waiter(&mut target_inner.accepters) {
+                    wakeup_task(task);
+                }
+                wake_all_waiters(&mut target_inner.pollers);
+                peer
+            }
+        };
+        self.inner.exclusive_access().peer = Some(peer);
+        Ok(())
+    }
+
+    /// 向对端发送数据并附带文件，返回发送的字节数
+    /// 流式套接字没有连接返回 ENOTCONN，数据报套接字没有 connect 返回 EDESTADDRREQ，
+    /// 其余错误见 Channel::send_stream 和 Channel::send_datagram
+    pub fn send(&self, data: &[u8], files: Vec<FileRef>) -> Result<usize, isize> {
+        let peer = match self.inner.exclusive_access().peer.clone() {
+            Some(peer) => peer,
+            None if self.kind == SocketType::Stream => return Err(-ENOTCONN),
+            None => return Err(-EDESTADDRREQ),
+        };
+        match self.kind {
+            SocketType::Stream => peer.send_stream(data, files),
+            SocketType::Datagram => peer.send_datagram(data, files),
+        }
+    }
+
+    /// 接收数据写入 buf，返回读取的字节数和附带的文件，没有连接的流式套接字返回 ENOTCONN
+    pub fn recv(&self, buf: UserBuffer) -> Result<(usize, Vec<FileRef>), isize> {
+        if self.kind == SocketType::Stream && self.inner.exclusive_access().peer.is_none() {
+            return Err(-ENOTCONN);
+        }
+        Ok(self.rx.recv(buf, self.kind))
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        let inner = self.inner.exclusive_access();
+        if self.kind == SocketType::Stream {
+            if let Some(peer) = inner.peer.as_ref() {
+                peer.close_write();
+            }
+        }
+        if let Some(path) = inner.path.as_ref() {
+            let mut namespace = SOCKET_NAMESPACE.exclusive_access();
+            if namespace
+                .get(path)
+                .map_or(false, |socket| socket.upgrade().is_none())
+            {
+                namespace.remove(path);
+            }
+        }
+        drop(inner);
+        self.rx.close_read();
+    }
+}
+
+impl File for Socket {
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    /// 接收数据，附带的文件被关闭，没有连接时返回 0
+    fn read(&self, buf: UserBuffer) -> usize {
+        self.recv(buf).map_or(0, |(len, _)| len)
+    }
+
+    /// 发送数据，出错时返回 0
+    fn write(&self, buf: UserBuffer) -> usize {
+        let data: Vec<u8> = buf
+            .into_iter()
+            .map(|byte_ref| unsafe { *byte_ref })
+            .collect();
+        self.send(&data, Vec::new()).unwrap_or(0)
+    }
+
+    fn as_socket(&self) -> Option<&Socket> {
+        Some(self)
+    }
+
+    /// 有数据、有等待 accept 的连接或者流式套接字的对端已经关闭时可读，
+    /// 对端的接收缓冲区有空间时可写，对端已经关闭时返回 ERR
+    fn poll(&self) -> PollEvents {
+        let mut events = PollEvents::empty();
+        let rx = self.rx.inner.exclusive_access();
+        if !rx.packets.is_empty() {
+            events |= PollEvents::IN;
+        }
+        if rx.write_closed {
+            events |= PollEvents::IN | PollEvents::HUP;
+        }
+        drop(rx);
+        let inner = self.inner.exclusive_access();
+        if !inner.pending.is_empty() {
+            events |= PollEvents::IN;
+        }
+        if let Some(peer) = inner.peer.as_ref() {
+            let peer = peer.inner.exclusive_access();
+            if peer.read_closed {
+                events |= PollEvents::ERR;
+            } else if peer.bytes < SOCKET_BUFFER_SIZE {
+                events |= PollEvents::OUT;
+            }
+        }
+        events
+    }
+
+    /// 同时等待本端的接收缓冲区、新的连接和对端的接收缓冲区
+    fn register_poller(&self, task: &Arc<TaskControlBlock>) -> bool {
+        self.rx
+            .inner
+            .exclusive_access()
+            .pollers
+            .push_back(Arc::clone(task));
+        let mut inner = self.inner.exclusive_access();
+        inner.pollers.push_back(Arc::clone(task));
+        // 连接到自己的数据报套接字只在接收缓冲区中登记一次
+        if let Some(peer) = inner
+            .peer
+            .as_ref()
+            .filter(|peer| !Arc::ptr_eq(peer, &self.rx))
+        {
+            peer.inner
+                .exclusive_access()
+                .pollers
+                .push_back(Arc::clone(task));
+        }
+        true
+    }
+
+    fn unregister_poller(&self, task: &Arc<TaskControlBlock>) {
+        remove_waiter(&mut self.rx.inner.exclusive_access().pollers, task);
+        let mut inner = self.inner.exclusive_access();
+        remove_waiter(&mut inner.pollers, task);
+        if let Some(peer) = inner
+            .peer
+            .as_ref()
+            .filter(|peer| !Arc::ptr_eq(peer, &self.rx))
+        {
+            remove_waiter(&mut peer.inner.exclusive_access().pollers, task);
+        }
+    }
+}
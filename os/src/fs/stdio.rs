@@ -1,13 +1,44 @@
 //!Stdin & Stdout
-use super::File;
+use super::{File, PollEvents};
 use crate::mm::UserBuffer;
 use crate::sbi::console_getchar;
+use crate::sync::UPSafeCell;
 use crate::task::suspend_current_and_run_next;
+use core::sync::atomic::{AtomicBool, Ordering};
+use lazy_static::*;
+
+lazy_static! {
+    /// poll 时从串口读到、还没有被 read 取走的字符
+    static ref STDIN_PENDING: UPSafeCell<Option<u8>> = unsafe { UPSafeCell::new(None) };
+}
+
+/// 取出一个输入字符，没有输入时返回 None
+fn stdin_getchar() -> Option<u8> {
+    if let Some(ch) = STDIN_PENDING.exclusive_access().take() {
+        return Some(ch);
+    }
+    match console_getchar() {
+        0 => None,
+        c => Some(c as u8),
+    }
+}
 ///Standard input
-pub struct Stdin;
+#[derive(Default)]
+pub struct Stdin {
+    /// 非阻塞模式，所有进程继承的是同一个 Stdin
+    nonblock: AtomicBool,
+}
 ///Standard output
 pub struct Stdout;
 
+impl Stdin {
+    pub fn new() -> Self {
+        Self {
+            nonblock: AtomicBool::new(false),
+        }
+    }
+}
+
 /// stdin 只读文件
 /// 1. 允许进程从里面读入文件
 /// 2. 目前只允许读入一个字符
@@ -22,17 +53,13 @@ impl File for Stdin {
     fn read(&self, mut user_buf: UserBuffer) -> usize {
         assert_eq!(user_buf.len(), 1);
         // busy loop
-        let mut c: usize;
-        loop {
-            c = console_getchar();
-            if c == 0 {
-                suspend_current_and_run_next();
-                continue;
-            } else {
-                break;
+        let ch = loop {
+            match stdin_getchar() {
+                Some(ch) => break ch,
+                None if self.nonblock() => return 0,
+                None => suspend_current_and_run_next(),
             }
-        }
-        let ch = c as u8;
+        };
         unsafe {
             user_buf.buffers[0].as_mut_ptr().write_volatile(ch);
         }
@@ -41,6 +68,23 @@ impl File for Stdin {
     fn write(&self, _user_buf: UserBuffer) -> usize {
         panic!("Cannot write to stdin!");
     }
+    /// 串口没有中断，只能在查询时读出一个字符暂存起来；
+    /// 没有等待队列，poll 会定期重新查询
+    fn poll(&self) -> PollEvents {
+        let ch = stdin_getchar();
+        *STDIN_PENDING.exclusive_access() = ch;
+        if ch.is_some() {
+            PollEvents::IN
+        } else {
+            PollEvents::empty()
+        }
+    }
+    fn nonblock(&self) -> bool {
+        self.nonblock.load(Ordering::Relaxed)
+    }
+    fn set_nonblock(&self, nonblock: bool) {
+        self.nonblock.store(nonblock, Ordering::Relaxed);
+    }
 }
 
 /// stdout 表示只读文件
@@ -0,0 +1,16 @@
+//! 周期性写回脏块的内核线程
+//!
+//! 文件的修改只写入块缓存，由写回线程每隔 WRITEBACK_INTERVAL_MS 写回磁盘，
+//! 不再在每次 write_at 时同步所有缓存块
+
+use crate::config::WRITEBACK_INTERVAL_MS;
+use crate::task::{kthread_sleep_ms, kthread_spawn};
+use easy_fs::block_cache_sync_all;
+
+/// 启动写回线程
+pub fn writeback_start() {
+    kthread_spawn(|| loop {
+        kthread_sleep_ms(WRITEBACK_INTERVAL_MS);
+        block_cache_sync_all();
+    });
+}
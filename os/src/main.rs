@@ -16,6 +16,7 @@ mod console;
 mod config;
 // pub mod batch;
 mod drivers;
+mod errno;
 pub mod fs;
 mod lang_items;
 mod loader;
@@ -62,6 +63,9 @@ fn rust_main() -> ! {
     // 调用 run-tasks
     info!("[kernel] run task...");
     task::add_initproc();
+    // 启动执行工作队列和写回脏块的内核线程
+    task::workqueue_start(&task::SYSTEM_WQ, 1);
+    fs::writeback_start();
     task::run_tasks();
     panic!("unreachable in rust main");
 }
@@ -62,6 +62,8 @@ pub struct StackFrameAllocator {
     end: usize,
     // recycled 以后入先出的方式保存了被回收的物理页号
     recycled: Vec<usize>,
+    // 可供分配的物理页帧总数
+    total: usize,
 }
 
 impl StackFrameAllocator {
@@ -69,6 +71,11 @@ impl StackFrameAllocator {
     pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
         self.current = l.0;
         self.end = r.0;
+        self.total = r.0 - l.0;
+    }
+    // 尚未分配出去的物理页帧数量
+    pub fn free_frames(&self) -> usize {
+        self.end - self.current + self.recycled.len()
     }
 }
 impl FrameAllocator for StackFrameAllocator {
@@ -81,6 +88,7 @@ impl FrameAllocator for StackFrameAllocator {
             end: 0,
             // 创建一个新的向量
             recycled: Vec::new(),
+            total: 0,
         }
     }
     fn alloc(&mut self) -> Option<PhysPageNum> {
@@ -149,6 +157,12 @@ pub fn frame_alloc() -> Option<FrameTracker> {
         .map(FrameTracker::new)
 }
 
+/// 返回 (空闲物理页帧数, 物理页帧总数)
+pub fn frame_stats() -> (usize, usize) {
+    let allocator = FRAME_ALLOCATOR.exclusive_access();
+    (allocator.free_frames(), allocator.total)
+}
+
 /// 提供给其他模块使用 释放一个物理页帧
 pub fn frame_dealloc(ppn: PhysPageNum) {
     FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
@@ -31,6 +31,12 @@ pub fn init_heap() {
     }
 }
 
+/// 返回 (内核堆已分配的字节数, 内核堆总字节数)
+pub fn heap_stats() -> (usize, usize) {
+    let heap = HEAP_ALLOCATOR.lock();
+    (heap.stats_alloc_actual(), heap.stats_total_bytes())
+}
+
 #[allow(unused)]
 pub fn heap_test() {
     use alloc::boxed::Box;
@@ -70,6 +70,28 @@ impl MemorySet {
         self.page_table.token()
     }
 
+    /// 各个逻辑段的起止虚拟地址和访问权限，供 procfs 展示
+    pub fn maps(&self) -> Vec<(VirtAddr, VirtAddr, MapPermission)> {
+        self.areas
+            .iter()
+            .map(|area| {
+                (
+                    area.vpn_range.get_start().into(),
+                    area.vpn_range.get_end().into(),
+                    area.map_perm,
+                )
+            })
+            .collect()
+    }
+
+    /// 所有逻辑段映射的内存大小，不包括跳板页
+    pub fn mapped_size(&self) -> usize {
+        self.areas
+            .iter()
+            .map(|area| (area.vpn_range.get_end().0 - area.vpn_range.get_start().0) * PAGE_SIZE)
+            .sum()
+    }
+
     /// Assume that no conflicts.
     /// 调用 push ，可以在当前地址空间插入一个 Framed 方式映射到物理内存的逻辑段
     /// 该方法的调用者要保证同一地址空间内的任意两个逻辑段不能存在交集
@@ -383,8 +405,10 @@ impl MemorySet {
         }
     }
 
-    pub fn recycle_data_pages(&mut self) {
-        self.areas.clear();
+    /// 取出所有逻辑段，它们占用的物理页帧在返回值被丢弃时回收
+    /// 进程退出时由工作队列丢弃，避免在退出的路径上逐页释放
+    pub fn take_data_pages(&mut self) -> Vec<MapArea> {
+        core::mem::take(&mut self.areas)
     }
 }
 
@@ -14,15 +14,17 @@ mod page_table;
 
 use address::VPNRange;
 pub use address::{PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
-pub use frame_allocator::{frame_alloc, frame_dealloc, FrameTracker};
+pub use frame_allocator::{frame_alloc, frame_dealloc, frame_stats, FrameTracker};
+pub use heap_allocator::heap_stats;
 pub use memory_set::remap_test;
 pub use memory_set::{kernel_token, MapPermission, MemorySet, KERNEL_SPACE};
 use page_table::PTEFlags;
 
 #[allow(unused)]
 pub use page_table::{
-    translated_byte_buffer, translated_ref, translated_refmut, translated_str, PageTable,
-    PageTableEntry, UserBuffer, UserBufferIterator,
+    check_user_writable, copy_from_user, copy_to_user, translated_byte_buffer,
+    translated_byte_buffer_mut, translated_pa, translated_str, PageTable, PageTableEntry,
+    UserBuffer, UserBufferIterator,
 };
 
 /// initiate heap allocator, frame allocator and kernel space
@@ -4,10 +4,13 @@
 //! 页表中的页表项的索引其实是虚拟地址中的虚拟页号，页表项的重要内容是物理地址的物理页帧号
 
 use super::{frame_alloc, FrameTracker, PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
+use crate::errno::EFAULT;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use bitflags::*;
+use core::mem::{size_of, MaybeUninit};
+use core::slice;
 
 // bitflags 是一个 Rust 中常用来比特标志位的 crate
 // 将一个 u8 封装成一个标志位的集合类型，支持一些常见的集合运算
@@ -83,6 +86,11 @@ impl PageTableEntry {
     pub fn executable(&self) -> bool {
         (self.flags() & PTEFlags::X) != PTEFlags::empty()
     }
+
+    /// 个页表项的对应虚拟页面是否允许 U 特权级访问
+    pub fn user(&self) -> bool {
+        (self.flags() & PTEFlags::U) != PTEFlags::empty()
+    }
 }
 
 /// page table structure
@@ -207,20 +215,49 @@ impl PageTable {
     }
 }
 
-/// translate a pointer to a mutable u8 Vec through page table
-/// 提供了将应用地址空间中一个缓冲区转化为在内核空间中能够直接访问的形式
-/// token: 某个应用地址空间的 token
-/// ptr 和 len 则分别表示该地址空间中的一段缓冲区的起始地址和长度
-/// 以向量的形式返回一组可以在内核空间中直接访问的字节数组切片
-pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
+/// 用户地址空间的上界，SV39 中更高的地址不属于用户态
+const USER_SPACE_END: usize = 1 << 38;
+
+/// 检查 [start, start + len) 是否全部位于用户地址空间，返回结束地址
+fn check_user_range(start: usize, len: usize) -> Result<usize, isize> {
+    match start.checked_add(len) {
+        Some(end) if end <= USER_SPACE_END => Ok(end),
+        _ => Err(-EFAULT),
+    }
+}
+
+/// 找到用户虚拟页对应的物理页号
+/// 页面没有映射、不允许 U 特权级访问或者没有所需的读写权限时返回 EFAULT
+fn translate_user_page(
+    page_table: &PageTable,
+    vpn: VirtPageNum,
+    write: bool,
+) -> Result<PhysPageNum, isize> {
+    match page_table.translate(vpn) {
+        Some(pte)
+            if pte.is_valid() && pte.user() && pte.readable() && (!write || pte.writable()) =>
+        {
+            Ok(pte.ppn())
+        }
+        _ => Err(-EFAULT),
+    }
+}
+
+/// 把用户地址空间中的一段缓冲区转换成内核中能直接访问的一组字节数组切片，每个切片位于一个物理页帧中
+fn translate_user_buffer(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+    write: bool,
+) -> Result<Vec<&'static mut [u8]>, isize> {
     let page_table = PageTable::from_token(token);
     let mut start = ptr as usize;
-    let end = start + len;
+    let end = check_user_range(start, len)?;
     let mut v = Vec::new();
     while start < end {
         let start_va = VirtAddr::from(start);
         let mut vpn = start_va.floor();
-        let ppn = page_table.translate(vpn).unwrap().ppn();
+        let ppn = translate_user_page(&page_table, vpn, write)?;
         vpn.step();
         let mut end_va: VirtAddr = vpn.into();
         end_va = end_va.min(VirtAddr::from(end));
@@ -231,44 +268,93 @@ pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&
         }
         start = end_va.into();
     }
-    v
+    Ok(v)
 }
 
-pub fn translated_str(token: usize, ptr: *const u8) -> String {
+/// translate a pointer to a mutable u8 Vec through page table
+/// 提供了将应用地址空间中一个缓冲区转化为在内核空间中能够直接访问的形式
+/// token: 某个应用地址空间的 token
+/// ptr 和 len 则分别表示该地址空间中的一段缓冲区的起始地址和长度
+/// 以向量的形式返回一组可以在内核空间中直接访问的字节数组切片
+/// 内核只会读取这段缓冲区，缓冲区中有页面不可读时返回 EFAULT
+pub fn translated_byte_buffer(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+) -> Result<Vec<&'static mut [u8]>, isize> {
+    translate_user_buffer(token, ptr, len, false)
+}
+
+/// 与 translated_byte_buffer 相同，内核会写入这段缓冲区，缓冲区中有页面不可写时返回 EFAULT
+pub fn translated_byte_buffer_mut(
+    token: usize,
+    ptr: *mut u8,
+    len: usize,
+) -> Result<Vec<&'static mut [u8]>, isize> {
+    translate_user_buffer(token, ptr, len, true)
+}
+
+/// 从用户地址空间中取出以 \0 结尾的字符串，字符串可以跨越多个页面
+/// 在遇到 \0 之前访问到不可读的页面时返回 EFAULT
+pub fn translated_str(token: usize, ptr: *const u8) -> Result<String, isize> {
     let page_table = PageTable::from_token(token);
     let mut string = String::new();
     let mut va = ptr as usize;
     loop {
-        let ch: u8 = *(page_table
-            .translate_va(VirtAddr::from(va))
-            .unwrap()
-            .get_mut());
-        if ch == 0 {
-            break;
+        check_user_range(va, 1)?;
+        let start_va = VirtAddr::from(va);
+        let ppn = translate_user_page(&page_table, start_va.floor(), false)?;
+        // 逐字节读取当前页面中的剩余部分
+        for &ch in &ppn.get_bytes_array()[start_va.page_offset()..] {
+            if ch == 0 {
+                return Ok(string);
+            }
+            string.push(ch as char);
+            va += 1;
         }
-        string.push(ch as char);
-        va += 1;
     }
-    string
 }
 
-#[allow(unused)]
-///Translate a generic through page table and return a reference
-pub fn translated_ref<T>(token: usize, ptr: *const T) -> &'static T {
-    let page_table = PageTable::from_token(token);
-    page_table
-        .translate_va(VirtAddr::from(ptr as usize))
-        .unwrap()
-        .get_ref()
+/// 从用户地址空间中复制出一个对象，对象可以跨越页面，也不要求按照类型对齐
+/// 对象所在的页面不可读时返回 EFAULT
+pub fn copy_from_user<T: Copy>(token: usize, ptr: *const T) -> Result<T, isize> {
+    let mut value = MaybeUninit::<T>::uninit();
+    let dst = unsafe { slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>()) };
+    let mut copied = 0;
+    for src in translate_user_buffer(token, ptr as *const u8, size_of::<T>(), false)? {
+        dst[copied..copied + src.len()].copy_from_slice(src);
+        copied += src.len();
+    }
+    Ok(unsafe { value.assume_init() })
+}
+
+/// 把对象复制到用户地址空间中，对象可以跨越页面
+/// 对象所在的页面不可写时返回 EFAULT，此时不会写入任何内容
+pub fn copy_to_user<T: Copy>(token: usize, ptr: *mut T, value: &T) -> Result<(), isize> {
+    let src = unsafe { slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) };
+    let mut copied = 0;
+    for dst in translate_user_buffer(token, ptr as *const u8, size_of::<T>(), true)? {
+        let len = dst.len();
+        dst.copy_from_slice(&src[copied..copied + len]);
+        copied += len;
+    }
+    Ok(())
+}
+
+/// 检查用户地址空间中的对象是否可写，用于在产生副作用之前提前返回 EFAULT
+pub fn check_user_writable<T>(token: usize, ptr: *mut T) -> Result<(), isize> {
+    translate_user_buffer(token, ptr as *const u8, size_of::<T>(), true).map(|_| ())
 }
 
-pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> &'static mut T {
+/// 用户虚拟地址对应的物理地址，地址没有被映射或者不允许 U 特权级访问时返回 None
+pub fn translated_pa(token: usize, va: usize) -> Option<PhysAddr> {
+    check_user_range(va, 1).ok()?;
     let page_table = PageTable::from_token(token);
-    let va = ptr as usize;
+    let va = VirtAddr::from(va);
     page_table
-        .translate_va(VirtAddr::from(va))
-        .unwrap()
-        .get_mut()
+        .translate(va.floor())
+        .filter(|pte| pte.is_valid() && pte.user())?;
+    page_table.translate_va(va)
 }
 
 /// UserBuffer
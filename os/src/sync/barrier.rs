@@ -0,0 +1,50 @@
+use crate::sync::UPSafeCell;
+use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use alloc::{collections::VecDeque, sync::Arc};
+
+/// 屏障：先到达的线程阻塞，直到 count 个线程都到达后一起继续执行
+/// 所有线程被放行后屏障回到初始状态，可以用于下一轮
+pub struct Barrier {
+    pub inner: UPSafeCell<BarrierInner>,
+}
+
+pub struct BarrierInner {
+    /// 每一轮需要到达的线程数
+    pub count: usize,
+    /// 本轮已经到达的线程数
+    pub arrived: usize,
+    /// 本轮已经到达并阻塞的线程
+    pub wait_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl Barrier {
+    pub fn new(count: usize) -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(BarrierInner {
+                    count,
+                    arrived: 0,
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    /// 等待本轮所有线程到达，最后到达的线程返回 true
+    pub fn wait(&self) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        inner.arrived += 1;
+        if inner.arrived == inner.count {
+            inner.arrived = 0;
+            while let Some(task) = inner.wait_queue.pop_front() {
+                wakeup_task(task);
+            }
+            true
+        } else {
+            inner.wait_queue.push_back(current_task().unwrap());
+            drop(inner);
+            block_current_and_run_next();
+            false
+        }
+    }
+}
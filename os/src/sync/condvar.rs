@@ -1,3 +1,4 @@
+use super::wait::{block_current_until, remove_waiter, take_waiter, wake_all_waiters};
 use crate::sync::{Mutex, UPSafeCell};
 use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
 use alloc::{collections::VecDeque, sync::Arc};
@@ -26,7 +27,7 @@ impl Condvar {
     pub fn signal(&self) {
         let mut inner = self.inner.exclusive_access();
         // 从阻塞队列中移除一个
-        if let Some(task) = inner.wait_queue.pop_front() {
+        if let Some(task) = take_waiter(&mut inner.wait_queue) {
             // 将其唤醒
             wakeup_task(task);
         }
@@ -45,4 +46,23 @@ impl Condvar {
         // 被唤醒之后还需要获取锁，wait 才能返回
         mutex.lock();
     }
+
+    /// 与 wait 相同，最多等待到 expire 时刻，超时返回 false
+    /// 无论是否超时，返回前都会重新获取锁
+    pub fn wait_timeout(&self, mutex: Arc<dyn Mutex>, expire: usize) -> bool {
+        mutex.unlock();
+        let task = current_task().unwrap();
+        let mut inner = self.inner.exclusive_access();
+        inner.wait_queue.push_back(Arc::clone(&task));
+        drop(inner);
+        block_current_until(&task, expire);
+        let timed_out = remove_waiter(&mut self.inner.exclusive_access().wait_queue, &task);
+        mutex.lock();
+        !timed_out
+    }
+
+    /// 销毁条件变量，唤醒所有等待者，它们仍然会重新获取锁后才返回
+    pub fn destroy(&self) {
+        wake_all_waiters(&mut self.inner.exclusive_access().wait_queue);
+    }
 }
@@ -0,0 +1,186 @@
+//! 基于银行家算法的死锁检测
+//!
+//! 每个进程记录互斥锁和信号量两类资源的 Available/Allocation/Need 矩阵，
+//! 线程申请资源时先把请求计入 Need，再检查是否存在一个安全序列：
+//! 若所有线程都能按某种顺序得到所需资源并运行结束，则系统处于安全状态
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 进程内可以被线程申请的资源
+#[derive(Debug, Clone, Copy)]
+pub enum Resource {
+    /// mutex_list 中的互斥锁
+    Mutex(usize),
+    /// semaphore_list 中的信号量
+    Semaphore(usize),
+}
+
+/// 一类资源的 Available/Allocation/Need 矩阵，Allocation 和 Need 按 tid 索引
+#[derive(Default)]
+struct Matrix {
+    available: Vec<usize>,
+    allocation: Vec<Vec<usize>>,
+    need: Vec<Vec<usize>>,
+}
+
+impl Matrix {
+    /// 资源 id 被创建（或者复用了已经释放的槽位），可用数量为 count
+    fn add_resource(&mut self, id: usize, count: usize) {
+        if self.available.len() <= id {
+            self.available.resize(id + 1, 0);
+        }
+        self.available[id] = count;
+        for row in self.allocation.iter_mut().chain(self.need.iter_mut()) {
+            if let Some(cell) = row.get_mut(id) {
+                *cell = 0;
+            }
+        }
+    }
+
+    fn get(rows: &[Vec<usize>], tid: usize, id: usize) -> usize {
+        rows.get(tid)
+            .and_then(|row| row.get(id))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn get_mut(rows: &mut Vec<Vec<usize>>, tid: usize, id: usize) -> &mut usize {
+        if rows.len() <= tid {
+            rows.resize(tid + 1, Vec::new());
+        }
+        let row = &mut rows[tid];
+        if row.len() <= id {
+            row.resize(id + 1, 0);
+        }
+        &mut row[id]
+    }
+
+    fn threads(&self) -> usize {
+        self.allocation.len().max(self.need.len())
+    }
+
+    /// 线程 tid 的所有需求都能用 work 满足
+    fn can_finish(&self, tid: usize, work: &[usize]) -> bool {
+        work.iter()
+            .enumerate()
+            .all(|(id, &count)| Self::get(&self.need, tid, id) <= count)
+    }
+
+    /// 线程 tid 运行结束，归还它持有的资源
+    fn finish(&self, tid: usize, work: &mut [usize]) {
+        for (id, count) in work.iter_mut().enumerate() {
+            *count += Self::get(&self.allocation, tid, id);
+        }
+    }
+}
+
+/// 进程的死锁检测器
+///
+/// 无论是否启用，加锁和释放都会更新矩阵，这样在运行中途启用时矩阵也是准确的；
+/// 只有启用后才会拒绝导致不安全状态的请求
+#[derive(Default)]
+pub struct DeadlockDetector {
+    /// 是否启用检测
+    pub enabled: bool,
+    mutex: Matrix,
+    semaphore: Matrix,
+}
+
+impl DeadlockDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn matrix(&mut self, res: Resource) -> (&mut Matrix, usize) {
+        match res {
+            Resource::Mutex(id) => (&mut self.mutex, id),
+            Resource::Semaphore(id) => (&mut self.semaphore, id),
+        }
+    }
+
+    /// 新建资源，互斥锁的数量为 1，信号量的数量为初始值
+    pub fn add_resource(&mut self, res: Resource, count: usize) {
+        let (matrix, id) = self.matrix(res);
+        matrix.add_resource(id, count);
+    }
+
+    /// 资源被销毁，清空它的可用数量以及各线程对它的占有和需求
+    pub fn remove_resource(&mut self, res: Resource) {
+        let (matrix, id) = self.matrix(res);
+        matrix.add_resource(id, 0);
+    }
+
+    /// 线程 tid 申请一个资源，启用检测且申请后系统不再安全时撤销申请并返回 false
+    pub fn request(&mut self, tid: usize, res: Resource) -> bool {
+        let enabled = self.enabled;
+        let (matrix, id) = self.matrix(res);
+        *Matrix::get_mut(&mut matrix.need, tid, id) += 1;
+        if enabled && !self.is_safe() {
+            let (matrix, id) = self.matrix(res);
+            *Matrix::get_mut(&mut matrix.need, tid, id) -= 1;
+            return false;
+        }
+        true
+    }
+
+    /// 线程 tid 没有得到申请的资源就放弃了等待（例如超时），撤销申请
+    pub fn cancel(&mut self, tid: usize, res: Resource) {
+        let (matrix, id) = self.matrix(res);
+        let need = Matrix::get_mut(&mut matrix.need, tid, id);
+        *need = need.saturating_sub(1);
+    }
+
+    /// 线程 tid 得到了申请的资源
+    pub fn acquire(&mut self, tid: usize, res: Resource) {
+        let (matrix, id) = self.matrix(res);
+        let need = Matrix::get_mut(&mut matrix.need, tid, id);
+        *need = need.saturating_sub(1);
+        *Matrix::get_mut(&mut matrix.allocation, tid, id) += 1;
+        if let Some(available) = matrix.available.get_mut(id) {
+            *available = available.saturating_sub(1);
+        }
+    }
+
+    /// 线程 tid 释放一个资源
+    /// 信号量可以由没有持有它的线程释放，此时只增加可用数量
+    pub fn release(&mut self, tid: usize, res: Resource) {
+        let (matrix, id) = self.matrix(res);
+        let allocation = Matrix::get_mut(&mut matrix.allocation, tid, id);
+        *allocation = allocation.saturating_sub(1);
+        if let Some(available) = matrix.available.get_mut(id) {
+            *available += 1;
+        }
+    }
+
+    /// 线程退出，它的 tid 可能被新线程复用，清空它所在的行
+    /// 退出时仍然持有的资源不再归还，等待它们的线程会被检测为死锁
+    pub fn release_thread(&mut self, tid: usize) {
+        for matrix in [&mut self.mutex, &mut self.semaphore] {
+            for rows in [&mut matrix.allocation, &mut matrix.need] {
+                if let Some(row) = rows.get_mut(tid) {
+                    row.clear();
+                }
+            }
+        }
+    }
+
+    /// 安全性检查：反复找出需求可以被满足的线程，假设它运行结束并归还资源，
+    /// 直到所有线程都能结束（安全），或者剩下的线程都无法继续（不安全）
+    fn is_safe(&self) -> bool {
+        let threads = self.mutex.threads().max(self.semaphore.threads());
+        let mut mutex_work = self.mutex.available.clone();
+        let mut semaphore_work = self.semaphore.available.clone();
+        let mut finish = vec![false; threads];
+        while let Some(tid) = (0..threads).find(|&tid| {
+            !finish[tid]
+                && self.mutex.can_finish(tid, &mutex_work)
+                && self.semaphore.can_finish(tid, &semaphore_work)
+        }) {
+            finish[tid] = true;
+            self.mutex.finish(tid, &mut mutex_work);
+            self.semaphore.finish(tid, &mut semaphore_work);
+        }
+        finish.iter().all(|&finished| finished)
+    }
+}
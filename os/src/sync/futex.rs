@@ -0,0 +1,87 @@
+//! futex: 以用户态 32 位字的物理地址为键的等待队列
+//!
+//! 用户态的锁只在发生竞争时才通过 futex 进入内核阻塞或唤醒，
+//! 使用物理地址作为键，同一进程的线程以及映射了同一物理页的进程都能看到同一个队列
+
+use super::UPSafeCell;
+use crate::task::{wakeup_task, TaskControlBlock, TaskStatus};
+use crate::timer::remove_timer;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// 检查用户字的值后阻塞
+pub const FUTEX_WAIT: usize = 0;
+/// 唤醒等待在用户字上的线程
+pub const FUTEX_WAKE: usize = 1;
+
+lazy_static! {
+    /// 每个被等待的物理地址上阻塞的线程，队列为空时删除
+    static ref FUTEX_QUEUES: UPSafeCell<BTreeMap<usize, VecDeque<Arc<TaskControlBlock>>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+/// 把 task 加入 pa 的等待队列
+pub fn futex_enqueue(pa: usize, task: Arc<TaskControlBlock>) {
+    FUTEX_QUEUES
+        .exclusive_access()
+        .entry(pa)
+        .or_default()
+        .push_back(task);
+}
+
+/// 把 task 从 pa 的等待队列中移除，返回它是否还在队列中（即没有被 futex_wake 唤醒）
+pub fn futex_dequeue(pa: usize, task: &Arc<TaskControlBlock>) -> bool {
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    let queue = match queues.get_mut(&pa) {
+        Some(queue) => queue,
+        None => return false,
+    };
+    let len = queue.len();
+    queue.retain(|waiter| !Arc::ptr_eq(waiter, task));
+    let found = queue.len() != len;
+    if queue.is_empty() {
+        queues.remove(&pa);
+    }
+    found
+}
+
+/// 按等待的先后顺序唤醒 pa 上最多 count 个线程，返回唤醒的数量
+/// 已经超时被定时器唤醒的线程留在队列中，由它自己移除
+pub fn futex_wake(pa: usize, count: usize) -> usize {
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    let queue = match queues.get_mut(&pa) {
+        Some(queue) => queue,
+        None => return 0,
+    };
+    let mut woken = Vec::new();
+    queue.retain(|task| {
+        if woken.len() < count && task.inner_exclusive_access().task_status == TaskStatus::Blocked {
+            woken.push(Arc::clone(task));
+            false
+        } else {
+            true
+        }
+    });
+    if queue.is_empty() {
+        queues.remove(&pa);
+    }
+    drop(queues);
+    let n = woken.len();
+    for task in woken {
+        // 取消超时定时器，避免线程被再次唤醒
+        remove_timer(Arc::clone(&task));
+        wakeup_task(task);
+    }
+    n
+}
+
+/// 进程退出时，把它仍在等待的线程从所有等待队列中移除
+pub fn futex_remove_task(task: &Arc<TaskControlBlock>) {
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    queues.retain(|_, queue| {
+        queue.retain(|waiter| !Arc::ptr_eq(waiter, task));
+        !queue.is_empty()
+    });
+}
@@ -1,8 +1,20 @@
+mod barrier;
 mod condvar;
+mod deadlock;
+mod futex;
 mod mutex;
+mod rwlock;
 mod semaphore;
 mod up;
+mod wait;
+pub use barrier::Barrier;
 pub use condvar::Condvar;
-pub use mutex::{Mutex, MutexBlocking, MutexSpin};
+pub use deadlock::{DeadlockDetector, Resource};
+pub use futex::{
+    futex_dequeue, futex_enqueue, futex_remove_task, futex_wake, FUTEX_WAIT, FUTEX_WAKE,
+};
+pub use mutex::{Mutex, MutexBlocking, MutexBlockingInner, MutexSpin};
+pub use rwlock::RwLock;
 pub use semaphore::Semaphore;
 pub use up::UPSafeCell;
+pub use wait::{block_current_until, remove_waiter, take_waiter, wake_all_waiters};
@@ -1,25 +1,43 @@
+use super::wait::{block_current_until, remove_waiter, take_top_waiter, wake_all_waiters};
 use super::UPSafeCell;
 use crate::task::TaskControlBlock;
 use crate::task::{block_current_and_run_next, suspend_current_and_run_next};
 use crate::task::{current_task, wakeup_task};
-use alloc::{collections::VecDeque, sync::Arc};
+use crate::timer::get_time;
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+use core::cell::RefMut;
 
 pub trait Mutex: Sync + Send {
     /// 加锁
     fn lock(&self);
-    /// 释放锁
-    fn unlock(&self);
+    /// 尝试加锁，锁已经被持有时立即返回 false
+    fn try_lock(&self) -> bool;
+    /// 加锁，最多等待到 expire 时刻，超时返回 false
+    fn lock_timeout(&self, expire: usize) -> bool;
+    /// 释放锁，锁没有被当前线程持有时返回 false
+    fn unlock(&self) -> bool;
+    /// 销毁锁，唤醒所有等待者，它们的加锁操作返回后由调用者报告错误
+    fn destroy(&self);
+}
+
+/// 持有者是否为当前线程，持有者已经退出时同样返回 false
+fn is_current_owner(owner: &Option<Weak<TaskControlBlock>>) -> bool {
+    owner.as_ref().map_or(false, |owner| {
+        owner.as_ptr() == Arc::as_ptr(&current_task().unwrap())
+    })
 }
 
 /// 基于 yield 机制
 pub struct MutexSpin {
-    locked: UPSafeCell<bool>,
+    /// 持有锁的线程，为 None 表示锁空闲
+    owner: UPSafeCell<Option<Weak<TaskControlBlock>>>,
 }
 
 impl MutexSpin {
     pub fn new() -> Self {
         Self {
-            locked: unsafe { UPSafeCell::new(false) },
+            owner: unsafe { UPSafeCell::new(None) },
         }
     }
 }
@@ -27,27 +45,60 @@ impl MutexSpin {
 impl Mutex for MutexSpin {
     fn lock(&self) {
         loop {
-            let mut locked = self.locked.exclusive_access();
-            if *locked {
-                drop(locked);
+            let mut owner = self.owner.exclusive_access();
+            if owner.is_some() {
+                drop(owner);
                 suspend_current_and_run_next();
                 continue;
             } else {
-                *locked = true;
+                *owner = Some(Arc::downgrade(&current_task().unwrap()));
                 return;
             }
         }
     }
 
-    fn unlock(&self) {
-        let mut locked = self.locked.exclusive_access();
-        *locked = false;
+    fn try_lock(&self) -> bool {
+        let mut owner = self.owner.exclusive_access();
+        if owner.is_some() {
+            false
+        } else {
+            *owner = Some(Arc::downgrade(&current_task().unwrap()));
+            true
+        }
+    }
+
+    fn lock_timeout(&self, expire: usize) -> bool {
+        while !self.try_lock() {
+            if get_time() >= expire {
+                return false;
+            }
+            suspend_current_and_run_next();
+        }
+        true
+    }
+
+    fn unlock(&self) -> bool {
+        let mut owner = self.owner.exclusive_access();
+        if !is_current_owner(&owner) {
+            return false;
+        }
+        *owner = None;
+        true
+    }
+
+    fn destroy(&self) {
+        // 让正在自旋等待的线程退出循环
+        *self.owner.exclusive_access() = None;
     }
 }
 
-/// 基于阻塞机制
+/// 优先级沿等待链传递的最大深度，避免死锁形成的环导致无限循环
+const PI_CHAIN_MAX: usize = 16;
+
+/// 基于阻塞机制，支持优先级继承：
+/// 持有者的有效优先级被提升到等待者中的最高优先级，释放锁时恢复
 pub struct MutexBlocking {
-    inner: UPSafeCell<MutexBlockingInner>,
+    inner: Arc<UPSafeCell<MutexBlockingInner>>,
 }
 
 pub struct MutexBlockingInner {
@@ -55,21 +106,69 @@ pub struct MutexBlockingInner {
     /// 线程通过 sys_mutex_lock 系统调用尝试获取锁的时候，发现这个值为 true
     /// 就需要等待该值变为 false，在此之前都需要被阻塞
     locked: bool,
+    /// 持有锁的线程，等待者的优先级被继承给它
+    owner: Option<Weak<TaskControlBlock>>,
     /// 阻塞（等待）队列，记录blocked 变为false 而被阻塞的线程
     wait_queue: VecDeque<Arc<TaskControlBlock>>,
 }
 
+impl MutexBlockingInner {
+    /// 等待者中最高的有效优先级
+    fn top_waiter_priority(&self) -> Option<usize> {
+        self.wait_queue
+            .iter()
+            .map(|task| task.inner_exclusive_access().effective_priority)
+            .max()
+    }
+}
+
+/// 等待队列发生变化后重新计算持有者继承的优先级。如果持有者的有效优先级变了，
+/// 而它自己又在等待另一把锁，则继续更新那把锁的持有者，直到链条结束
+fn propagate_priority(mut mutex: Arc<UPSafeCell<MutexBlockingInner>>) {
+    for _ in 0..PI_CHAIN_MAX {
+        let mutex_inner = mutex.exclusive_access();
+        let owner = match mutex_inner.owner.as_ref().and_then(Weak::upgrade) {
+            Some(owner) => owner,
+            None => return,
+        };
+        let top = mutex_inner.top_waiter_priority();
+        drop(mutex_inner);
+        let mut owner_inner = owner.inner_exclusive_access();
+        if !owner_inner.set_inherited_priority(Arc::as_ptr(&mutex) as usize, top) {
+            return;
+        }
+        match owner_inner.blocked_on.as_ref().and_then(Weak::upgrade) {
+            Some(next) => mutex = next,
+            None => return,
+        }
+    }
+}
+
 impl MutexBlocking {
     pub fn new() -> Self {
         Self {
-            inner: unsafe {
+            inner: Arc::new(unsafe {
                 UPSafeCell::new(MutexBlockingInner {
                     locked: false,
+                    owner: None,
                     wait_queue: VecDeque::new(),
                 })
-            },
+            }),
         }
     }
+
+    /// 当前线程加入阻塞队列，并把优先级继承给持有者
+    fn enqueue_current(
+        &self,
+        mut mutex_inner: RefMut<'_, MutexBlockingInner>,
+    ) -> Arc<TaskControlBlock> {
+        let task = current_task().unwrap();
+        mutex_inner.wait_queue.push_back(Arc::clone(&task));
+        drop(mutex_inner);
+        task.inner_exclusive_access().blocked_on = Some(Arc::downgrade(&self.inner));
+        propagate_priority(Arc::clone(&self.inner));
+        task
+    }
 }
 
 impl Mutex for MutexBlocking {
@@ -78,27 +177,96 @@ impl Mutex for MutexBlocking {
         // 首先检测是否已经有线程在临界区
         // 如果为 locked 为 true，将当前线程复制一份加入到阻塞队列中
         if mutex_inner.locked {
-            mutex_inner.wait_queue.push_back(current_task().unwrap());
-            drop(mutex_inner);
-            // 阻塞当前线程
+            self.enqueue_current(mutex_inner);
+            // 阻塞当前线程，被唤醒时 unlock 已经把锁交给了当前线程
             block_current_and_run_next();
         } else {
             // 当前线程可以进入临界区。
             // locked  改为 true
             mutex_inner.locked = true;
+            mutex_inner.owner = Some(Arc::downgrade(&current_task().unwrap()));
+        }
+    }
+
+    fn try_lock(&self) -> bool {
+        let mut mutex_inner = self.inner.exclusive_access();
+        if mutex_inner.locked {
+            false
+        } else {
+            mutex_inner.locked = true;
+            mutex_inner.owner = Some(Arc::downgrade(&current_task().unwrap()));
+            true
+        }
+    }
+
+    fn lock_timeout(&self, expire: usize) -> bool {
+        let mut mutex_inner = self.inner.exclusive_access();
+        if !mutex_inner.locked {
+            mutex_inner.locked = true;
+            mutex_inner.owner = Some(Arc::downgrade(&current_task().unwrap()));
+            return true;
+        }
+        let task = self.enqueue_current(mutex_inner);
+        block_current_until(&task, expire);
+        // 被 unlock 唤醒时已经离开阻塞队列并且得到了锁，仍在队列中说明超时
+        let timed_out = remove_waiter(&mut self.inner.exclusive_access().wait_queue, &task);
+        if timed_out {
+            // 不再等待，持有者不再继承当前线程的优先级
+            task.inner_exclusive_access().blocked_on = None;
+            propagate_priority(Arc::clone(&self.inner));
         }
+        !timed_out
     }
 
-    fn unlock(&self) {
+    fn unlock(&self) -> bool {
         let mut mutex_inner = self.inner.exclusive_access();
-        // 假定持有锁
-        assert!(mutex_inner.locked);
-        // 从阻塞队列中取出一个线程
-        if let Some(waking_task) = mutex_inner.wait_queue.pop_front() {
-            // 如果存在，将该线程唤醒
+        // 只有持有者可以释放锁，否则会把锁交给等待者并撤销持有者继承的优先级
+        if !mutex_inner.locked || !is_current_owner(&mutex_inner.owner) {
+            return false;
+        }
+        let key = Arc::as_ptr(&self.inner) as usize;
+        // 释放锁的线程恢复原来的优先级
+        if let Some(owner) = mutex_inner.owner.take().and_then(|owner| owner.upgrade()) {
+            owner
+                .inner_exclusive_access()
+                .set_inherited_priority(key, None);
+        }
+        // 从阻塞队列中取出优先级最高的线程，避免它排在先到的低优先级线程之后
+        if let Some(waking_task) = take_top_waiter(&mut mutex_inner.wait_queue) {
+            // 如果存在，把锁交给该线程，它继承剩下的等待者的优先级，然后将其唤醒
+            mutex_inner.owner = Some(Arc::downgrade(&waking_task));
+            let top = mutex_inner.top_waiter_priority();
+            let mut waking_inner = waking_task.inner_exclusive_access();
+            waking_inner.blocked_on = None;
+            waking_inner.set_inherited_priority(key, top);
+            drop(waking_inner);
             wakeup_task(waking_task);
         } else {
             mutex_inner.locked = false;
         }
+        true
+    }
+
+    fn destroy(&self) {
+        let mut mutex_inner = self.inner.exclusive_access();
+        for task in mutex_inner.wait_queue.iter() {
+            task.inner_exclusive_access().blocked_on = None;
+        }
+        wake_all_waiters(&mut mutex_inner.wait_queue);
+        // 仍然引用这把锁的条件变量等待者重新加锁时不会永远阻塞
+        mutex_inner.locked = false;
+        let owner = mutex_inner.owner.take();
+        drop(mutex_inner);
+        // 持有者不再继承等待者的优先级，如果它自己在等待另一把锁，继续更新那把锁的持有者
+        if let Some(owner) = owner.and_then(|owner| owner.upgrade()) {
+            let mut owner_inner = owner.inner_exclusive_access();
+            let key = Arc::as_ptr(&self.inner) as usize;
+            if owner_inner.set_inherited_priority(key, None) {
+                if let Some(next) = owner_inner.blocked_on.as_ref().and_then(Weak::upgrade) {
+                    drop(owner_inner);
+                    propagate_priority(next);
+                }
+            }
+        }
     }
 }
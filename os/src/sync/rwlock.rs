@@ -0,0 +1,102 @@
+use crate::sync::UPSafeCell;
+use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use alloc::{collections::VecDeque, sync::Arc, vec::Vec};
+
+/// 读写锁：允许多个读者同时持有，或者一个写者独占
+/// 写者优先：有写者在等待时新来的读者也要等待，释放时先唤醒写者，避免写者饥饿
+pub struct RwLock {
+    pub inner: UPSafeCell<RwLockInner>,
+}
+
+pub struct RwLockInner {
+    /// 持有读锁的线程，同一个线程多次获取读锁时出现多次
+    pub readers: Vec<Arc<TaskControlBlock>>,
+    /// 持有写锁的线程
+    pub writer: Option<Arc<TaskControlBlock>>,
+    /// 等待读锁的线程
+    pub read_queue: VecDeque<Arc<TaskControlBlock>>,
+    /// 等待写锁的线程
+    pub write_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl RwLock {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(RwLockInner {
+                    readers: Vec::new(),
+                    writer: None,
+                    read_queue: VecDeque::new(),
+                    write_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    pub fn read(&self) {
+        let mut inner = self.inner.exclusive_access();
+        let task = current_task().unwrap();
+        if inner.writer.is_some() || !inner.write_queue.is_empty() {
+            // 被唤醒时释放锁的线程已经把当前线程计入读者
+            inner.read_queue.push_back(task);
+            drop(inner);
+            block_current_and_run_next();
+        } else {
+            inner.readers.push(task);
+        }
+    }
+
+    pub fn write(&self) {
+        let mut inner = self.inner.exclusive_access();
+        let task = current_task().unwrap();
+        if inner.writer.is_some() || !inner.readers.is_empty() {
+            // 被唤醒时锁已经交给当前线程
+            inner.write_queue.push_back(task);
+            drop(inner);
+            block_current_and_run_next();
+        } else {
+            inner.writer = Some(task);
+        }
+    }
+
+    /// 释放当前线程持有的读锁或写锁，当前线程没有持有锁时返回 false
+    pub fn unlock(&self) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        let task = current_task().unwrap();
+        if inner
+            .writer
+            .as_ref()
+            .map_or(false, |writer| Arc::ptr_eq(writer, &task))
+        {
+            inner.writer = None;
+        } else if let Some(pos) = inner
+            .readers
+            .iter()
+            .position(|reader| Arc::ptr_eq(reader, &task))
+        {
+            inner.readers.swap_remove(pos);
+        } else {
+            return false;
+        }
+        if !inner.readers.is_empty() {
+            return true;
+        }
+        // 锁空闲时先交给等待的写者，没有写者时唤醒所有等待的读者
+        if let Some(task) = inner.write_queue.pop_front() {
+            inner.writer = Some(Arc::clone(&task));
+            wakeup_task(task);
+        } else {
+            while let Some(task) = inner.read_queue.pop_front() {
+                inner.readers.push(Arc::clone(&task));
+                wakeup_task(task);
+            }
+        }
+        true
+    }
+}
+
+impl Default for RwLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
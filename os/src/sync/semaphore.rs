@@ -2,6 +2,7 @@ use alloc::{collections::vec_deque::VecDeque, sync::Arc};
 
 use crate::task::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
 
+use super::wait::{block_current_until, remove_waiter, take_waiter, wake_all_waiters};
 use super::UPSafeCell;
 
 pub struct Semaphore {
@@ -32,7 +33,7 @@ impl Semaphore {
         let mut inner = self.inner.exclusive_access();
         inner.count += 1;
         if inner.count <= 0 {
-            if let Some(task) = inner.wait_queue.pop_front() {
+            if let Some(task) = take_waiter(&mut inner.wait_queue) {
                 wakeup_task(task);
             }
         }
@@ -47,4 +48,41 @@ impl Semaphore {
             block_current_and_run_next();
         }
     }
+
+    /// 没有可用资源时立即返回 false
+    pub fn try_down(&self) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        if inner.count > 0 {
+            inner.count -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 最多阻塞到 expire 时刻，超时返回 false
+    pub fn down_timeout(&self, expire: usize) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        inner.count -= 1;
+        if inner.count >= 0 {
+            return true;
+        }
+        let task = current_task().unwrap();
+        inner.wait_queue.push_back(Arc::clone(&task));
+        drop(inner);
+        block_current_until(&task, expire);
+        let mut inner = self.inner.exclusive_access();
+        if remove_waiter(&mut inner.wait_queue, &task) {
+            // 超时，归还 down 时预先占用的资源
+            inner.count += 1;
+            false
+        } else {
+            true
+        }
+    }
+
+    /// 销毁信号量，唤醒所有等待者，它们没有得到资源
+    pub fn destroy(&self) {
+        wake_all_waiters(&mut self.inner.exclusive_access().wait_queue);
+    }
 }
@@ -0,0 +1,70 @@
+//! 同步原语共用的等待队列操作
+//!
+//! 带超时的等待会让一个线程同时被等待队列和定时器引用。定时器先到期时线程已经被唤醒，
+//! 但仍然留在等待队列中，由它自己在恢复运行后移除，并据此判断等待超时
+
+use crate::task::{block_current_and_run_next, wakeup_task, TaskControlBlock, TaskStatus};
+use crate::timer::{add_timer, remove_timer};
+use alloc::{collections::VecDeque, sync::Arc};
+use core::cmp::Reverse;
+
+/// 从等待队列中取出第一个仍在阻塞的线程，并取消它的超时定时器
+/// 已经超时被定时器唤醒的线程留在队列中；所属进程已经退出的线程直接丢弃，
+/// 跨进程共享的等待队列（例如消息队列）中可能留有这样的线程
+pub fn take_waiter(queue: &mut VecDeque<Arc<TaskControlBlock>>) -> Option<Arc<TaskControlBlock>> {
+    queue.retain(|task| task.inner_exclusive_access().res.is_some());
+    let pos = queue
+        .iter()
+        .position(|task| task.inner_exclusive_access().task_status == TaskStatus::Blocked)?;
+    let task = queue.remove(pos).unwrap();
+    remove_timer(Arc::clone(&task));
+    Some(task)
+}
+
+/// 与 take_waiter 相同，但取出有效优先级最高的线程，优先级相同时先到先得
+pub fn take_top_waiter(
+    queue: &mut VecDeque<Arc<TaskControlBlock>>,
+) -> Option<Arc<TaskControlBlock>> {
+    queue.retain(|task| task.inner_exclusive_access().res.is_some());
+    let (pos, _) = queue
+        .iter()
+        .enumerate()
+        .filter_map(|(pos, task)| {
+            let task_inner = task.inner_exclusive_access();
+            (task_inner.task_status == TaskStatus::Blocked)
+                .then_some((pos, task_inner.effective_priority))
+        })
+        // min_by_key 在相等时返回第一个，即最早进入队列的线程
+        .min_by_key(|&(_, priority)| Reverse(priority))?;
+    let task = queue.remove(pos).unwrap();
+    remove_timer(Arc::clone(&task));
+    Some(task)
+}
+
+/// 同步对象被销毁时唤醒所有仍在阻塞的等待者
+pub fn wake_all_waiters(queue: &mut VecDeque<Arc<TaskControlBlock>>) {
+    while let Some(task) = take_waiter(queue) {
+        wakeup_task(task);
+    }
+}
+
+/// 把 task 从等待队列中移除，返回它是否还在队列中
+pub fn remove_waiter(
+    queue: &mut VecDeque<Arc<TaskControlBlock>>,
+    task: &Arc<TaskControlBlock>,
+) -> bool {
+    match queue.iter().position(|waiter| Arc::ptr_eq(waiter, task)) {
+        Some(pos) => {
+            queue.remove(pos);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 当前线程加入等待队列后调用，阻塞直到被唤醒或者到达 expire 时刻
+/// 返回后调用者需要通过 remove_waiter 判断是被唤醒还是超时
+pub fn block_current_until(task: &Arc<TaskControlBlock>, expire: usize) {
+    add_timer(expire, Arc::clone(task));
+    block_current_and_run_next();
+}
@@ -1,112 +1,373 @@
 //! File and filesystem-related syscalls
 use log::info;
 
-use crate::fs::{make_pipe, open_file, OpenFlags};
-use crate::mm::{translated_byte_buffer, translated_refmut, translated_str, UserBuffer};
-use crate::task::{current_process, current_user_token};
+use super::ipc::optional_expire;
+use crate::config::CLOCK_FREQ;
+use crate::errno::*;
+use crate::fs::{
+    create_fifo, is_fifo, is_proc_path, make_pipe, open_fifo, open_file, open_proc, File,
+    OpenFlags, PollEvents,
+};
+use crate::mm::{
+    check_user_writable, copy_from_user, copy_to_user, translated_byte_buffer,
+    translated_byte_buffer_mut, translated_str, UserBuffer,
+};
+use crate::sync::block_current_until;
+use crate::task::{
+    block_current_and_run_next, current_add_signal, current_process, current_task,
+    current_user_token, SignalFlags, RLIMIT_NOFILE,
+};
+use crate::timer::{get_time, TimeSpec, TICKS_PER_SEC};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 /// 写文件，需要访问当前进程的文件描述符表
+/// fd 不存在或者不可写时返回 EBADF，buf 不可读时返回 EFAULT
 pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
     let token = current_user_token();
     let process = current_process();
     let inner = process.inner_exclusive_access();
     if fd >= inner.fd_table.len() {
-        return -1;
+        return -EBADF;
     }
     // 根据文件描述符获取在文件描述符表中的文件
     if let Some(file) = &inner.fd_table[fd] {
         // 判断文件是否可写
         if !file.writable() {
-            return -1;
+            return -EBADF;
         }
         // clone文件
         let file = file.clone();
         // release current task TCB manually to avoid multi-borrow
         drop(inner);
+        // 非阻塞模式下写不进任何数据时返回 EAGAIN，能写入一部分时返回写入的字节数
+        if file.nonblock() && !file.poll().intersects(PollEvents::OUT | PollEvents::ERR) {
+            return -EAGAIN;
+        }
+        let buffers = match translated_byte_buffer(token, buf, len) {
+            Ok(buffers) => buffers,
+            Err(err) => return err,
+        };
         // 调用 File trait 的 write 接口
-        file.write(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
+        let written = file.write(UserBuffer::new(buffers));
+        // 管道的读端或者套接字的对端已经全部关闭：向进程发送 SIGPIPE 并返回 EPIPE
+        if written == 0 && len > 0 && file.poll().contains(PollEvents::ERR) {
+            current_add_signal(SignalFlags::SIGPIPE);
+            return -EPIPE;
+        }
+        written as isize
     } else {
-        -1
+        -EBADF
     }
 }
 
-pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
+/// fd 不存在或者不可读时返回 EBADF，buf 不可写时返回 EFAULT
+pub fn sys_read(fd: usize, buf: *mut u8, len: usize) -> isize {
     let token = current_user_token();
     let process = current_process();
     let inner = process.inner_exclusive_access();
     if fd >= inner.fd_table.len() {
-        return -1;
+        return -EBADF;
     }
     if let Some(file) = &inner.fd_table[fd] {
         let file = file.clone();
         if !file.readable() {
-            return -1;
+            return -EBADF;
         }
         // release current task TCB manually to avoid multi-borrow
         drop(inner);
-        file.read(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
+        // 非阻塞模式下没有数据可读并且不会读到文件结尾时返回 EAGAIN
+        if file.nonblock() && !file.poll().intersects(PollEvents::IN | PollEvents::HUP) {
+            return -EAGAIN;
+        }
+        match translated_byte_buffer_mut(token, buf, len) {
+            Ok(buffers) => file.read(UserBuffer::new(buffers)) as isize,
+            Err(err) => err,
+        }
     } else {
-        -1
+        -EBADF
     }
 }
 
+/// flags 非法时返回 EINVAL，没有空闲的文件描述符时返回 EMFILE，path 不可读时返回 EFAULT，
+/// 其余错误码见 open_file、open_fifo 和 open_proc
 pub fn sys_open(path: *const u8, flags: u32) -> isize {
     let process = current_process();
     info!("sus_open .....");
     let token = current_user_token();
-    let path = translated_str(token, path);
+    let path = match translated_str(token, path) {
+        Ok(path) => path,
+        Err(err) => return err,
+    };
     info!("sys_open translated_str....");
-    if let Some(inode) = open_file(path.as_str(), OpenFlags::from_bits(flags).unwrap()) {
-        let mut inner = process.inner_exclusive_access();
-        let fd = inner.alloc_fd();
-        inner.fd_table[fd] = Some(inode);
-        fd as isize
+    let flags = match OpenFlags::from_bits(flags) {
+        Some(flags) => flags,
+        None => return -EINVAL,
+    };
+    // /proc 下的路径由 procfs 处理，其余的在 easy-fs 的根目录中查找
+    let file: Result<Arc<dyn File + Send + Sync>, isize> = if is_proc_path(path.as_str()) {
+        open_proc(path.as_str(), flags).map(|file| file as _)
     } else {
-        -1
+        let cred = process.inner_exclusive_access().cred;
+        // 打开命名管道可能阻塞到另一端被打开，此时没有持有进程控制块
+        if is_fifo(path.as_str()) {
+            open_fifo(path.as_str(), flags, &cred).map(|pipe| pipe as _)
+        } else {
+            open_file(path.as_str(), flags, &cred).map(|inode| inode as _)
+        }
+    };
+    match file {
+        Ok(file) => {
+            if flags.contains(OpenFlags::NONBLOCK) {
+                file.set_nonblock(true);
+            }
+            let mut inner = process.inner_exclusive_access();
+            let fd = match inner.alloc_fd() {
+                Some(fd) => fd,
+                None => return -EMFILE,
+            };
+            inner.fd_table[fd] = Some(file);
+            fd as isize
+        }
+        Err(err) => err,
     }
 }
 
+/// 在根目录下创建权限为 mode 的命名管道，成功返回 0
+/// 没有根目录的写权限返回 EACCES，名字已经存在返回 EEXIST，path 不可读时返回 EFAULT
+pub fn sys_mkfifo(path: *const u8, mode: u32) -> isize {
+    let process = current_process();
+    let token = current_user_token();
+    let path = match translated_str(token, path) {
+        Ok(path) => path,
+        Err(err) => return err,
+    };
+    if is_proc_path(path.as_str()) {
+        return -EACCES;
+    }
+    let cred = process.inner_exclusive_access().cred;
+    match create_fifo(path.as_str(), (mode & 0o777) as u16, &cred) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// fd 不存在时返回 EBADF
 pub fn sys_close(fd: usize) -> isize {
     let process = current_process();
     let mut inner = process.inner_exclusive_access();
     if fd >= inner.fd_table.len() {
-        return -1;
+        return -EBADF;
     }
     if inner.fd_table[fd].is_none() {
-        return -1;
+        return -EBADF;
     }
     inner.fd_table[fd].take();
     0
 }
 
-pub fn sys_pipe(pipe: *mut usize) -> isize {
+/// 创建管道，读端和写端的文件描述符写入 pipe 指向的数组
+/// flags 只能含有 OpenFlags::NONBLOCK，此时两端都处于非阻塞模式，否则返回 EINVAL
+/// pipe 不可写时返回 EFAULT，此时不会创建管道
+pub fn sys_pipe(pipe: *mut usize, flags: u32) -> isize {
+    let nonblock = match OpenFlags::from_bits(flags) {
+        Some(flags) if (flags - OpenFlags::NONBLOCK).is_empty() => {
+            flags.contains(OpenFlags::NONBLOCK)
+        }
+        _ => return -EINVAL,
+    };
     let process = current_process();
     let token = current_user_token();
+    let pipe = pipe as *mut [usize; 2];
+    if let Err(err) = check_user_writable(token, pipe) {
+        return err;
+    }
     let mut inner = process.inner_exclusive_access();
     // 创建一个管道，并获取其读写端
     let (pipe_read, pipe_write) = make_pipe();
+    pipe_read.set_nonblock(nonblock);
+    pipe_write.set_nonblock(nonblock);
     // 为读端和写端分配文件描述符并将它们放置在文件描述符表中的相应位置中
-    let read_fd = inner.alloc_fd();
+    let read_fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => return -EMFILE,
+    };
     inner.fd_table[read_fd] = Some(pipe_read);
-    let write_fd = inner.alloc_fd();
+    let write_fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => {
+            inner.fd_table[read_fd] = None;
+            return -EMFILE;
+        }
+    };
     inner.fd_table[write_fd] = Some(pipe_write);
-    // 将读端和写端的文件描述符写回到应用地址空间
-    *translated_refmut(token, pipe) = read_fd;
-    *translated_refmut(token, unsafe { pipe.add(1) }) = write_fd;
-    0
+    // 将读端和写端的文件描述符写回到应用地址空间，前面已经检查过可写
+    match copy_to_user(token, pipe, &[read_fd, write_fd]) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
 }
 
+/// fcntl 的命令：读取文件的访问模式和状态标志
+const F_GETFL: usize = 3;
+/// fcntl 的命令：设置文件的状态标志
+const F_SETFL: usize = 4;
+
+/// 读取或者修改打开的文件的标志，fork 和 dup 得到的描述符共享这些标志
+/// F_GETFL 返回访问模式和 NONBLOCK；F_SETFL 按 arg 设置 NONBLOCK，arg 中的其他标志被忽略
+/// fd 不存在时返回 EBADF，cmd 不支持时返回 EINVAL
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    let file = {
+        let process = current_process();
+        let inner = process.inner_exclusive_access();
+        match inner.fd_table.get(fd).cloned().flatten() {
+            Some(file) => file,
+            None => return -EBADF,
+        }
+    };
+    match cmd {
+        F_GETFL => {
+            let mut flags = match (file.readable(), file.writable()) {
+                (true, true) => OpenFlags::RDWR,
+                (false, true) => OpenFlags::WRONLY,
+                _ => OpenFlags::RDONLY,
+            };
+            if file.nonblock() {
+                flags |= OpenFlags::NONBLOCK;
+            }
+            flags.bits() as isize
+        }
+        F_SETFL => {
+            file.set_nonblock((arg as u32 & OpenFlags::NONBLOCK.bits()) != 0);
+            0
+        }
+        _ => -EINVAL,
+    }
+}
+
+/// fd 不存在时返回 EBADF，没有空闲的文件描述符时返回 EMFILE
 pub fn sys_dup(fd: usize) -> isize {
     let process = current_process();
     let mut inner = process.inner_exclusive_access();
     if fd >= inner.fd_table.len() {
-        return -1;
+        return -EBADF;
     }
     if inner.fd_table[fd].is_none() {
-        return -1;
+        return -EBADF;
     }
-    let new_fd = inner.alloc_fd();
+    let new_fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => return -EMFILE,
+    };
     inner.fd_table[new_fd] = Some(Arc::clone(inner.fd_table[fd].as_ref().unwrap()));
     new_fd as isize
 }
+
+/// ppoll 的参数，与 Linux 的 struct pollfd 布局相同
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PollFd {
+    /// 文件描述符，为负数时忽略这一项
+    pub fd: i32,
+    /// 关心的事件
+    pub events: u16,
+    /// 返回的就绪事件
+    pub revents: u16,
+}
+
+/// 没有等待队列的文件（例如 stdin）在 poll 中重新查询的间隔
+const POLL_INTERVAL: usize = CLOCK_FREQ / TICKS_PER_SEC;
+
+/// 等待 fds 中的任意一个文件就绪，就绪的事件写回 revents，返回就绪的文件数量
+/// ERR、HUP 总是会返回，不存在的文件描述符返回 NVAL 并算作就绪
+/// timeout 为空指针时一直等待，超时返回 0；nfds 超过打开文件数量的上限或者时间非法返回 EINVAL，
+/// 等待时收到信号返回 EINTR，fds 不可读写时返回 EFAULT
+pub fn sys_ppoll(fds: *mut PollFd, nfds: usize, timeout: *const TimeSpec) -> isize {
+    let token = current_user_token();
+    let process = current_process();
+    let expire = match optional_expire(timeout) {
+        Ok(expire) => expire,
+        Err(err) => return err,
+    };
+    // 在返回之前一直持有这些文件，即使其他线程关闭了描述符也能注销等待
+    let mut polls: Vec<(PollFd, Option<Arc<dyn File + Send + Sync>>)> = Vec::new();
+    {
+        let inner = process.inner_exclusive_access();
+        if nfds > inner.rlimits[RLIMIT_NOFILE].rlim_cur {
+            return -EINVAL;
+        }
+        for i in 0..nfds {
+            // revents 需要写回，所以要求 fds 可写
+            let pollfd_ptr = unsafe { fds.add(i) };
+            let pollfd = match check_user_writable(token, pollfd_ptr)
+                .and_then(|_| copy_from_user(token, pollfd_ptr))
+            {
+                Ok(pollfd) => pollfd,
+                Err(err) => return err,
+            };
+            let file = if pollfd.fd < 0 {
+                None
+            } else {
+                inner.fd_table.get(pollfd.fd as usize).cloned().flatten()
+            };
+            polls.push((pollfd, file));
+        }
+    }
+    let task = current_task().unwrap();
+    let mut waited = false;
+    loop {
+        let mut ready = 0;
+        for (pollfd, file) in polls.iter_mut() {
+            let revents = match file {
+                _ if pollfd.fd < 0 => PollEvents::empty(),
+                Some(file) => {
+                    let events = PollEvents::from_bits_truncate(pollfd.events)
+                        | PollEvents::ERR
+                        | PollEvents::HUP;
+                    file.poll() & events
+                }
+                None => PollEvents::NVAL,
+            };
+            pollfd.revents = revents.bits();
+            if !revents.is_empty() {
+                ready += 1;
+            }
+        }
+        let now = get_time();
+        let timed_out = expire.map_or(false, |expire| now >= expire);
+        if ready > 0 || timed_out {
+            for (i, (pollfd, _)) in polls.iter().enumerate() {
+                // 等待期间其他线程可能取消了映射
+                if let Err(err) = copy_to_user(token, unsafe { fds.add(i) }, pollfd) {
+                    return err;
+                }
+            }
+            return ready;
+        }
+        if waited && !process.inner_exclusive_access().signals.is_empty() {
+            return -EINTR;
+        }
+        // 单核且内核不可抢占，查询和登记之间文件状态不会改变
+        let mut needs_recheck = false;
+        for file in polls.iter().filter_map(|(_, file)| file.as_ref()) {
+            if !file.register_poller(&task) {
+                needs_recheck = true;
+            }
+        }
+        let wake_at = if needs_recheck {
+            Some(expire.map_or(now + POLL_INTERVAL, |expire| {
+                expire.min(now + POLL_INTERVAL)
+            }))
+        } else {
+            expire
+        };
+        match wake_at {
+            Some(wake_at) => block_current_until(&task, wake_at),
+            None => block_current_and_run_next(),
+        }
+        for file in polls.iter().filter_map(|(_, file)| file.as_ref()) {
+            file.unregister_poller(&task);
+        }
+        waited = true;
+    }
+}
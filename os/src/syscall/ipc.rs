@@ -0,0 +1,179 @@
+//! Message queue syscalls
+
+use super::sync::timeout_to_expire;
+use crate::errno::*;
+use crate::fs::{mq_open, mq_unlink, File, MqAttr, OpenFlags};
+use crate::mm::{
+    check_user_writable, copy_from_user, copy_to_user, translated_byte_buffer,
+    translated_byte_buffer_mut, translated_str, UserBuffer,
+};
+use crate::task::{current_process, current_user_token};
+use crate::timer::TimeSpec;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// 取出文件描述符 fd 对应的文件
+pub(super) fn get_file(fd: usize) -> Option<Arc<dyn File + Send + Sync>> {
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    inner.fd_table.get(fd).cloned().flatten()
+}
+
+/// timeout 为空指针时一直等待，否则换算成到期时刻，时间非法时返回 EINVAL，不可读时返回 EFAULT
+pub(super) fn optional_expire(timeout: *const TimeSpec) -> Result<Option<usize>, isize> {
+    if timeout.is_null() {
+        Ok(None)
+    } else {
+        timeout_to_expire(timeout).map(Some)
+    }
+}
+
+/// 按名字打开消息队列，返回文件描述符
+/// flags 含有 CREATE 且队列不存在时创建，attr 为空指针时使用默认的容量和消息长度
+/// 名字、标志或者属性非法时返回 EINVAL，队列不存在且不允许创建时返回 ENOENT，
+/// name 或者 attr 不可读时返回 EFAULT
+pub fn sys_mq_open(name: *const u8, flags: u32, attr: *const MqAttr) -> isize {
+    let token = current_user_token();
+    let name = match translated_str(token, name) {
+        Ok(name) => name,
+        Err(err) => return err,
+    };
+    let flags = match OpenFlags::from_bits(flags) {
+        Some(flags) => flags,
+        None => return -EINVAL,
+    };
+    let attr = if attr.is_null() {
+        None
+    } else {
+        match copy_from_user(token, attr) {
+            Ok(attr) => Some(attr),
+            Err(err) => return err,
+        }
+    };
+    // 先分配描述符，避免描述符用完时新建的消息队列留在命名空间中
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    let fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => return -EMFILE,
+    };
+    let mq = match mq_open(name.as_str(), flags, attr) {
+        Ok(mq) => mq,
+        Err(err) => return err,
+    };
+    inner.fd_table[fd] = Some(mq);
+    fd as isize
+}
+
+/// 删除消息队列的名字，已经打开的描述符仍然可用，名字不存在时返回 ENOENT，name 不可读时返回 EFAULT
+pub fn sys_mq_unlink(name: *const u8) -> isize {
+    let name = match translated_str(current_user_token(), name) {
+        Ok(name) => name,
+        Err(err) => return err,
+    };
+    if mq_unlink(name.as_str()) {
+        0
+    } else {
+        -ENOENT
+    }
+}
+
+/// 发送一条优先级为 prio 的消息，队列已满时最多等待 timeout 指定的相对时间，
+/// timeout 为空指针时一直等待
+/// fd 不是以写方式打开的消息队列返回 EBADF，消息过长返回 EMSGSIZE，优先级或者时间非法返回 EINVAL，
+/// 非阻塞模式下队列已满返回 EAGAIN，超时返回 ETIMEDOUT，msg 不可读时返回 EFAULT
+pub fn sys_mq_timedsend(
+    fd: usize,
+    msg: *const u8,
+    len: usize,
+    prio: u32,
+    timeout: *const TimeSpec,
+) -> isize {
+    let file = match get_file(fd) {
+        Some(file) => file,
+        None => return -EBADF,
+    };
+    let mq = match file.as_mqueue() {
+        Some(mq) if file.writable() => mq,
+        _ => return -EBADF,
+    };
+    let expire = match optional_expire(timeout) {
+        Ok(expire) => expire,
+        Err(err) => return err,
+    };
+    let msg: Vec<u8> = match translated_byte_buffer(current_user_token(), msg, len) {
+        Ok(buffers) => buffers
+            .iter()
+            .flat_map(|slice| slice.iter().copied())
+            .collect(),
+        Err(err) => return err,
+    };
+    match mq.send(msg, prio, expire) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// 接收优先级最高的消息，写入 buf 并返回消息的长度，prio 不为空指针时写入消息的优先级
+/// 队列为空时最多等待 timeout 指定的相对时间，timeout 为空指针时一直等待
+/// fd 不是以读方式打开的消息队列返回 EBADF，len 小于消息的最大长度返回 EMSGSIZE，时间非法返回 EINVAL，
+/// 非阻塞模式下队列为空返回 EAGAIN，超时返回 ETIMEDOUT，
+/// buf 或者不为空的 prio 不可写时返回 EFAULT，此时不会取出消息
+pub fn sys_mq_timedreceive(
+    fd: usize,
+    buf: *mut u8,
+    len: usize,
+    prio: *mut u32,
+    timeout: *const TimeSpec,
+) -> isize {
+    let token = current_user_token();
+    let file = match get_file(fd) {
+        Some(file) => file,
+        None => return -EBADF,
+    };
+    let mq = match file.as_mqueue() {
+        Some(mq) if file.readable() => mq,
+        _ => return -EBADF,
+    };
+    let expire = match optional_expire(timeout) {
+        Ok(expire) => expire,
+        Err(err) => return err,
+    };
+    let buf = match translated_byte_buffer_mut(token, buf, len) {
+        Ok(buffers) => UserBuffer::new(buffers),
+        Err(err) => return err,
+    };
+    if !prio.is_null() {
+        if let Err(err) = check_user_writable(token, prio) {
+            return err;
+        }
+    }
+    match mq.receive(buf, expire) {
+        Ok((len, msg_prio)) => {
+            if !prio.is_null() {
+                // 等待期间其他线程可能取消了映射
+                if let Err(err) = copy_to_user(token, prio, &msg_prio) {
+                    return err;
+                }
+            }
+            len as isize
+        }
+        Err(err) => err,
+    }
+}
+
+/// 把消息队列的属性写入 attr，fd 不是消息队列时返回 EBADF，attr 不可写时返回 EFAULT
+pub fn sys_mq_getattr(fd: usize, attr: *mut MqAttr) -> isize {
+    let file = match get_file(fd) {
+        Some(file) => file,
+        None => return -EBADF,
+    };
+    let mq = match file.as_mqueue() {
+        Some(mq) => mq,
+        None => return -EBADF,
+    };
+    match copy_to_user(current_user_token(), attr, &mq.attr()) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
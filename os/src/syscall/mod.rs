@@ -1,12 +1,20 @@
 /// 系统、调用
 
 const SYSCALL_DUP: usize = 24;
+const SYSCALL_FCNTL: usize = 25;
+const SYSCALL_MKFIFO: usize = 33;
 const SYSCALL_OPEN: usize = 56;
 const SYSCALL_CLOSE: usize = 57;
 const SYSCALL_PIPE: usize = 59;
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_PPOLL: usize = 73;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_NANOSLEEP: usize = 101;
+const SYSCALL_GETITIMER: usize = 102;
+const SYSCALL_SETITIMER: usize = 103;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
 const SYSCALL_YIELD: usize = 124;
 const SYSCALL_KILL: usize = 129;
 #[allow(unused)]
@@ -15,69 +23,442 @@ const SYSCALL_SIGACTION: usize = 134;
 const SYSCALL_SIGPROCMASK: usize = 135;
 #[allow(unused)]
 const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_SETGID: usize = 144;
+const SYSCALL_SETUID: usize = 146;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_GETRLIMIT: usize = 163;
+const SYSCALL_SETRLIMIT: usize = 164;
+const SYSCALL_GETRUSAGE: usize = 165;
 const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_GETPID: usize = 172;
+const SYSCALL_GETUID: usize = 174;
+const SYSCALL_GETEUID: usize = 175;
+const SYSCALL_GETGID: usize = 176;
+const SYSCALL_GETEGID: usize = 177;
+const SYSCALL_MQ_OPEN: usize = 180;
+const SYSCALL_MQ_UNLINK: usize = 181;
+const SYSCALL_MQ_TIMEDSEND: usize = 182;
+const SYSCALL_MQ_TIMEDRECEIVE: usize = 183;
+const SYSCALL_MQ_GETATTR: usize = 185;
+const SYSCALL_SOCKET: usize = 198;
+const SYSCALL_SOCKETPAIR: usize = 199;
+const SYSCALL_BIND: usize = 200;
+const SYSCALL_LISTEN: usize = 201;
+const SYSCALL_ACCEPT: usize = 202;
+const SYSCALL_CONNECT: usize = 203;
+const SYSCALL_SENDMSG: usize = 211;
+const SYSCALL_RECVMSG: usize = 212;
 const SYSCALL_FORK: usize = 220;
 const SYSCALL_EXEC: usize = 221;
 const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_CPU_STAT: usize = 411;
+const SYSCALL_ENABLE_DEADLOCK_DETECT: usize = 469;
 
 const SYSCALL_THREAD_CREATE: usize = 1000;
 const SYSCALL_GETTID: usize = 1001;
 const SYSCALL_WAITTID: usize = 1002;
+const SYSCALL_THREAD_DETACH: usize = 1003;
 const SYSCALL_MUTEX_CREATE: usize = 1010;
 const SYSCALL_MUTEX_LOCK: usize = 1011;
 const SYSCALL_MUTEX_UNLOCK: usize = 1012;
+const SYSCALL_MUTEX_TRYLOCK: usize = 1013;
+const SYSCALL_MUTEX_LOCK_TIMEOUT: usize = 1014;
+const SYSCALL_MUTEX_DESTROY: usize = 1015;
 const SYSCALL_SEMAPHORE_CREATE: usize = 1020;
 const SYSCALL_SEMAPHORE_UP: usize = 1021;
 const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
+const SYSCALL_SEMAPHORE_TRYDOWN: usize = 1023;
+const SYSCALL_SEMAPHORE_DOWN_TIMEOUT: usize = 1024;
+const SYSCALL_SEMAPHORE_DESTROY: usize = 1025;
 const SYSCALL_CONDVAR_CREATE: usize = 1030;
 const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
 const SYSCALL_CONDVAR_WAIT: usize = 1032;
+const SYSCALL_CONDVAR_WAIT_TIMEOUT: usize = 1033;
+const SYSCALL_CONDVAR_DESTROY: usize = 1034;
+const SYSCALL_RWLOCK_CREATE: usize = 1040;
+const SYSCALL_RWLOCK_READ: usize = 1041;
+const SYSCALL_RWLOCK_WRITE: usize = 1042;
+const SYSCALL_RWLOCK_UNLOCK: usize = 1043;
+const SYSCALL_BARRIER_CREATE: usize = 1050;
+const SYSCALL_BARRIER_WAIT: usize = 1051;
+const SYSCALL_TRACE: usize = 1060;
+const SYSCALL_TRACE_READ: usize = 1061;
 mod fs;
+mod ipc;
+mod net;
 mod process;
 mod sync;
 mod thread;
+mod time;
+mod trace;
 
+use crate::errno::ENOSYS;
+use crate::fs::MqAttr;
+use crate::sync::UPSafeCell;
+use crate::task::{current_process, RLimit};
+use crate::timer::{ITimerVal, TimeSpec};
+use alloc::vec;
+use alloc::vec::Vec;
 use fs::*;
+use ipc::*;
+use lazy_static::*;
+use log::warn;
+use net::*;
 use process::*;
 use sync::*;
 use thread::*;
+use time::*;
+use trace::ArgKind::{self, *};
+use trace::*;
 
-pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
-    match syscall_id {
-        SYSCALL_DUP => sys_dup(args[0]),
-        SYSCALL_OPEN => sys_open(args[0] as *const u8, args[1] as u32),
-        SYSCALL_CLOSE => sys_close(args[0]),
-        SYSCALL_PIPE => sys_pipe(args[0] as *mut usize),
-        SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
-        SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
-        SYSCALL_EXIT => sys_exit(args[0] as i32),
-        SYSCALL_YIELD => sys_yield(),
-        SYSCALL_KILL => sys_kill(args[0], args[1] as i32),
-        // SYSCALL_SIGACTION => sys_sigaction(
-        //     args[0] as i32,
-        //     args[1] as *const SignalAction,
-        //     args[2] as *mut SignalAction,
-        // ),
-        //SYSCALL_SIGPROCMASK => sys_sigprocmask(args[0] as u32),
-        //SYSCALL_SIGRETURN => sys_sigreturn(),
-        SYSCALL_GET_TIME => sys_get_time(),
-        SYSCALL_GETPID => sys_getpid(),
-        SYSCALL_FORK => sys_fork(),
-        SYSCALL_EXEC => sys_exec(args[0] as *const u8, args[1] as *const usize),
-        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
-        SYSCALL_THREAD_CREATE => sys_thread_create(args[0], args[1]),
-        SYSCALL_GETTID => sys_gettid(),
-        SYSCALL_WAITTID => sys_waittid(args[0]) as isize,
-        SYSCALL_MUTEX_CREATE => sys_mutex_create(args[0] == 1),
-        SYSCALL_MUTEX_LOCK => sys_mutex_lock(args[0]),
-        SYSCALL_MUTEX_UNLOCK => sys_mutex_unlock(args[0]),
-        SYSCALL_SEMAPHORE_CREATE => sys_semaphore_create(args[0]),
-        SYSCALL_SEMAPHORE_UP => sys_semaphore_up(args[0]),
-        SYSCALL_SEMAPHORE_DOWN => sys_semaphore_down(args[0]),
-        SYSCALL_CONDVAR_CREATE => sys_condvar_create(),
-        SYSCALL_CONDVAR_SIGNAL => sys_condvar_signal(args[0]),
-        SYSCALL_CONDVAR_WAIT => sys_condvar_wait(args[0], args[1]),
-        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+/// 系统调用表中的一项
+struct SyscallEntry {
+    id: usize,
+    name: &'static str,
+    /// 每个参数的类型，用于跟踪时解码
+    args: &'static [ArgKind],
+    handler: fn([usize; 6]) -> isize,
+}
+
+impl SyscallEntry {
+    const fn new(
+        id: usize,
+        name: &'static str,
+        args: &'static [ArgKind],
+        handler: fn([usize; 6]) -> isize,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            args,
+            handler,
+        }
+    }
+}
+
+/// 系统调用表，按调用号升序排列以便二分查找
+static SYSCALL_TABLE: &[SyscallEntry] = &[
+    SyscallEntry::new(SYSCALL_DUP, "dup", &[Int], |a| sys_dup(a[0])),
+    SyscallEntry::new(SYSCALL_FCNTL, "fcntl", &[Int, Int, Int], |a| {
+        sys_fcntl(a[0], a[1], a[2])
+    }),
+    SyscallEntry::new(SYSCALL_MKFIFO, "mkfifo", &[Str, Hex], |a| {
+        sys_mkfifo(a[0] as *const u8, a[1] as u32)
+    }),
+    SyscallEntry::new(SYSCALL_OPEN, "open", &[Str, Hex], |a| {
+        sys_open(a[0] as *const u8, a[1] as u32)
+    }),
+    SyscallEntry::new(SYSCALL_CLOSE, "close", &[Int], |a| sys_close(a[0])),
+    SyscallEntry::new(SYSCALL_PIPE, "pipe", &[Ptr, Hex], |a| {
+        sys_pipe(a[0] as *mut usize, a[1] as u32)
+    }),
+    SyscallEntry::new(SYSCALL_READ, "read", &[Int, Ptr, Int], |a| {
+        sys_read(a[0], a[1] as *mut u8, a[2])
+    }),
+    SyscallEntry::new(SYSCALL_WRITE, "write", &[Int, Ptr, Int], |a| {
+        sys_write(a[0], a[1] as *const u8, a[2])
+    }),
+    SyscallEntry::new(SYSCALL_PPOLL, "ppoll", &[Ptr, Int, Ptr], |a| {
+        sys_ppoll(a[0] as *mut PollFd, a[1], a[2] as *const TimeSpec)
+    }),
+    SyscallEntry::new(SYSCALL_EXIT, "exit", &[Int], |a| sys_exit(a[0] as i32)),
+    SyscallEntry::new(SYSCALL_FUTEX, "futex", &[Ptr, Int, Int, Ptr], |a| {
+        sys_futex(
+            a[0] as *const u32,
+            a[1],
+            a[2] as u32,
+            a[3] as *const TimeSpec,
+        )
+    }),
+    SyscallEntry::new(SYSCALL_NANOSLEEP, "nanosleep", &[Ptr, Ptr], |a| {
+        sys_nanosleep(a[0] as *const TimeSpec, a[1] as *mut TimeSpec)
+    }),
+    SyscallEntry::new(SYSCALL_GETITIMER, "getitimer", &[Int, Ptr], |a| {
+        sys_getitimer(a[0], a[1] as *mut ITimerVal)
+    }),
+    SyscallEntry::new(SYSCALL_SETITIMER, "setitimer", &[Int, Ptr, Ptr], |a| {
+        sys_setitimer(a[0], a[1] as *const ITimerVal, a[2] as *mut ITimerVal)
+    }),
+    SyscallEntry::new(SYSCALL_CLOCK_GETTIME, "clock_gettime", &[Int, Ptr], |a| {
+        sys_clock_gettime(a[0], a[1] as *mut TimeSpec)
+    }),
+    SyscallEntry::new(SYSCALL_YIELD, "yield", &[], |_| sys_yield()),
+    SyscallEntry::new(SYSCALL_KILL, "kill", &[Int, Int], |a| {
+        sys_kill(a[0], a[1] as i32)
+    }),
+    SyscallEntry::new(SYSCALL_SET_PRIORITY, "set_priority", &[Int], |a| {
+        sys_set_priority(a[0] as isize)
+    }),
+    SyscallEntry::new(SYSCALL_SETGID, "setgid", &[Int], |a| sys_setgid(a[0])),
+    SyscallEntry::new(SYSCALL_SETUID, "setuid", &[Int], |a| sys_setuid(a[0])),
+    SyscallEntry::new(SYSCALL_TIMES, "times", &[Ptr], |a| {
+        sys_times(a[0] as *mut Tms)
+    }),
+    SyscallEntry::new(SYSCALL_GETRLIMIT, "getrlimit", &[Int, Ptr], |a| {
+        sys_getrlimit(a[0], a[1] as *mut RLimit)
+    }),
+    SyscallEntry::new(SYSCALL_SETRLIMIT, "setrlimit", &[Int, Ptr], |a| {
+        sys_setrlimit(a[0], a[1] as *const RLimit)
+    }),
+    SyscallEntry::new(SYSCALL_GETRUSAGE, "getrusage", &[Int, Ptr], |a| {
+        sys_getrusage(a[0] as isize, a[1] as *mut RUsage)
+    }),
+    SyscallEntry::new(SYSCALL_GET_TIME, "get_time", &[], |_| sys_get_time()),
+    SyscallEntry::new(SYSCALL_GETPID, "getpid", &[], |_| sys_getpid()),
+    SyscallEntry::new(SYSCALL_GETUID, "getuid", &[], |_| sys_getuid()),
+    SyscallEntry::new(SYSCALL_GETEUID, "geteuid", &[], |_| sys_geteuid()),
+    SyscallEntry::new(SYSCALL_GETGID, "getgid", &[], |_| sys_getgid()),
+    SyscallEntry::new(SYSCALL_GETEGID, "getegid", &[], |_| sys_getegid()),
+    SyscallEntry::new(SYSCALL_MQ_OPEN, "mq_open", &[Str, Hex, Ptr], |a| {
+        sys_mq_open(a[0] as *const u8, a[1] as u32, a[2] as *const MqAttr)
+    }),
+    SyscallEntry::new(SYSCALL_MQ_UNLINK, "mq_unlink", &[Str], |a| {
+        sys_mq_unlink(a[0] as *const u8)
+    }),
+    SyscallEntry::new(
+        SYSCALL_MQ_TIMEDSEND,
+        "mq_timedsend",
+        &[Int, Ptr, Int, Int, Ptr],
+        |a| {
+            sys_mq_timedsend(
+                a[0],
+                a[1] as *const u8,
+                a[2],
+                a[3] as u32,
+                a[4] as *const TimeSpec,
+            )
+        },
+    ),
+    SyscallEntry::new(
+        SYSCALL_MQ_TIMEDRECEIVE,
+        "mq_timedreceive",
+        &[Int, Ptr, Int, Ptr, Ptr],
+        |a| {
+            sys_mq_timedreceive(
+                a[0],
+                a[1] as *mut u8,
+                a[2],
+                a[3] as *mut u32,
+                a[4] as *const TimeSpec,
+            )
+        },
+    ),
+    SyscallEntry::new(SYSCALL_MQ_GETATTR, "mq_getattr", &[Int, Ptr], |a| {
+        sys_mq_getattr(a[0], a[1] as *mut MqAttr)
+    }),
+    SyscallEntry::new(SYSCALL_SOCKET, "socket", &[Int, Int, Int], |a| {
+        sys_socket(a[0], a[1], a[2])
+    }),
+    SyscallEntry::new(
+        SYSCALL_SOCKETPAIR,
+        "socketpair",
+        &[Int, Int, Int, Ptr],
+        |a| sys_socketpair(a[0], a[1], a[2], a[3] as *mut usize),
+    ),
+    SyscallEntry::new(SYSCALL_BIND, "bind", &[Int, Str], |a| {
+        sys_bind(a[0], a[1] as *const u8)
+    }),
+    SyscallEntry::new(SYSCALL_LISTEN, "listen", &[Int, Int], |a| {
+        sys_listen(a[0], a[1])
+    }),
+    SyscallEntry::new(SYSCALL_ACCEPT, "accept", &[Int], |a| sys_accept(a[0])),
+    SyscallEntry::new(SYSCALL_CONNECT, "connect", &[Int, Str], |a| {
+        sys_connect(a[0], a[1] as *const u8)
+    }),
+    SyscallEntry::new(SYSCALL_SENDMSG, "sendmsg", &[Int, Ptr], |a| {
+        sys_sendmsg(a[0], a[1] as *const MsgHdr)
+    }),
+    SyscallEntry::new(SYSCALL_RECVMSG, "recvmsg", &[Int, Ptr], |a| {
+        sys_recvmsg(a[0], a[1] as *mut MsgHdr)
+    }),
+    SyscallEntry::new(SYSCALL_FORK, "fork", &[], |_| sys_fork()),
+    SyscallEntry::new(SYSCALL_EXEC, "exec", &[Str, Ptr], |a| {
+        sys_exec(a[0] as *const u8, a[1] as *const usize)
+    }),
+    SyscallEntry::new(SYSCALL_WAITPID, "waitpid", &[Int, Ptr], |a| {
+        sys_waitpid(a[0] as isize, a[1] as *mut i32)
+    }),
+    SyscallEntry::new(SYSCALL_CPU_STAT, "cpu_stat", &[Ptr], |a| {
+        sys_cpu_stat(a[0] as *mut CpuStat)
+    }),
+    SyscallEntry::new(
+        SYSCALL_ENABLE_DEADLOCK_DETECT,
+        "enable_deadlock_detect",
+        &[Int],
+        |a| sys_enable_deadlock_detect(a[0]),
+    ),
+    SyscallEntry::new(
+        SYSCALL_THREAD_CREATE,
+        "thread_create",
+        &[Hex, Hex, Ptr],
+        |a| sys_thread_create(a[0], a[1], a[2] as *const ThreadAttr),
+    ),
+    SyscallEntry::new(SYSCALL_GETTID, "gettid", &[], |_| sys_gettid()),
+    SyscallEntry::new(SYSCALL_WAITTID, "waittid", &[Int, Ptr], |a| {
+        sys_waittid(a[0], a[1] as *mut i32)
+    }),
+    SyscallEntry::new(SYSCALL_THREAD_DETACH, "thread_detach", &[Int], |a| {
+        sys_thread_detach(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_MUTEX_CREATE, "mutex_create", &[Int], |a| {
+        sys_mutex_create(a[0] == 1)
+    }),
+    SyscallEntry::new(SYSCALL_MUTEX_LOCK, "mutex_lock", &[Int], |a| {
+        sys_mutex_lock(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_MUTEX_UNLOCK, "mutex_unlock", &[Int], |a| {
+        sys_mutex_unlock(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_MUTEX_TRYLOCK, "mutex_trylock", &[Int], |a| {
+        sys_mutex_trylock(a[0])
+    }),
+    SyscallEntry::new(
+        SYSCALL_MUTEX_LOCK_TIMEOUT,
+        "mutex_lock_timeout",
+        &[Int, Ptr],
+        |a| sys_mutex_lock_timeout(a[0], a[1] as *const TimeSpec),
+    ),
+    SyscallEntry::new(SYSCALL_MUTEX_DESTROY, "mutex_destroy", &[Int], |a| {
+        sys_mutex_destroy(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_SEMAPHORE_CREATE, "semaphore_create", &[Int], |a| {
+        sys_semaphore_create(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_SEMAPHORE_UP, "semaphore_up", &[Int], |a| {
+        sys_semaphore_up(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_SEMAPHORE_DOWN, "semaphore_down", &[Int], |a| {
+        sys_semaphore_down(a[0])
+    }),
+    SyscallEntry::new(
+        SYSCALL_SEMAPHORE_TRYDOWN,
+        "semaphore_trydown",
+        &[Int],
+        |a| sys_semaphore_trydown(a[0]),
+    ),
+    SyscallEntry::new(
+        SYSCALL_SEMAPHORE_DOWN_TIMEOUT,
+        "semaphore_down_timeout",
+        &[Int, Ptr],
+        |a| sys_semaphore_down_timeout(a[0], a[1] as *const TimeSpec),
+    ),
+    SyscallEntry::new(
+        SYSCALL_SEMAPHORE_DESTROY,
+        "semaphore_destroy",
+        &[Int],
+        |a| sys_semaphore_destroy(a[0]),
+    ),
+    SyscallEntry::new(SYSCALL_CONDVAR_CREATE, "condvar_create", &[], |_| {
+        sys_condvar_create()
+    }),
+    SyscallEntry::new(SYSCALL_CONDVAR_SIGNAL, "condvar_signal", &[Int], |a| {
+        sys_condvar_signal(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_CONDVAR_WAIT, "condvar_wait", &[Int, Int], |a| {
+        sys_condvar_wait(a[0], a[1])
+    }),
+    SyscallEntry::new(
+        SYSCALL_CONDVAR_WAIT_TIMEOUT,
+        "condvar_wait_timeout",
+        &[Int, Int, Ptr],
+        |a| sys_condvar_wait_timeout(a[0], a[1], a[2] as *const TimeSpec),
+    ),
+    SyscallEntry::new(SYSCALL_CONDVAR_DESTROY, "condvar_destroy", &[Int], |a| {
+        sys_condvar_destroy(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_RWLOCK_CREATE, "rwlock_create", &[], |_| {
+        sys_rwlock_create()
+    }),
+    SyscallEntry::new(SYSCALL_RWLOCK_READ, "rwlock_read", &[Int], |a| {
+        sys_rwlock_read(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_RWLOCK_WRITE, "rwlock_write", &[Int], |a| {
+        sys_rwlock_write(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_RWLOCK_UNLOCK, "rwlock_unlock", &[Int], |a| {
+        sys_rwlock_unlock(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_BARRIER_CREATE, "barrier_create", &[Int], |a| {
+        sys_barrier_create(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_BARRIER_WAIT, "barrier_wait", &[Int], |a| {
+        sys_barrier_wait(a[0])
+    }),
+    SyscallEntry::new(SYSCALL_TRACE, "trace", &[Int], |a| sys_trace(a[0])),
+    SyscallEntry::new(SYSCALL_TRACE_READ, "trace_read", &[Int, Ptr, Int], |a| {
+        sys_trace_read(a[0], a[1] as *mut u8, a[2])
+    }),
+    // SyscallEntry::new(SYSCALL_SIGACTION, "sigaction", &[Int, Ptr, Ptr], |a| {
+    //     sys_sigaction(a[0] as i32, a[1] as *const SignalAction, a[2] as *mut SignalAction)
+    // }),
+    // SyscallEntry::new(SYSCALL_SIGPROCMASK, "sigprocmask", &[Hex], |a| sys_sigprocmask(a[0] as u32)),
+    // SyscallEntry::new(SYSCALL_SIGRETURN, "sigreturn", &[], |_| sys_sigreturn()),
+];
+
+/// 单个系统调用的统计信息
+pub struct SyscallStat {
+    pub id: usize,
+    pub name: &'static str,
+    pub nargs: usize,
+    pub calls: usize,
+}
+
+/// 系统调用的调用次数
+struct SyscallCounters {
+    /// 与 SYSCALL_TABLE 一一对应
+    calls: Vec<usize>,
+    /// 未知调用号的调用次数
+    unknown: usize,
+}
+
+lazy_static! {
+    static ref SYSCALL_COUNTERS: UPSafeCell<SyscallCounters> = unsafe {
+        // 二分查找依赖调用号严格递增
+        assert!(SYSCALL_TABLE.windows(2).all(|pair| pair[0].id < pair[1].id));
+        UPSafeCell::new(SyscallCounters {
+            calls: vec![0; SYSCALL_TABLE.len()],
+            unknown: 0,
+        })
+    };
+}
+
+/// 返回每个系统调用的统计信息以及未知调用号的调用次数
+pub fn syscall_stats() -> (Vec<SyscallStat>, usize) {
+    let counters = SYSCALL_COUNTERS.exclusive_access();
+    let stats = SYSCALL_TABLE
+        .iter()
+        .zip(counters.calls.iter())
+        .map(|(entry, &calls)| SyscallStat {
+            id: entry.id,
+            name: entry.name,
+            nargs: entry.args.len(),
+            calls,
+        })
+        .collect();
+    (stats, counters.unknown)
+}
+
+pub fn syscall(syscall_id: usize, args: [usize; 6]) -> isize {
+    let index = SYSCALL_TABLE.binary_search_by_key(&syscall_id, |entry| entry.id);
+    let mut counters = SYSCALL_COUNTERS.exclusive_access();
+    let entry = match index {
+        Ok(index) => {
+            counters.calls[index] += 1;
+            &SYSCALL_TABLE[index]
+        }
+        Err(_) => {
+            counters.unknown += 1;
+            drop(counters);
+            warn!("Unsupported syscall_id: {}", syscall_id);
+            return -ENOSYS;
+        }
+    };
+    // 处理函数可能阻塞或者不再返回，调用前释放计数器
+    drop(counters);
+    let trace = current_process().inner_exclusive_access().trace.clone();
+    match trace {
+        Some(buffer) => trace_syscall(buffer, entry, args),
+        None => (entry.handler)(args),
     }
 }
@@ -0,0 +1,255 @@
+//! Socket syscalls
+
+use super::ipc::get_file;
+use crate::errno::*;
+use crate::fs::{File, Socket, SocketType, AF_UNIX, SCM_MAX_FD};
+use crate::mm::{
+    check_user_writable, copy_from_user, copy_to_user, translated_byte_buffer,
+    translated_byte_buffer_mut, translated_str, UserBuffer,
+};
+use crate::task::{current_process, current_user_token};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// sendmsg 和 recvmsg 的参数，与用户态的布局相同
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct MsgHdr {
+    /// 数据缓冲区的地址
+    pub buf: usize,
+    /// 数据缓冲区的长度
+    pub len: usize,
+    /// 文件描述符数组的地址
+    pub fds: usize,
+    /// 发送时为附带的文件描述符数量；接收时传入数组的容量，返回收到的数量
+    pub nfds: usize,
+}
+
+/// 把文件放入当前进程的文件描述符表，没有空闲的描述符时返回 EMFILE
+fn install_file(file: Arc<dyn File + Send + Sync>) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    match inner.alloc_fd() {
+        Some(fd) => {
+            inner.fd_table[fd] = Some(file);
+            fd as isize
+        }
+        None => -EMFILE,
+    }
+}
+
+/// 取出文件描述符 fd 对应的套接字，fd 不存在时返回 EBADF，不是套接字时返回 ENOTSOCK
+fn get_socket(fd: usize) -> Result<Arc<dyn File + Send + Sync>, isize> {
+    match get_file(fd) {
+        Some(file) if file.as_socket().is_some() => Ok(file),
+        Some(_) => Err(-ENOTSOCK),
+        None => Err(-EBADF),
+    }
+}
+
+/// 检查地址族、类型和协议，目前只支持 AF_UNIX 地址族的 SOCK_STREAM 和 SOCK_DGRAM，protocol 必须为 0
+fn socket_kind(domain: usize, type_: usize, protocol: usize) -> Result<SocketType, isize> {
+    if domain != AF_UNIX {
+        return Err(-EAFNOSUPPORT);
+    }
+    let kind = SocketType::from_raw(type_).ok_or(-EINVAL)?;
+    if protocol != 0 {
+        return Err(-EPROTONOSUPPORT);
+    }
+    Ok(kind)
+}
+
+/// 创建一个套接字，返回文件描述符
+pub fn sys_socket(domain: usize, type_: usize, protocol: usize) -> isize {
+    match socket_kind(domain, type_, protocol) {
+        Ok(kind) => install_file(Socket::new(kind)),
+        Err(err) => err,
+    }
+}
+
+/// 创建一对互相连接的套接字，把两个文件描述符写入 sv 指向的数组，sv 不可写时返回 EFAULT
+pub fn sys_socketpair(domain: usize, type_: usize, protocol: usize, sv: *mut usize) -> isize {
+    let kind = match socket_kind(domain, type_, protocol) {
+        Ok(kind) => kind,
+        Err(err) => return err,
+    };
+    let token = current_user_token();
+    let sv = sv as *mut [usize; 2];
+    if let Err(err) = check_user_writable(token, sv) {
+        return err;
+    }
+    let (a, b) = Socket::pair(kind);
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    let fd0 = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => return -EMFILE,
+    };
+    inner.fd_table[fd0] = Some(a);
+    let fd1 = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => {
+            inner.fd_table[fd0] = None;
+            return -EMFILE;
+        }
+    };
+    inner.fd_table[fd1] = Some(b);
+    // 前面已经检查过可写
+    match copy_to_user(token, sv, &[fd0, fd1]) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// 把套接字绑定到根目录下的 path，在文件系统中留下一个套接字节点
+/// path 不可读时返回 EFAULT，其余错误码见 Socket::bind
+pub fn sys_bind(fd: usize, path: *const u8) -> isize {
+    let path = match translated_str(current_user_token(), path) {
+        Ok(path) => path,
+        Err(err) => return err,
+    };
+    let file = match get_socket(fd) {
+        Ok(file) => file,
+        Err(err) => return err,
+    };
+    let cred = current_process().inner_exclusive_access().cred;
+    match file.as_socket().unwrap().bind(path.as_str(), &cred) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// 开始在已经绑定的流式套接字上接受连接，backlog 为等待 accept 的连接数量上限
+pub fn sys_listen(fd: usize, backlog: usize) -> isize {
+    let file = match get_socket(fd) {
+        Ok(file) => file,
+        Err(err) => return err,
+    };
+    match file.as_socket().unwrap().listen(backlog) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// 接受一个连接，没有连接时阻塞，返回新连接的文件描述符
+/// fd 不是正在监听的套接字时返回 EINVAL
+pub fn sys_accept(fd: usize) -> isize {
+    let file = match get_socket(fd) {
+        Ok(file) => file,
+        Err(err) => return err,
+    };
+    match file.as_socket().unwrap().accept() {
+        Ok(socket) => install_file(socket),
+        Err(err) => err,
+    }
+}
+
+/// 连接到绑定在 path 上的套接字，需要套接字节点的写权限
+/// 流式套接字的连接进入对方的 backlog 后立即返回；数据报套接字记下默认的发送目标
+/// path 不可读时返回 EFAULT，其余错误码见 Socket::connect
+pub fn sys_connect(fd: usize, path: *const u8) -> isize {
+    let path = match translated_str(current_user_token(), path) {
+        Ok(path) => path,
+        Err(err) => return err,
+    };
+    let file = match get_socket(fd) {
+        Ok(file) => file,
+        Err(err) => return err,
+    };
+    let cred = current_process().inner_exclusive_access().cred;
+    match file.as_socket().unwrap().connect(path.as_str(), &cred) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// 发送 msg 描述的数据，并把其中的文件描述符对应的文件传给对端，返回发送的字节数
+/// 附带的描述符超过 SCM_MAX_FD 个时返回 EINVAL，其中有不存在的描述符时返回 EBADF，
+/// msg 或者它描述的缓冲区不可读时返回 EFAULT，其余错误码见 Socket::send
+pub fn sys_sendmsg(fd: usize, msg: *const MsgHdr) -> isize {
+    let token = current_user_token();
+    let msg = match copy_from_user(token, msg) {
+        Ok(msg) => msg,
+        Err(err) => return err,
+    };
+    let file = match get_socket(fd) {
+        Ok(file) => file,
+        Err(err) => return err,
+    };
+    if msg.nfds > SCM_MAX_FD {
+        return -EINVAL;
+    }
+    let mut files = Vec::new();
+    for i in 0..msg.nfds {
+        let fd = match copy_from_user(token, unsafe { (msg.fds as *const usize).add(i) }) {
+            Ok(fd) => fd,
+            Err(err) => return err,
+        };
+        match get_file(fd) {
+            Some(file) => files.push(file),
+            None => return -EBADF,
+        }
+    }
+    let data: Vec<u8> = match translated_byte_buffer(token, msg.buf as *const u8, msg.len) {
+        Ok(buffers) => buffers
+            .iter()
+            .flat_map(|slice| slice.iter().copied())
+            .collect(),
+        Err(err) => return err,
+    };
+    match file.as_socket().unwrap().send(&data, files) {
+        Ok(len) => len as isize,
+        Err(err) => err,
+    }
+}
+
+/// 接收数据写入 msg 描述的缓冲区，返回读取的字节数，对端已经关闭时返回 0
+/// 附带的文件放入新的文件描述符并写入 msg.fds，数量写回 msg.nfds，超出容量的文件被关闭
+/// 流式套接字没有连接时返回 ENOTCONN
+/// msg、它描述的缓冲区或者文件描述符数组不可写时返回 EFAULT，此时不会接收数据
+pub fn sys_recvmsg(fd: usize, msg: *mut MsgHdr) -> isize {
+    let token = current_user_token();
+    let mut hdr = match check_user_writable(token, msg).and_then(|_| copy_from_user(token, msg)) {
+        Ok(hdr) => hdr,
+        Err(err) => return err,
+    };
+    let file = match get_socket(fd) {
+        Ok(file) => file,
+        Err(err) => return err,
+    };
+    let buf = match translated_byte_buffer_mut(token, hdr.buf as *mut u8, hdr.len) {
+        Ok(buffers) => UserBuffer::new(buffers),
+        Err(err) => return err,
+    };
+    // 一条消息最多附带 SCM_MAX_FD 个文件，更大的容量不会被用到
+    let fds = hdr.fds as *mut usize;
+    let max_fds = hdr.nfds.min(SCM_MAX_FD);
+    if let Err(err) = translated_byte_buffer_mut(
+        token,
+        fds as *mut u8,
+        max_fds * core::mem::size_of::<usize>(),
+    ) {
+        return err;
+    }
+    let (len, files) = match file.as_socket().unwrap().recv(buf) {
+        Ok(received) => received,
+        Err(err) => return err,
+    };
+    let mut nfds = 0;
+    for file in files.into_iter().take(max_fds) {
+        let new_fd = install_file(file);
+        if new_fd < 0 {
+            break;
+        }
+        // 等待期间其他线程可能取消了映射
+        if let Err(err) = copy_to_user(token, unsafe { fds.add(nfds) }, &(new_fd as usize)) {
+            return err;
+        }
+        nfds += 1;
+    }
+    hdr.nfds = nfds;
+    match copy_to_user(token, msg, &hdr) {
+        Ok(()) => len as isize,
+        Err(err) => err,
+    }
+}
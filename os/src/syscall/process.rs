@@ -1,12 +1,15 @@
 //! App management syscalls
 
+use crate::config::USER_STACK_SIZE;
+use crate::errno::*;
 use crate::fs::{open_file, OpenFlags};
-use crate::mm::translated_ref;
-use crate::task::pid2process;
+use crate::mm::{check_user_writable, copy_from_user, copy_to_user};
+use crate::task::{pid2process, RLimit, TaskUsage, MAY_EXEC, RLIMIT_NLIMITS, RLIMIT_NPROC};
+use crate::timer::{get_time, ticks_to_clock_t, TimeVal};
 use crate::{
-    mm::{translated_refmut, translated_str},
+    mm::translated_str,
     task::{
-        current_process, current_task, current_user_token, exit_current_and_run_next,
+        current_process, current_task, current_user_token, exit_current_and_run_next, queue_work,
         suspend_current_and_run_next, SignalFlags,
     },
     timer::get_time_ms,
@@ -35,8 +38,14 @@ pub fn sys_getpid() -> isize {
     current_task().unwrap().process.upgrade().unwrap().getpid() as isize
 }
 
+/// 子进程数量达到 RLIMIT_NPROC 时返回 EAGAIN
 pub fn sys_fork() -> isize {
     let current_process = current_process();
+    let inner = current_process.inner_exclusive_access();
+    if inner.children.len() >= inner.rlimits[RLIMIT_NPROC].rlim_cur {
+        return -EAGAIN;
+    }
+    drop(inner);
     let new_process = current_process.fork();
     let new_pid = new_process.getpid();
 
@@ -50,39 +59,76 @@ pub fn sys_fork() -> isize {
     new_pid as isize
 }
 
+/// exec 的参数在新的用户栈上最多占用的空间，剩下的留给程序使用
+const ARG_MAX: usize = USER_STACK_SIZE / 2;
+
+/// 文件不存在返回 ENOENT，没有读权限或者执行权限返回 EACCES，
+/// 新的地址空间超过 RLIMIT_AS 时返回 ENOMEM，path 或者参数不可读时返回 EFAULT，
+/// 参数超过 ARG_MAX 时返回 E2BIG
 pub fn sys_exec(path: *const u8, mut args: *const usize) -> isize {
     let token = current_user_token();
-    let path = translated_str(token, path);
+    let path = match translated_str(token, path) {
+        Ok(path) => path,
+        Err(err) => return err,
+    };
 
     let mut args_vec: Vec<String> = Vec::new();
+    // 参数字符串和 argv 数组在用户栈上占用的空间
+    let mut args_size = core::mem::size_of::<usize>();
     loop {
-        let arg_str_ptr = *translated_ref(token, args);
+        let arg_str_ptr = match copy_from_user(token, args) {
+            Ok(arg_str_ptr) => arg_str_ptr,
+            Err(err) => return err,
+        };
         if arg_str_ptr == 0 {
             break;
         }
-        args_vec.push(translated_str(token, arg_str_ptr as *const u8));
+        let arg = match translated_str(token, arg_str_ptr as *const u8) {
+            Ok(arg) => arg,
+            Err(err) => return err,
+        };
+        args_size += arg.len() + 1 + core::mem::size_of::<usize>();
+        if args_size > ARG_MAX {
+            return -E2BIG;
+        }
+        args_vec.push(arg);
         unsafe {
             args = args.add(1);
         }
     }
 
     // 当执行获取应用的 ELF 数据的操作时，首先调用 open_file 函数
-    if let Some(app_inode) = open_file(path.as_str(), OpenFlags::RDONLY) {
-        // 将文件的数据全部读到一个 all_data 向量中
-        // 就可以从向量 all_data 中拿到应用中的 ELF 数据，当解析完毕并创建完应用地址空间后该向量将会被回收。
-        let all_data = app_inode.read_all();
-        let process = current_process();
-        let argc = args_vec.len();
-        process.exec(all_data.as_slice(), args_vec);
-        // return argc because cx.x[10] will be covered with it later
-        argc as isize
-    } else {
-        -1
+    // 执行文件需要读权限和执行权限
+    let process = current_process();
+    let cred = process.inner_exclusive_access().cred;
+    let app_inode = match open_file(path.as_str(), OpenFlags::RDONLY, &cred) {
+        Ok(app_inode) => app_inode,
+        Err(err) => return err,
+    };
+    if !app_inode.permits(&cred, MAY_EXEC) {
+        return -EACCES;
+    }
+    // 将文件的数据全部读到一个 all_data 向量中
+    // 就可以从向量 all_data 中拿到应用中的 ELF 数据，当解析完毕并创建完应用地址空间后该向量将会被回收。
+    let all_data = app_inode.read_all();
+    let argc = args_vec.len();
+    if !process.exec(all_data.as_slice(), args_vec) {
+        return -ENOMEM;
     }
+    // return argc because cx.x[10] will be covered with it later
+    argc as isize
 }
 
+/// 没有符合条件的子进程返回 ECHILD，子进程都还没有退出返回 EAGAIN
+/// exit_code_ptr 为空指针时不写回退出码，不可写时返回 EFAULT，此时不会回收子进程
 pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
     //trace!("kernel: sys_waitpid");
+    let token = current_user_token();
+    if !exit_code_ptr.is_null() {
+        if let Err(err) = check_user_writable(token, exit_code_ptr) {
+            return err;
+        }
+    }
     let task = current_process();
     // find a child process
 
@@ -93,7 +139,7 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
         .iter()
         .any(|p| pid == -1 || pid as usize == p.getpid())
     {
-        return -1;
+        return -ECHILD;
         // ---- release current PCB
     }
     let pair = inner.children.iter().enumerate().find(|(_, p)| {
@@ -107,26 +153,111 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
         assert_eq!(Arc::strong_count(&child), 1);
         let found_pid = child.getpid();
         // ++++ temporarily access child PCB exclusively
-        let exit_code = child.inner_exclusive_access().exit_code;
+        let child_inner = child.inner_exclusive_access();
+        let exit_code = child_inner.exit_code;
+        // 子进程以及它回收的子进程的资源使用统计并入当前进程
+        let mut child_usage = child_inner.usage();
+        child_usage += child_inner.children_usage;
+        drop(child_inner);
         // ++++ release child PCB
-        *translated_refmut(inner.memory_set.token(), exit_code_ptr) = exit_code;
+        inner.children_usage += child_usage;
+        // 子进程剩下的内核栈、页表等资源交给工作队列释放
+        queue_work(move || drop(child));
+        if !exit_code_ptr.is_null() {
+            // 前面已经检查过可写，回收子进程之后不会再失败
+            copy_to_user(token, exit_code_ptr, &exit_code).ok();
+        }
         found_pid as isize
     } else {
-        -2
+        -EAGAIN
     }
     // ---- release current PCB automatically
 }
 
+/// times 返回的时间，以时钟计数表示
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Tms {
+    pub tms_utime: usize,
+    pub tms_stime: usize,
+    pub tms_cutime: usize,
+    pub tms_cstime: usize,
+}
+
+/// 返回启动以来的时钟计数，tms 不可写时返回 EFAULT
+pub fn sys_times(tms: *mut Tms) -> isize {
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let usage = process_inner.usage();
+    let children_usage = process_inner.children_usage;
+    drop(process_inner);
+    let value = Tms {
+        tms_utime: ticks_to_clock_t(usage.utime),
+        tms_stime: ticks_to_clock_t(usage.stime),
+        tms_cutime: ticks_to_clock_t(children_usage.utime),
+        tms_cstime: ticks_to_clock_t(children_usage.stime),
+    };
+    match copy_to_user(current_user_token(), tms, &value) {
+        Ok(()) => ticks_to_clock_t(get_time()) as isize,
+        Err(err) => err,
+    }
+}
+
+pub const RUSAGE_SELF: isize = 0;
+pub const RUSAGE_CHILDREN: isize = -1;
+pub const RUSAGE_THREAD: isize = 1;
+
+/// getrusage 返回的资源使用统计，只包含内核统计的字段
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RUsage {
+    pub ru_utime: TimeVal,
+    pub ru_stime: TimeVal,
+    /// 没有换页机制，所有的访存异常都计为 minor fault
+    pub ru_minflt: usize,
+    pub ru_majflt: usize,
+    pub ru_nvcsw: usize,
+    pub ru_nivcsw: usize,
+}
+
+impl From<TaskUsage> for RUsage {
+    fn from(usage: TaskUsage) -> Self {
+        Self {
+            ru_utime: TimeVal::from_ticks(usage.utime),
+            ru_stime: TimeVal::from_ticks(usage.stime),
+            ru_minflt: usage.page_faults,
+            ru_majflt: 0,
+            ru_nvcsw: usage.nvcsw,
+            ru_nivcsw: usage.nivcsw,
+        }
+    }
+}
+
+/// who 非法时返回 EINVAL，rusage 不可写时返回 EFAULT
+pub fn sys_getrusage(who: isize, rusage: *mut RUsage) -> isize {
+    let usage = match who {
+        RUSAGE_SELF => current_process().inner_exclusive_access().usage(),
+        RUSAGE_CHILDREN => current_process().inner_exclusive_access().children_usage,
+        RUSAGE_THREAD => current_task().unwrap().inner_exclusive_access().usage,
+        _ => return -EINVAL,
+    };
+    match copy_to_user(current_user_token(), rusage, &usage.into()) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// 进程不存在返回 ESRCH，信号非法返回 EINVAL
 pub fn sys_kill(pid: usize, signum: i32) -> isize {
     if let Some(process) = pid2process(pid) {
         if let Some(flag) = SignalFlags::from_bits(1 << signum) {
             process.inner_exclusive_access().signals |= flag;
             0
         } else {
-            -1
+            -EINVAL
         }
     } else {
-        -1
+        -ESRCH
     }
 }
 
@@ -198,3 +329,76 @@ pub fn sys_sigaction(
     }
 }
 */
+
+/// 读取资源限制，resource 非法时返回 EINVAL，rlim 不可写时返回 EFAULT
+pub fn sys_getrlimit(resource: usize, rlim: *mut RLimit) -> isize {
+    if resource >= RLIMIT_NLIMITS {
+        return -EINVAL;
+    }
+    let process = current_process();
+    let limit = process.inner_exclusive_access().rlimits[resource];
+    match copy_to_user(current_user_token(), rlim, &limit) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// 设置资源限制，软限制不能超过硬限制，否则返回 EINVAL；硬限制只能降低不能提高，否则返回 EPERM
+/// rlim 不可读时返回 EFAULT
+pub fn sys_setrlimit(resource: usize, rlim: *const RLimit) -> isize {
+    if resource >= RLIMIT_NLIMITS {
+        return -EINVAL;
+    }
+    let limit = match copy_from_user(current_user_token(), rlim) {
+        Ok(limit) => limit,
+        Err(err) => return err,
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if limit.rlim_cur > limit.rlim_max {
+        return -EINVAL;
+    }
+    if limit.rlim_max > inner.rlimits[resource].rlim_max {
+        return -EPERM;
+    }
+    inner.rlimits[resource] = limit;
+    0
+}
+
+pub fn sys_getuid() -> isize {
+    current_process().inner_exclusive_access().cred.uid as isize
+}
+
+pub fn sys_geteuid() -> isize {
+    current_process().inner_exclusive_access().cred.euid as isize
+}
+
+pub fn sys_getgid() -> isize {
+    current_process().inner_exclusive_access().cred.gid as isize
+}
+
+pub fn sys_getegid() -> isize {
+    current_process().inner_exclusive_access().cred.egid as isize
+}
+
+/// root 设置实际和有效用户 id，普通用户只能把有效用户 id 设置为实际用户 id，否则返回 EPERM
+pub fn sys_setuid(uid: usize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if inner.cred.set_uid(uid as u32) {
+        0
+    } else {
+        -EPERM
+    }
+}
+
+/// 规则与 sys_setuid 相同
+pub fn sys_setgid(gid: usize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if inner.cred.set_gid(gid as u32) {
+        0
+    } else {
+        -EPERM
+    }
+}
@@ -1,16 +1,53 @@
-use crate::sync::{Condvar, Mutex, MutexBlocking, MutexSpin, Semaphore};
-use crate::task::{block_current_and_run_next, current_process, current_task};
-use crate::timer::{add_timer, get_time_ms};
+use crate::errno::*;
+use crate::mm::{copy_from_user, translated_pa};
+use crate::sync::{
+    futex_dequeue, futex_enqueue, futex_wake, Barrier, Condvar, Mutex, MutexBlocking, MutexSpin,
+    Resource, RwLock, Semaphore, FUTEX_WAIT, FUTEX_WAKE,
+};
+use crate::task::{block_current_and_run_next, current_process, current_task, current_user_token};
+use crate::timer::{add_timer, get_time, TimeSpec};
 use alloc::sync::Arc;
 
-pub fn sys_sleep(ms: usize) -> isize {
-    let expire_ms = get_time_ms() + ms;
-    let task = current_task().unwrap();
-    add_timer(expire_ms, task);
-    block_current_and_run_next();
+fn current_tid() -> usize {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .res
+        .as_ref()
+        .unwrap()
+        .tid
+}
+
+/// 把用户传入的相对超时时间换算成到期时刻，时间非法时返回 EINVAL，不可读时返回 EFAULT
+/// 超时时间过长时到期时刻取 usize::MAX，即一直等待
+pub fn timeout_to_expire(timeout: *const TimeSpec) -> Result<usize, isize> {
+    let timeout = copy_from_user(current_user_token(), timeout)?;
+    if !timeout.is_valid() {
+        return Err(-EINVAL);
+    }
+    Ok(get_time().saturating_add(timeout.to_ticks()))
+}
+
+/// 开启或关闭当前进程的死锁检测，enabled 为 1 开启，为 0 关闭
+/// 开启后，会导致不安全状态的 mutex_lock 和 semaphore_down 返回 EDEADLK 而不阻塞
+pub fn sys_enable_deadlock_detect(enabled: usize) -> isize {
+    if enabled > 1 {
+        return -EINVAL;
+    }
+    current_process().inner_exclusive_access().deadlock.enabled = enabled == 1;
     0
 }
 
+/// 取出编号为 id 的同步对象，编号越界或者对象已经被销毁时返回 None
+fn get_object<T: ?Sized>(list: &[Option<Arc<T>>], id: usize) -> Option<Arc<T>> {
+    list.get(id).cloned().flatten()
+}
+
+/// 阻塞返回后检查等待的对象是否已经被销毁（槽位为空或者被新对象复用）
+fn is_destroyed<T: ?Sized>(list: &[Option<Arc<T>>], id: usize, object: &Arc<T>) -> bool {
+    !matches!(list.get(id), Some(Some(current)) if Arc::ptr_eq(current, object))
+}
+
 pub fn sys_mutex_create(blocking: bool) -> isize {
     let process = current_process();
     let mutex: Option<Arc<dyn Mutex>> = if !blocking {
@@ -19,7 +56,7 @@ pub fn sys_mutex_create(blocking: bool) -> isize {
         Some(Arc::new(MutexBlocking::new()))
     };
     let mut process_inner = process.inner_exclusive_access();
-    if let Some(id) = process_inner
+    let id = if let Some(id) = process_inner
         .mutex_list
         .iter()
         .enumerate()
@@ -27,30 +64,148 @@ pub fn sys_mutex_create(blocking: bool) -> isize {
         .map(|(id, _)| id)
     {
         process_inner.mutex_list[id] = mutex;
-        id as isize
+        id
     } else {
         process_inner.mutex_list.push(mutex);
-        process_inner.mutex_list.len() as isize - 1
-    }
+        process_inner.mutex_list.len() - 1
+    };
+    process_inner.deadlock.add_resource(Resource::Mutex(id), 1);
+    id as isize
 }
 
+/// 获取互斥锁，mutex_id 非法时返回 EINVAL，等待期间互斥锁被销毁时返回 EIDRM
 pub fn sys_mutex_lock(mutex_id: usize) -> isize {
+    let tid = current_tid();
     let process = current_process();
-    let process_inner = process.inner_exclusive_access();
-    let mutex = Arc::clone(process_inner.mutex_list[mutex_id].as_ref().unwrap());
+    let mut process_inner = process.inner_exclusive_access();
+    let mutex = match get_object(&process_inner.mutex_list, mutex_id) {
+        Some(mutex) => mutex,
+        None => return -EINVAL,
+    };
+    if !process_inner
+        .deadlock
+        .request(tid, Resource::Mutex(mutex_id))
+    {
+        return -EDEADLK;
+    }
     drop(process_inner);
     drop(process);
     mutex.lock();
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    if is_destroyed(&process_inner.mutex_list, mutex_id, &mutex) {
+        process_inner
+            .deadlock
+            .cancel(tid, Resource::Mutex(mutex_id));
+        return -EIDRM;
+    }
+    process_inner
+        .deadlock
+        .acquire(tid, Resource::Mutex(mutex_id));
     0
 }
 
-pub fn sys_mutex_unlock(mutex_id: usize) -> isize {
+/// 尝试获取互斥锁，锁已经被持有时返回 EBUSY，mutex_id 非法时返回 EINVAL
+pub fn sys_mutex_trylock(mutex_id: usize) -> isize {
+    let tid = current_tid();
     let process = current_process();
     let process_inner = process.inner_exclusive_access();
-    let mutex = Arc::clone(process_inner.mutex_list[mutex_id].as_ref().unwrap());
+    let mutex = match get_object(&process_inner.mutex_list, mutex_id) {
+        Some(mutex) => mutex,
+        None => return -EINVAL,
+    };
+    drop(process_inner);
+    if !mutex.try_lock() {
+        return -EBUSY;
+    }
+    process
+        .inner_exclusive_access()
+        .deadlock
+        .acquire(tid, Resource::Mutex(mutex_id));
+    0
+}
+
+/// 获取互斥锁，最多等待 timeout 指定的相对时间，超时返回 ETIMEDOUT，
+/// 时间或者 mutex_id 非法时返回 EINVAL，等待期间互斥锁被销毁时返回 EIDRM
+pub fn sys_mutex_lock_timeout(mutex_id: usize, timeout: *const TimeSpec) -> isize {
+    let expire = match timeout_to_expire(timeout) {
+        Ok(expire) => expire,
+        Err(err) => return err,
+    };
+    let tid = current_tid();
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let mutex = match get_object(&process_inner.mutex_list, mutex_id) {
+        Some(mutex) => mutex,
+        None => return -EINVAL,
+    };
+    if !process_inner
+        .deadlock
+        .request(tid, Resource::Mutex(mutex_id))
+    {
+        return -EDEADLK;
+    }
     drop(process_inner);
     drop(process);
-    mutex.unlock();
+    let locked = mutex.lock_timeout(expire);
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    if is_destroyed(&process_inner.mutex_list, mutex_id, &mutex) {
+        process_inner
+            .deadlock
+            .cancel(tid, Resource::Mutex(mutex_id));
+        -EIDRM
+    } else if locked {
+        process_inner
+            .deadlock
+            .acquire(tid, Resource::Mutex(mutex_id));
+        0
+    } else {
+        process_inner
+            .deadlock
+            .cancel(tid, Resource::Mutex(mutex_id));
+        -ETIMEDOUT
+    }
+}
+
+/// 释放互斥锁，mutex_id 非法时返回 EINVAL，锁没有被当前线程持有时返回 EPERM
+pub fn sys_mutex_unlock(mutex_id: usize) -> isize {
+    let tid = current_tid();
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let mutex = match get_object(&process_inner.mutex_list, mutex_id) {
+        Some(mutex) => mutex,
+        None => return -EINVAL,
+    };
+    drop(process_inner);
+    if !mutex.unlock() {
+        return -EPERM;
+    }
+    process
+        .inner_exclusive_access()
+        .deadlock
+        .release(tid, Resource::Mutex(mutex_id));
+    0
+}
+
+/// 销毁互斥锁并回收它的编号，mutex_id 非法时返回 EINVAL
+/// 阻塞在这把锁上的线程被唤醒，它们的加锁操作返回 EIDRM
+pub fn sys_mutex_destroy(mutex_id: usize) -> isize {
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let mutex = match process_inner
+        .mutex_list
+        .get_mut(mutex_id)
+        .and_then(Option::take)
+    {
+        Some(mutex) => mutex,
+        None => return -EINVAL,
+    };
+    process_inner
+        .deadlock
+        .remove_resource(Resource::Mutex(mutex_id));
+    drop(process_inner);
+    mutex.destroy();
     0
 }
 
@@ -72,24 +227,142 @@ pub fn sys_semaphore_create(res_count: usize) -> isize {
             .push(Some(Arc::new(Semaphore::new(res_count))));
         process_inner.semaphore_list.len() - 1
     };
+    process_inner
+        .deadlock
+        .add_resource(Resource::Semaphore(id), res_count);
     id as isize
 }
 
+/// V 操作，sem_id 非法时返回 EINVAL
 pub fn sys_semaphore_up(sem_id: usize) -> isize {
+    let tid = current_tid();
     let process = current_process();
-    let process_inner = process.inner_exclusive_access();
-    let sem = Arc::clone(process_inner.semaphore_list[sem_id].as_ref().unwrap());
+    let mut process_inner = process.inner_exclusive_access();
+    let sem = match get_object(&process_inner.semaphore_list, sem_id) {
+        Some(sem) => sem,
+        None => return -EINVAL,
+    };
+    process_inner
+        .deadlock
+        .release(tid, Resource::Semaphore(sem_id));
     drop(process_inner);
     sem.up();
     0
 }
 
+/// P 操作，sem_id 非法时返回 EINVAL，等待期间信号量被销毁时返回 EIDRM
 pub fn sys_semaphore_down(sem_id: usize) -> isize {
+    let tid = current_tid();
     let process = current_process();
-    let process_inner = process.inner_exclusive_access();
-    let sem = Arc::clone(process_inner.semaphore_list[sem_id].as_ref().unwrap());
+    let mut process_inner = process.inner_exclusive_access();
+    let sem = match get_object(&process_inner.semaphore_list, sem_id) {
+        Some(sem) => sem,
+        None => return -EINVAL,
+    };
+    if !process_inner
+        .deadlock
+        .request(tid, Resource::Semaphore(sem_id))
+    {
+        return -EDEADLK;
+    }
     drop(process_inner);
+    drop(process);
     sem.down();
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    if is_destroyed(&process_inner.semaphore_list, sem_id, &sem) {
+        process_inner
+            .deadlock
+            .cancel(tid, Resource::Semaphore(sem_id));
+        return -EIDRM;
+    }
+    process_inner
+        .deadlock
+        .acquire(tid, Resource::Semaphore(sem_id));
+    0
+}
+
+/// 信号量没有可用资源时返回 EAGAIN 而不阻塞，sem_id 非法时返回 EINVAL
+pub fn sys_semaphore_trydown(sem_id: usize) -> isize {
+    let tid = current_tid();
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let sem = match get_object(&process_inner.semaphore_list, sem_id) {
+        Some(sem) => sem,
+        None => return -EINVAL,
+    };
+    drop(process_inner);
+    if !sem.try_down() {
+        return -EAGAIN;
+    }
+    process
+        .inner_exclusive_access()
+        .deadlock
+        .acquire(tid, Resource::Semaphore(sem_id));
+    0
+}
+
+/// P 操作，最多等待 timeout 指定的相对时间，超时返回 ETIMEDOUT，
+/// 时间或者 sem_id 非法时返回 EINVAL，等待期间信号量被销毁时返回 EIDRM
+pub fn sys_semaphore_down_timeout(sem_id: usize, timeout: *const TimeSpec) -> isize {
+    let expire = match timeout_to_expire(timeout) {
+        Ok(expire) => expire,
+        Err(err) => return err,
+    };
+    let tid = current_tid();
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let sem = match get_object(&process_inner.semaphore_list, sem_id) {
+        Some(sem) => sem,
+        None => return -EINVAL,
+    };
+    if !process_inner
+        .deadlock
+        .request(tid, Resource::Semaphore(sem_id))
+    {
+        return -EDEADLK;
+    }
+    drop(process_inner);
+    drop(process);
+    let acquired = sem.down_timeout(expire);
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    if is_destroyed(&process_inner.semaphore_list, sem_id, &sem) {
+        process_inner
+            .deadlock
+            .cancel(tid, Resource::Semaphore(sem_id));
+        -EIDRM
+    } else if acquired {
+        process_inner
+            .deadlock
+            .acquire(tid, Resource::Semaphore(sem_id));
+        0
+    } else {
+        process_inner
+            .deadlock
+            .cancel(tid, Resource::Semaphore(sem_id));
+        -ETIMEDOUT
+    }
+}
+
+/// 销毁信号量并回收它的编号，sem_id 非法时返回 EINVAL
+/// 阻塞在这个信号量上的线程被唤醒，它们的 P 操作返回 EIDRM
+pub fn sys_semaphore_destroy(sem_id: usize) -> isize {
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let sem = match process_inner
+        .semaphore_list
+        .get_mut(sem_id)
+        .and_then(Option::take)
+    {
+        Some(sem) => sem,
+        None => return -EINVAL,
+    };
+    process_inner
+        .deadlock
+        .remove_resource(Resource::Semaphore(sem_id));
+    drop(process_inner);
+    sem.destroy();
     0
 }
 
@@ -114,21 +387,258 @@ pub fn sys_condvar_create() -> isize {
     id as isize
 }
 
+/// 唤醒一个等待者，condvar_id 非法时返回 EINVAL
 pub fn sys_condvar_signal(condvar_id: usize) -> isize {
     let process = current_process();
     let process_inner = process.inner_exclusive_access();
-    let condvar = Arc::clone(process_inner.condvar_list[condvar_id].as_ref().unwrap());
+    let condvar = match get_object(&process_inner.condvar_list, condvar_id) {
+        Some(condvar) => condvar,
+        None => return -EINVAL,
+    };
     drop(process_inner);
     condvar.signal();
     0
 }
 
+/// 等待期间释放互斥锁，被唤醒后重新持有它，重新加锁不经过死锁检测
+/// 编号非法时返回 EINVAL；等待期间条件变量被销毁时重新持有互斥锁后返回 EIDRM
 pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    let tid = current_tid();
     let process = current_process();
-    let process_inner = process.inner_exclusive_access();
-    let condvar = Arc::clone(process_inner.condvar_list[condvar_id].as_ref().unwrap());
-    let mutex = Arc::clone(process_inner.mutex_list[mutex_id].as_ref().unwrap());
+    let mut process_inner = process.inner_exclusive_access();
+    let (condvar, mutex) = match (
+        get_object(&process_inner.condvar_list, condvar_id),
+        get_object(&process_inner.mutex_list, mutex_id),
+    ) {
+        (Some(condvar), Some(mutex)) => (condvar, mutex),
+        _ => return -EINVAL,
+    };
+    process_inner
+        .deadlock
+        .release(tid, Resource::Mutex(mutex_id));
     drop(process_inner);
+    drop(process);
     condvar.wait(mutex);
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    process_inner
+        .deadlock
+        .acquire(tid, Resource::Mutex(mutex_id));
+    if is_destroyed(&process_inner.condvar_list, condvar_id, &condvar) {
+        -EIDRM
+    } else {
+        0
+    }
+}
+
+/// 与 sys_condvar_wait 相同，最多等待 timeout 指定的相对时间
+/// 超时返回 ETIMEDOUT，时间或者编号非法时返回 EINVAL，等待期间条件变量被销毁时返回 EIDRM，
+/// 只要开始了等待，返回时都重新持有互斥锁
+pub fn sys_condvar_wait_timeout(
+    condvar_id: usize,
+    mutex_id: usize,
+    timeout: *const TimeSpec,
+) -> isize {
+    let expire = match timeout_to_expire(timeout) {
+        Ok(expire) => expire,
+        Err(err) => return err,
+    };
+    let tid = current_tid();
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let (condvar, mutex) = match (
+        get_object(&process_inner.condvar_list, condvar_id),
+        get_object(&process_inner.mutex_list, mutex_id),
+    ) {
+        (Some(condvar), Some(mutex)) => (condvar, mutex),
+        _ => return -EINVAL,
+    };
+    process_inner
+        .deadlock
+        .release(tid, Resource::Mutex(mutex_id));
+    drop(process_inner);
+    drop(process);
+    let signaled = condvar.wait_timeout(mutex, expire);
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    process_inner
+        .deadlock
+        .acquire(tid, Resource::Mutex(mutex_id));
+    if is_destroyed(&process_inner.condvar_list, condvar_id, &condvar) {
+        -EIDRM
+    } else if signaled {
+        0
+    } else {
+        -ETIMEDOUT
+    }
+}
+
+/// 销毁条件变量并回收它的编号，condvar_id 非法时返回 EINVAL
+/// 等待在条件变量上的线程被唤醒，重新持有互斥锁后返回 EIDRM
+pub fn sys_condvar_destroy(condvar_id: usize) -> isize {
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let condvar = match process_inner
+        .condvar_list
+        .get_mut(condvar_id)
+        .and_then(Option::take)
+    {
+        Some(condvar) => condvar,
+        None => return -EINVAL,
+    };
+    drop(process_inner);
+    condvar.destroy();
+    0
+}
+
+pub fn sys_rwlock_create() -> isize {
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let id = if let Some(id) = process_inner
+        .rwlock_list
+        .iter()
+        .enumerate()
+        .find(|(_, item)| item.is_none())
+        .map(|(id, _)| id)
+    {
+        process_inner.rwlock_list[id] = Some(Arc::new(RwLock::new()));
+        id
+    } else {
+        process_inner
+            .rwlock_list
+            .push(Some(Arc::new(RwLock::new())));
+        process_inner.rwlock_list.len() - 1
+    };
+    id as isize
+}
+
+/// 获取读锁，有写者持有或者等待时阻塞，rwlock_id 非法时返回 EINVAL
+pub fn sys_rwlock_read(rwlock_id: usize) -> isize {
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let rwlock = match get_object(&process_inner.rwlock_list, rwlock_id) {
+        Some(rwlock) => rwlock,
+        None => return -EINVAL,
+    };
+    drop(process_inner);
+    drop(process);
+    rwlock.read();
+    0
+}
+
+/// 获取写锁，有其他线程持有锁时阻塞，rwlock_id 非法时返回 EINVAL
+pub fn sys_rwlock_write(rwlock_id: usize) -> isize {
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let rwlock = match get_object(&process_inner.rwlock_list, rwlock_id) {
+        Some(rwlock) => rwlock,
+        None => return -EINVAL,
+    };
+    drop(process_inner);
+    drop(process);
+    rwlock.write();
     0
 }
+
+/// 释放读锁或写锁，rwlock_id 非法时返回 EINVAL，当前线程没有持有锁时返回 EPERM
+pub fn sys_rwlock_unlock(rwlock_id: usize) -> isize {
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let rwlock = match get_object(&process_inner.rwlock_list, rwlock_id) {
+        Some(rwlock) => rwlock,
+        None => return -EINVAL,
+    };
+    drop(process_inner);
+    drop(process);
+    if rwlock.unlock() {
+        0
+    } else {
+        -EPERM
+    }
+}
+
+/// 创建一个每轮需要 count 个线程到达的屏障，count 为 0 时返回 EINVAL
+pub fn sys_barrier_create(count: usize) -> isize {
+    if count == 0 {
+        return -EINVAL;
+    }
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let id = if let Some(id) = process_inner
+        .barrier_list
+        .iter()
+        .enumerate()
+        .find(|(_, item)| item.is_none())
+        .map(|(id, _)| id)
+    {
+        process_inner.barrier_list[id] = Some(Arc::new(Barrier::new(count)));
+        id
+    } else {
+        process_inner
+            .barrier_list
+            .push(Some(Arc::new(Barrier::new(count))));
+        process_inner.barrier_list.len() - 1
+    };
+    id as isize
+}
+
+/// 等待本轮的所有线程到达屏障，最后到达的线程返回 1，其他线程返回 0，barrier_id 非法时返回 EINVAL
+pub fn sys_barrier_wait(barrier_id: usize) -> isize {
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let barrier = match get_object(&process_inner.barrier_list, barrier_id) {
+        Some(barrier) => barrier,
+        None => return -EINVAL,
+    };
+    drop(process_inner);
+    drop(process);
+    barrier.wait() as isize
+}
+
+/// futex 操作
+/// FUTEX_WAIT: 如果 *uaddr 等于 val 则阻塞，直到被 FUTEX_WAKE 唤醒或者经过 timeout 指定的相对时间，
+///             timeout 为空指针表示一直等待。被唤醒返回 0，值不相等返回 EAGAIN，超时返回 ETIMEDOUT
+/// FUTEX_WAKE: 唤醒最多 val 个等待在 uaddr 上的线程，返回唤醒的数量
+/// uaddr 没有 4 字节对齐返回 EINVAL，没有被映射返回 EFAULT，op 非法返回 ENOSYS
+pub fn sys_futex(uaddr: *const u32, op: usize, val: u32, timeout: *const TimeSpec) -> isize {
+    let token = current_user_token();
+    if uaddr as usize % core::mem::size_of::<u32>() != 0 {
+        return -EINVAL;
+    }
+    let pa: usize = match translated_pa(token, uaddr as usize) {
+        Some(pa) => pa.into(),
+        None => return -EFAULT,
+    };
+    match op {
+        FUTEX_WAIT => {
+            let expire = if timeout.is_null() {
+                None
+            } else {
+                match timeout_to_expire(timeout) {
+                    Ok(expire) => Some(expire),
+                    Err(err) => return err,
+                }
+            };
+            // 内核中不会被抢占，检查值和加入等待队列之间不会错过其他线程的唤醒
+            match copy_from_user(token, uaddr) {
+                Ok(current) if current != val => return -EAGAIN,
+                Ok(_) => {}
+                Err(err) => return err,
+            }
+            let task = current_task().unwrap();
+            futex_enqueue(pa, Arc::clone(&task));
+            if let Some(expire) = expire {
+                add_timer(expire, Arc::clone(&task));
+            }
+            block_current_and_run_next();
+            // 仍然在等待队列中说明是被定时器唤醒的
+            if futex_dequeue(pa, &task) {
+                -ETIMEDOUT
+            } else {
+                0
+            }
+        }
+        FUTEX_WAKE => futex_wake(pa, val as usize) as isize,
+        _ => -ENOSYS,
+    }
+}
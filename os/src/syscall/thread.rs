@@ -1,13 +1,78 @@
 use crate::{
-    mm::kernel_token,
-    task::{add_task, current_task, TaskControlBlock},
+    config::{
+        DEFAULT_PRIORITY, MAX_PRIORITY, MAX_USER_STACK_SIZE, MAX_USER_TLS_SIZE, MIN_PRIORITY,
+        PAGE_SIZE, USER_STACK_SIZE, USER_TLS_SIZE,
+    },
+    errno::*,
+    mm::{check_user_writable, copy_from_user, copy_to_user, kernel_token},
+    task::{
+        add_task, block_current_and_run_next, current_task, current_user_token, TaskControlBlock,
+        RLIMIT_NTHREAD, RLIMIT_STACK,
+    },
     trap::{trap_handler, TrapContext},
 };
 use alloc::sync::Arc;
 
-pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+/// 创建线程时的属性，与 user_lib 中的 ThreadAttr 布局一致
+/// 各字段为 0 时使用默认值
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ThreadAttr {
+    /// 用户栈大小
+    pub stack_size: usize,
+    /// TLS 块大小
+    pub tls_size: usize,
+    /// THREAD_DETACHED 等标志位
+    pub flags: usize,
+}
+
+/// 线程创建后即处于分离状态
+pub const THREAD_DETACHED: usize = 1 << 0;
+
+/// 将属性中请求的大小按页向上取整，0 表示使用默认值，超过上限返回 None
+fn thread_res_size(size: usize, default: usize, max: usize) -> Option<usize> {
+    let size = if size == 0 { default } else { size };
+    let size = (size + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    if size > max {
+        None
+    } else {
+        Some(size)
+    }
+}
+
+/// 创建线程，attr 为空指针时使用默认属性
+/// 属性非法返回 EINVAL，超过资源限制返回 EAGAIN，attr 不可读时返回 EFAULT，否则返回新线程的 tid
+pub fn sys_thread_create(entry: usize, arg: usize, attr: *const ThreadAttr) -> isize {
+    let attr = if attr.is_null() {
+        ThreadAttr {
+            stack_size: 0,
+            tls_size: 0,
+            flags: 0,
+        }
+    } else {
+        match copy_from_user(current_user_token(), attr) {
+            Ok(attr) => attr,
+            Err(err) => return err,
+        }
+    };
+    let ustack_size = match thread_res_size(attr.stack_size, USER_STACK_SIZE, MAX_USER_STACK_SIZE) {
+        Some(size) => size,
+        None => return -EINVAL,
+    };
+    let tls_size = match thread_res_size(attr.tls_size, USER_TLS_SIZE, MAX_USER_TLS_SIZE) {
+        Some(size) => size,
+        None => return -EINVAL,
+    };
     let task = current_task().unwrap();
     let process = task.process.upgrade().unwrap();
+    let process_inner = process.inner_exclusive_access();
+    if process_inner.live_thread_count() >= process_inner.rlimits[RLIMIT_NTHREAD].rlim_cur
+        || ustack_size > process_inner.rlimits[RLIMIT_STACK].rlim_cur
+        || process_inner.exceeds_as_limit(ustack_size + tls_size + PAGE_SIZE)
+    {
+        return -EAGAIN;
+    }
+    drop(process_inner);
     // create a new thread
     let new_task = Arc::new(TaskControlBlock::new(
         Arc::clone(&process),
@@ -16,11 +81,14 @@ pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
             .as_ref()
             .unwrap()
             .ustack_base,
+        ustack_size,
+        tls_size,
         true,
     ));
     // add new task to scheduler
     add_task(Arc::clone(&new_task));
-    let new_task_inner = new_task.inner_exclusive_access();
+    let mut new_task_inner = new_task.inner_exclusive_access();
+    new_task_inner.detached = attr.flags & THREAD_DETACHED != 0;
     let new_task_res = new_task_inner.res.as_ref().unwrap();
     let new_task_tid = new_task_res.tid;
     let mut process_inner = process.inner_exclusive_access();
@@ -39,6 +107,7 @@ pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
         trap_handler as usize,
     );
     (*new_task_trap_cx).x[10] = arg;
+    new_task_trap_cx.set_tp(new_task_res.tls_base());
     new_task_tid as isize
 }
 
@@ -52,34 +121,90 @@ pub fn sys_gettid() -> isize {
         .tid as isize
 }
 
-/// thread does not exist, return -1
-/// thread has not exited yet, return -2
-/// otherwise, return thread's exit code
-pub fn sys_waittid(tid: usize) -> i32 {
+/// 设置当前线程的优先级，数值越大越优先，不在 [MIN_PRIORITY, MAX_PRIORITY] 内时返回 EINVAL，
+/// 非 root 用户设置高于 DEFAULT_PRIORITY 的优先级时返回 EPERM，否则返回设置的优先级
+/// 持有互斥锁时，有效优先级仍然不低于继承的优先级
+pub fn sys_set_priority(priority: isize) -> isize {
+    if priority < MIN_PRIORITY as isize || priority > MAX_PRIORITY as isize {
+        return -EINVAL;
+    }
     let task = current_task().unwrap();
     let process = task.process.upgrade().unwrap();
-    let task_inner = task.inner_exclusive_access();
-    let mut process_inner = process.inner_exclusive_access();
-    // a thread cannot wait for itself
-    if task_inner.res.as_ref().unwrap().tid == tid {
-        return -1;
+    if priority > DEFAULT_PRIORITY as isize && process.inner_exclusive_access().cred.euid != 0 {
+        return -EPERM;
     }
-    let mut exit_code: Option<i32> = None;
-    let waited_task = process_inner.tasks[tid].as_ref();
-    if let Some(waited_task) = waited_task {
-        if let Some(waited_exit_code) = waited_task.inner_exclusive_access().exit_code {
-            exit_code = Some(waited_exit_code);
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.priority = priority as usize;
+    task_inner.update_effective_priority();
+    priority
+}
+
+/// 阻塞等待线程退出，退出码写入 exit_code 指向的位置
+/// 等待自己返回 EDEADLK，线程不存在返回 ESRCH，线程已经分离返回 EINVAL
+/// exit_code 不可写时返回 EFAULT，此时不会回收线程
+pub fn sys_waittid(tid: usize, exit_code_ptr: *mut i32) -> isize {
+    loop {
+        let token = current_user_token();
+        if let Err(err) = check_user_writable(token, exit_code_ptr) {
+            return err;
         }
-    } else {
-        // waited thread does not exist
-        return -1;
+        let task = current_task().unwrap();
+        let process = task.process.upgrade().unwrap();
+        let task_inner = task.inner_exclusive_access();
+        let mut process_inner = process.inner_exclusive_access();
+        // a thread cannot wait for itself
+        if task_inner.res.as_ref().unwrap().tid == tid {
+            return -EDEADLK;
+        }
+        drop(task_inner);
+        let waited_task = match process_inner.tasks.get(tid) {
+            Some(Some(waited_task)) => Arc::clone(waited_task),
+            // waited thread does not exist
+            _ => return -ESRCH,
+        };
+        let mut waited_task_inner = waited_task.inner_exclusive_access();
+        if waited_task_inner.detached {
+            return -EINVAL;
+        }
+        if let Some(exit_code) = waited_task_inner.exit_code {
+            drop(waited_task_inner);
+            // dealloc the exited thread
+            process_inner.tasks[tid] = None;
+            // 前面已经检查过可写，回收线程之后不会再失败
+            copy_to_user(token, exit_code_ptr, &exit_code).ok();
+            return 0;
+        }
+        // waited thread has not exited, block until it calls exit
+        waited_task_inner.join_waiters.push(Arc::clone(&task));
+        drop(waited_task_inner);
+        drop(process_inner);
+        drop(process);
+        drop(task);
+        block_current_and_run_next();
     }
-    if let Some(exit_code) = exit_code {
-        // dealloc the exited thread
+}
+
+/// 将线程设置为分离状态，已经退出的线程立即被回收
+/// 线程不存在返回 ESRCH，已经分离或者是主线程返回 EINVAL
+pub fn sys_thread_detach(tid: usize) -> isize {
+    let task = current_task().unwrap();
+    let process = task.process.upgrade().unwrap();
+    let mut process_inner = process.inner_exclusive_access();
+    if tid == 0 {
+        return -EINVAL;
+    }
+    let detached_task = match process_inner.tasks.get(tid) {
+        Some(Some(detached_task)) => Arc::clone(detached_task),
+        _ => return -ESRCH,
+    };
+    let mut detached_task_inner = detached_task.inner_exclusive_access();
+    if detached_task_inner.detached {
+        return -EINVAL;
+    }
+    detached_task_inner.detached = true;
+    if detached_task_inner.exit_code.is_some() {
+        drop(detached_task_inner);
         process_inner.tasks[tid] = None;
-        exit_code
-    } else {
-        // waited thread has not exited
-        -2
     }
+    0
 }
@@ -0,0 +1,165 @@
+//! Time related syscalls
+
+use crate::errno::{EINTR, EINVAL};
+use crate::mm::{check_user_writable, copy_from_user, copy_to_user};
+use crate::task::{
+    block_current_and_run_next, current_process, current_task, current_user_token, idle_time,
+    ready_task_count,
+};
+use crate::timer::{
+    add_alarm, add_timer, cancel_timer, get_time, ticks_to_us, ITimer, ITimerVal, TimeSpec,
+    TimeVal, CLOCK_MONOTONIC, CLOCK_REALTIME, ITIMER_PROF, ITIMER_REAL,
+};
+use alloc::sync::Arc;
+
+/// 阻塞睡眠 req 指定的时间，精度取决于 time 寄存器的频率
+/// 时间非法时返回 EINVAL，被信号提前唤醒时把剩余时间写入 rem 并返回 EINTR
+/// req 不可读或者 rem 不为空并且不可写时返回 EFAULT
+pub fn sys_nanosleep(req: *const TimeSpec, rem: *mut TimeSpec) -> isize {
+    let token = current_user_token();
+    let req = match copy_from_user(token, req) {
+        Ok(req) => req,
+        Err(err) => return err,
+    };
+    if !req.is_valid() {
+        return -EINVAL;
+    }
+    if !rem.is_null() {
+        if let Err(err) = check_user_writable(token, rem) {
+            return err;
+        }
+    }
+    let expire = get_time().saturating_add(req.to_ticks());
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().interruptible = true;
+    add_timer(expire, Arc::clone(&task));
+    block_current_and_run_next();
+    task.inner_exclusive_access().interruptible = false;
+    let now = get_time();
+    if now < expire {
+        if !rem.is_null() {
+            copy_to_user(token, rem, &TimeSpec::from_ticks(expire - now)).ok();
+        }
+        return -EINTR;
+    }
+    0
+}
+
+/// 读取时钟，两种时钟都返回启动以来的时间，clock_id 非法时返回 EINVAL，tp 不可写时返回 EFAULT
+pub fn sys_clock_gettime(clock_id: usize, tp: *mut TimeSpec) -> isize {
+    if clock_id != CLOCK_REALTIME && clock_id != CLOCK_MONOTONIC {
+        return -EINVAL;
+    }
+    match copy_to_user(current_user_token(), tp, &TimeSpec::from_ticks(get_time())) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// 将内核中的定时器换算成用户可见的剩余时间
+fn itimer_val(itimer: &ITimer, which: usize) -> ITimerVal {
+    let remaining = if which == ITIMER_REAL && itimer.value != 0 {
+        // 已经到期但还没有被处理的定时器至少还剩一个计数
+        itimer.value.saturating_sub(get_time()).max(1)
+    } else {
+        itimer.value
+    };
+    ITimerVal {
+        it_interval: TimeVal::from_ticks(itimer.interval),
+        it_value: TimeVal::from_ticks(remaining),
+    }
+}
+
+/// which 非法时返回 EINVAL，curr_value 不可写时返回 EFAULT
+pub fn sys_getitimer(which: usize, curr_value: *mut ITimerVal) -> isize {
+    if which > ITIMER_PROF {
+        return -EINVAL;
+    }
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    let value = itimer_val(&process_inner.itimers[which], which);
+    drop(process_inner);
+    match copy_to_user(current_user_token(), curr_value, &value) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
+
+/// 设置间隔定时器，old_value 不为空时写入原来的设置
+/// it_value 为 0 表示关闭定时器，which 或者时间非法时返回 EINVAL
+/// new_value 不可读或者 old_value 不为空并且不可写时返回 EFAULT，此时不会修改定时器
+pub fn sys_setitimer(
+    which: usize,
+    new_value: *const ITimerVal,
+    old_value: *mut ITimerVal,
+) -> isize {
+    if which > ITIMER_PROF {
+        return -EINVAL;
+    }
+    let token = current_user_token();
+    let new_value = match copy_from_user(token, new_value) {
+        Ok(new_value) => new_value,
+        Err(err) => return err,
+    };
+    if !new_value.it_interval.is_valid() || !new_value.it_value.is_valid() {
+        return -EINVAL;
+    }
+    if !old_value.is_null() {
+        if let Err(err) = check_user_writable(token, old_value) {
+            return err;
+        }
+    }
+    let process = current_process();
+    let mut process_inner = process.inner_exclusive_access();
+    let old = itimer_val(&process_inner.itimers[which], which);
+    let interval = new_value.it_interval.to_ticks();
+    let mut value = new_value.it_value.to_ticks();
+    if value != 0 && which == ITIMER_REAL {
+        value = value.saturating_add(get_time());
+    }
+    process_inner.itimers[which] = ITimer { interval, value };
+    if which == ITIMER_REAL {
+        if let Some(handle) = process_inner.real_timer.take() {
+            cancel_timer(&handle);
+        }
+        if value != 0 {
+            process_inner.real_timer = Some(add_alarm(value, &process));
+        }
+    }
+    drop(process_inner);
+    if !old_value.is_null() {
+        // 前面已经检查过可写
+        copy_to_user(token, old_value, &old).ok();
+    }
+    0
+}
+
+/// 处理器的使用情况，时间以微秒为单位
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CpuStat {
+    /// 启动以来的时间
+    pub uptime: usize,
+    /// 处理器运行线程或者内核代码的时间
+    pub busy: usize,
+    /// 处理器在空闲循环中等待中断的时间
+    pub idle: usize,
+    /// 就绪队列中等待运行的线程数
+    pub nr_ready: usize,
+}
+
+/// stat 不可写时返回 EFAULT
+pub fn sys_cpu_stat(stat: *mut CpuStat) -> isize {
+    let now = get_time();
+    let idle = idle_time();
+    let value = CpuStat {
+        uptime: ticks_to_us(now),
+        busy: ticks_to_us(now - idle),
+        idle: ticks_to_us(idle),
+        nr_ready: ready_task_count(),
+    };
+    match copy_to_user(current_user_token(), stat, &value) {
+        Ok(()) => 0,
+        Err(err) => err,
+    }
+}
@@ -0,0 +1,138 @@
+//! System call tracing
+
+use super::{SyscallEntry, SYSCALL_EXIT};
+use crate::errno::*;
+use crate::mm::{translated_byte_buffer_mut, translated_str, UserBuffer};
+use crate::task::{current_process, current_task, current_user_token, TraceBuffer, TraceRecord};
+use crate::timer::{get_time, ticks_to_us};
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt::Write;
+
+/// 跟踪记录中字符串参数的最大字符数，超出的部分以 ... 代替
+const TRACE_STR_MAX: usize = 32;
+
+/// 系统调用参数的类型，决定跟踪时如何解码
+#[derive(Clone, Copy)]
+pub(super) enum ArgKind {
+    /// 有符号十进制整数，例如文件描述符、长度、进程号
+    Int,
+    /// 十六进制整数，例如标志位和入口地址
+    Hex,
+    /// 用户指针，空指针显示为 NULL
+    Ptr,
+    /// 以 \0 结尾的字符串，例如路径，不可读时按指针显示
+    Str,
+}
+
+/// 把参数解码成以逗号分隔的字符串，必须在系统调用执行之前解码，exec 会替换地址空间
+fn decode_args(kinds: &[ArgKind], args: &[usize; 6]) -> String {
+    let mut s = String::new();
+    for (i, (kind, &arg)) in kinds.iter().zip(args.iter()).enumerate() {
+        if i > 0 {
+            s.push_str(", ");
+        }
+        match kind {
+            ArgKind::Int => write!(s, "{}", arg as isize).unwrap(),
+            ArgKind::Hex => write!(s, "{:#x}", arg).unwrap(),
+            ArgKind::Ptr if arg == 0 => s.push_str("NULL"),
+            ArgKind::Ptr => write!(s, "{:#x}", arg).unwrap(),
+            ArgKind::Str => match translated_str(current_user_token(), arg as *const u8) {
+                Ok(string) if string.chars().count() > TRACE_STR_MAX => {
+                    let string: String = string.chars().take(TRACE_STR_MAX).collect();
+                    write!(s, "{:?}...", string).unwrap()
+                }
+                Ok(string) => write!(s, "{:?}", string).unwrap(),
+                Err(_) => write!(s, "{:#x}", arg).unwrap(),
+            },
+        }
+    }
+    s
+}
+
+/// 执行系统调用并把调用记录写入跟踪缓冲区
+pub(super) fn trace_syscall(
+    buffer: Arc<TraceBuffer>,
+    entry: &SyscallEntry,
+    args: [usize; 6],
+) -> isize {
+    let pid = current_process().getpid();
+    let tid = current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .res
+        .as_ref()
+        .map_or(0, |res| res.tid);
+    let mut record = TraceRecord {
+        pid,
+        tid,
+        name: entry.name,
+        args: decode_args(entry.args, &args),
+        ret: None,
+        duration_us: 0,
+    };
+    // exit 不会返回，在执行前写入记录并释放缓冲区的引用
+    if entry.id == SYSCALL_EXIT {
+        buffer.push(record);
+        drop(buffer);
+        return (entry.handler)(args);
+    }
+    let start = get_time();
+    let ret = (entry.handler)(args);
+    record.ret = Some(ret);
+    record.duration_us = ticks_to_us(get_time() - start);
+    buffer.push(record);
+    ret
+}
+
+/// enable 非 0 时开启当前进程的系统调用跟踪，之后 fork 出的子进程继承同一个跟踪缓冲区；
+/// enable 为 0 时关闭跟踪
+pub fn sys_trace(enable: usize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if enable == 0 {
+        inner.trace = None;
+    } else if inner.trace.is_none() {
+        inner.trace = Some(TraceBuffer::new());
+    }
+    0
+}
+
+/// 从子进程 pid 的跟踪缓冲区读出整条记录到 buf，返回读出的字节数，格式见 TraceBuffer::read
+///
+/// pid 不是当前进程的子进程返回 ECHILD，子进程没有开启跟踪返回 EINVAL；
+/// 缓冲区为空时，子进程已经退出返回 0，否则返回 EAGAIN；
+/// buf 放不下一条记录返回 EINVAL，buf 不可写返回 EFAULT
+pub fn sys_trace_read(pid: usize, buf: *mut u8, len: usize) -> isize {
+    let buf = match translated_byte_buffer_mut(current_user_token(), buf, len) {
+        Ok(buffers) => UserBuffer::new(buffers),
+        Err(err) => return err,
+    };
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    let child = match inner.children.iter().find(|child| child.getpid() == pid) {
+        Some(child) => Arc::clone(child),
+        None => return -ECHILD,
+    };
+    drop(inner);
+    let child_inner = child.inner_exclusive_access();
+    let trace = match child_inner.trace.as_ref() {
+        Some(trace) => Arc::clone(trace),
+        None => return -EINVAL,
+    };
+    let is_zombie = child_inner.is_zombie;
+    drop(child_inner);
+    if trace.is_empty() {
+        return if is_zombie { 0 } else { -EAGAIN };
+    }
+    let data = trace.read(len);
+    if data.is_empty() {
+        return -EINVAL;
+    }
+    for (byte_ref, &byte) in buf.into_iter().zip(data.as_bytes()) {
+        unsafe {
+            *byte_ref = byte;
+        }
+    }
+    data.len() as isize
+}
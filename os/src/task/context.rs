@@ -1,5 +1,6 @@
 //! implemention of ['TaskContext']
 
+use super::kthread::kthread_entry;
 use crate::trap::trap_return;
 
 /// TaskContext
@@ -29,4 +30,13 @@ impl TaskContext {
             s: [0; 12],
         }
     }
+
+    /// 内核线程从 kthread_entry 开始执行，不会返回用户态
+    pub fn goto_kthread_entry(kstack_ptr: usize) -> Self {
+        Self {
+            ra: kthread_entry as usize,
+            sp: kstack_ptr,
+            s: [0; 12],
+        }
+    }
 }
@@ -0,0 +1,79 @@
+//! 进程的身份凭证与文件权限检查
+
+/// 超级用户的 uid
+pub const ROOT_UID: u32 = 0;
+
+/// 读权限
+pub const MAY_READ: u16 = 0o4;
+/// 写权限
+pub const MAY_WRITE: u16 = 0o2;
+/// 执行权限
+pub const MAY_EXEC: u16 = 0o1;
+
+/// 进程的用户和组，fork 时被子进程继承，exec 时保持不变
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Credentials {
+    /// 实际用户 id
+    pub uid: u32,
+    /// 实际组 id
+    pub gid: u32,
+    /// 有效用户 id，用于权限检查
+    pub euid: u32,
+    /// 有效组 id，用于权限检查
+    pub egid: u32,
+}
+
+impl Credentials {
+    /// initproc 以及内核自己打开文件时使用 root 的身份
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.euid == ROOT_UID
+    }
+
+    /// 按照属主、属组、其他用户的顺序选出适用的权限位，检查是否包含 access 要求的全部权限
+    /// root 不受读写权限的限制，但是只有至少设置了一个执行位的文件才能被 root 执行
+    pub fn permits(&self, uid: u32, gid: u32, mode: u16, access: u16) -> bool {
+        if self.is_root() {
+            return access & MAY_EXEC == 0 || mode & 0o111 != 0;
+        }
+        let bits = if self.euid == uid {
+            mode >> 6
+        } else if self.egid == gid {
+            mode >> 3
+        } else {
+            mode
+        };
+        bits & access == access
+    }
+
+    /// 特权进程同时设置实际和有效用户 id，普通进程只能把有效用户 id 设回实际用户 id
+    pub fn set_uid(&mut self, uid: u32) -> bool {
+        if self.is_root() {
+            self.uid = uid;
+            self.euid = uid;
+            true
+        } else if uid == self.uid {
+            self.euid = uid;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 与 set_uid 相同的规则设置组 id
+    pub fn set_gid(&mut self, gid: u32) -> bool {
+        if self.is_root() {
+            self.gid = gid;
+            self.egid = gid;
+            true
+        } else if gid == self.gid {
+            self.egid = gid;
+            true
+        } else {
+            false
+        }
+    }
+}
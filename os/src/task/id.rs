@@ -1,7 +1,10 @@
 //! Implemention of ['pidAllocator']
 
 use super::ProcessControlBlock;
-use crate::config::{KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT_BASE, USER_STACK_SIZE};
+use crate::config::{
+    KERNEL_STACK_SIZE, MAX_USER_STACK_SIZE, MAX_USER_TLS_SIZE, PAGE_SIZE, TRAMPOLINE,
+    TRAP_CONTEXT_BASE,
+};
 use crate::mm::{MapPermission, PhysPageNum, VirtAddr, KERNEL_SPACE};
 use crate::sync::UPSafeCell;
 use alloc::{
@@ -116,6 +119,10 @@ impl KernelStack {
 pub struct TaskUserRes {
     pub tid: usize,
     pub ustack_base: usize,
+    /// 用户栈大小
+    pub ustack_size: usize,
+    /// TLS 块大小，TLS 块紧贴在用户栈栈顶之上
+    pub tls_size: usize,
     pub process: Weak<ProcessControlBlock>,
 }
 
@@ -123,20 +130,28 @@ fn trap_cx_bottom_from_tid(tid: usize) -> usize {
     TRAP_CONTEXT_BASE - tid * PAGE_SIZE
 }
 
+/// 每个线程在用户地址空间中占据一个固定大小的槽位：
+/// [ 用户栈 | TLS 块 | 未映射 ... | 保护页 ]
+/// 槽位按最大栈和最大 TLS 计算，因此不同线程可以有不同的栈大小
 fn ustack_bottom_from_tid(ustack_base: usize, tid: usize) -> usize {
-    ustack_base + tid * (PAGE_SIZE + USER_STACK_SIZE)
+    ustack_base + tid * (PAGE_SIZE + MAX_USER_STACK_SIZE + MAX_USER_TLS_SIZE)
 }
 
 impl TaskUserRes {
     pub fn new(
         process: Arc<ProcessControlBlock>,
         ustack_base: usize,
+        ustack_size: usize,
+        tls_size: usize,
         alloc_user_res: bool,
     ) -> Self {
+        assert!(ustack_size <= MAX_USER_STACK_SIZE && tls_size <= MAX_USER_TLS_SIZE);
         let tid = process.inner_exclusive_access().alloc_tid();
         let task_user_res = Self {
             tid,
             ustack_base,
+            ustack_size,
+            tls_size,
             process: Arc::downgrade(&process),
         };
         if alloc_user_res {
@@ -150,12 +165,20 @@ impl TaskUserRes {
         let mut process_inner = process.inner_exclusive_access();
         // alloc user stack
         let ustack_bottom = ustack_bottom_from_tid(self.ustack_base, self.tid);
-        let ustack_top = ustack_bottom + USER_STACK_SIZE;
+        let ustack_top = ustack_bottom + self.ustack_size;
         process_inner.memory_set.insert_framed_area(
             ustack_bottom.into(),
             ustack_top.into(),
             MapPermission::R | MapPermission::W | MapPermission::U,
         );
+        // alloc tls block
+        if self.tls_size > 0 {
+            process_inner.memory_set.insert_framed_area(
+                ustack_top.into(),
+                (ustack_top + self.tls_size).into(),
+                MapPermission::R | MapPermission::W | MapPermission::U,
+            );
+        }
         // alloc trap_cx
         let trap_cx_bottom = trap_cx_bottom_from_tid(self.tid);
         let trap_cx_top = trap_cx_bottom + PAGE_SIZE;
@@ -175,6 +198,13 @@ impl TaskUserRes {
         process_inner
             .memory_set
             .remove_area_with_start_vpn(ustack_bottom_va.into());
+        // dealloc tls block manually
+        if self.tls_size > 0 {
+            let tls_base_va: VirtAddr = self.tls_base().into();
+            process_inner
+                .memory_set
+                .remove_area_with_start_vpn(tls_base_va.into());
+        }
         // dealloc trap_cx manually
         let trap_cx_bottom_va: VirtAddr = trap_cx_bottom_from_tid(self.tid).into();
         process_inner
@@ -217,7 +247,12 @@ impl TaskUserRes {
         self.ustack_base
     }
     pub fn ustack_top(&self) -> usize {
-        ustack_bottom_from_tid(self.ustack_base, self.tid) + USER_STACK_SIZE
+        ustack_bottom_from_tid(self.ustack_base, self.tid) + self.ustack_size
+    }
+
+    /// TLS 块的起始地址，线程进入用户态时 tp 寄存器指向这里
+    pub fn tls_base(&self) -> usize {
+        self.ustack_top()
     }
 }
 
@@ -0,0 +1,53 @@
+//! 内核线程
+//!
+//! 内核线程只在内核栈上运行，不属于任何进程。内核中不会发生时钟中断，
+//! 所以内核线程不会被抢占，需要通过阻塞或者让出处理器来把处理器交给其他线程
+
+use super::processor::defer_release_task;
+use super::{
+    add_task, block_current_and_run_next, current_task, schedule, take_current_task, TaskContext,
+    TaskControlBlock,
+};
+use crate::config::CLOCK_FREQ;
+use crate::timer::{add_timer, get_time};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+
+/// 创建一个内核线程并加入就绪队列，entry 返回后线程退出
+pub fn kthread_spawn<F>(entry: F) -> Arc<TaskControlBlock>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let task = Arc::new(TaskControlBlock::new_kernel(Box::new(entry)));
+    add_task(Arc::clone(&task));
+    task
+}
+
+/// 内核线程第一次被调度时从这里开始执行
+pub fn kthread_entry() -> ! {
+    let entry = current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .kthread_entry
+        .take()
+        .unwrap();
+    entry();
+    kthread_exit()
+}
+
+/// 结束当前内核线程，它的内核栈在切换回 idle 控制流之后释放
+pub fn kthread_exit() -> ! {
+    let task = take_current_task().unwrap();
+    task.inner_exclusive_access().exit_code = Some(0);
+    defer_release_task(task);
+    let mut _unused = TaskContext::zero_init();
+    schedule(&mut _unused as *mut _);
+    unreachable!("exited kernel thread is scheduled again");
+}
+
+/// 当前内核线程睡眠 ms 毫秒
+pub fn kthread_sleep_ms(ms: usize) {
+    let expire = get_time() + ms * CLOCK_FREQ / 1000;
+    add_timer(expire, current_task().unwrap());
+    block_current_and_run_next();
+}
@@ -1,4 +1,5 @@
 use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
 use alloc::{collections::vec_deque::VecDeque, sync::Arc};
 
 use super::process::ProcessControlBlock;
@@ -11,12 +12,15 @@ use lazy_static::*;
 pub struct TaskManager {
     // 维护一个双端队列 FIFO
     ready_queue: VecDeque<Arc<TaskControlBlock>>,
+    // 主动让出处理器的线程，只有没有其他就绪线程时才会被立即选中
+    yielded: Option<Arc<TaskControlBlock>>,
 }
 
 impl TaskManager {
     pub fn new() -> Self {
         Self {
             ready_queue: VecDeque::new(),
+            yielded: None,
         }
     }
 
@@ -25,9 +29,44 @@ impl TaskManager {
         self.ready_queue.push_back(task)
     }
 
-    // 从队列头中取出
+    // 主动让出处理器的线程先让其他就绪线程运行一次，不论它们的优先级高低
+    pub fn add_yielded(&mut self, task: Arc<TaskControlBlock>) {
+        if let Some(yielded) = self.yielded.replace(task) {
+            self.ready_queue.push_back(yielded);
+        }
+    }
+
+    // 取出有效优先级最高的线程，优先级相同时从队列头中取出
+    // 队列为空时才取出主动让出处理器的线程，否则把它放回队列重新参与调度
     pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
-        self.ready_queue.pop_front()
+        let mut best: Option<(usize, usize)> = None;
+        for (id, task) in self.ready_queue.iter().enumerate() {
+            let priority = task.inner_exclusive_access().effective_priority;
+            if best.map_or(true, |(_, best_priority)| priority > best_priority) {
+                best = Some((id, priority));
+            }
+        }
+        match best {
+            Some((id, _)) => {
+                if let Some(yielded) = self.yielded.take() {
+                    self.ready_queue.push_back(yielded);
+                }
+                self.ready_queue.remove(id)
+            }
+            None => self.yielded.take(),
+        }
+    }
+
+    pub fn ready_count(&self) -> usize {
+        self.ready_queue.len() + self.yielded.iter().count()
+    }
+
+    pub fn ready_tasks(&self) -> Vec<Arc<TaskControlBlock>> {
+        self.ready_queue
+            .iter()
+            .chain(self.yielded.iter())
+            .cloned()
+            .collect()
     }
 
     pub fn remove(&mut self, task: Arc<TaskControlBlock>) {
@@ -39,6 +78,13 @@ impl TaskManager {
         {
             self.ready_queue.remove(id);
         }
+        if self
+            .yielded
+            .as_ref()
+            .map_or(false, |yielded| Arc::ptr_eq(yielded, &task))
+        {
+            self.yielded = None;
+        }
     }
 }
 
@@ -54,6 +100,11 @@ pub fn add_task(task: Arc<TaskControlBlock>) {
     TASK_MANAGER.exclusive_access().add(task);
 }
 
+/// 将主动让出处理器的线程添加到就绪队列
+pub fn add_yielded_task(task: Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().add_yielded(task);
+}
+
 /// 唤醒线程
 pub fn wakeup_task(task: Arc<TaskControlBlock>) {
     let mut task_inner = task.inner_exclusive_access();
@@ -72,6 +123,16 @@ pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
     TASK_MANAGER.exclusive_access().fetch()
 }
 
+/// 就绪队列中的线程数量，不包括正在运行的线程
+pub fn ready_task_count() -> usize {
+    TASK_MANAGER.exclusive_access().ready_count()
+}
+
+/// 按顺序返回就绪队列中的线程
+pub fn ready_tasks() -> Vec<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().ready_tasks()
+}
+
 pub fn pid2process(pid: usize) -> Option<Arc<ProcessControlBlock>> {
     let map = PID2PCB.exclusive_access();
     map.get(&pid).map(Arc::clone)
@@ -1,52 +1,91 @@
 //! os/src/task/mod.rs
 /// 应用的执行与切换
 mod context;
+mod cred;
 mod id;
+mod kthread;
 mod manager;
 mod process;
 mod processor;
+mod rlimit;
 mod switch;
 
 mod action;
 mod signal;
 #[allow(clippy::rodule_inception)]
 mod task;
+mod trace;
+mod usage;
+mod workqueue;
 
 use self::id::TaskUserRes;
 use crate::fs::{open_file, OpenFlags};
 use crate::sbi::shutdown;
-use crate::timer::remove_timer;
+use crate::sync::futex_remove_task;
+use crate::timer::{cancel_timer, get_time, remove_timer};
 use alloc::{sync::Arc, vec::Vec};
 pub use context::TaskContext;
+pub use cred::{Credentials, MAY_EXEC, MAY_READ, MAY_WRITE};
+use easy_fs::block_cache_sync_all;
 pub use id::{kstack_alloc, pid_alloc, KernelStack, PidHandle, IDLE_PID};
+pub use kthread::{kthread_sleep_ms, kthread_spawn};
 use lazy_static::*;
-use manager::remove_task;
-pub use manager::{add_task, fetch_task, pid2process, remove_from_pid2process, wakeup_task};
-use process::ProcessControlBlock;
+pub use manager::{
+    add_task, fetch_task, pid2process, ready_task_count, ready_tasks, remove_from_pid2process,
+    wakeup_task,
+};
+use manager::{add_yielded_task, remove_task};
+pub use process::ProcessControlBlock;
+use processor::defer_release_task;
 pub use processor::{
     current_kstack_top, current_process, current_task, current_trap_cx, current_trap_cx_user_va,
-    current_user_token, run_tasks, schedule, take_current_task,
+    current_user_token, idle_time, run_tasks, schedule, take_current_task,
+};
+pub use rlimit::{
+    RLimit, RLIMIT_CPU, RLIMIT_NLIMITS, RLIMIT_NOFILE, RLIMIT_NPROC, RLIMIT_NTHREAD, RLIMIT_STACK,
+    RLIM_INFINITY,
 };
 pub use signal::{SignalFlags, MAX_SIG};
 use switch::__switch;
 pub use task::{TaskControlBlock, TaskStatus};
+pub use trace::{TraceBuffer, TraceRecord};
+pub use usage::TaskUsage;
+pub use workqueue::{queue_work, workqueue_start, SYSTEM_WQ};
 
+/// 主动让出处理器，线程仍然处于就绪状态
 pub fn suspend_current_and_run_next() {
-    let task = take_current_task().unwrap();
-    let mut task_inner = task.inner_exclusive_access();
-    let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
-    task_inner.task_status = TaskStatus::Ready;
-    drop(task_inner);
-    add_task(task);
-    schedule(task_cx_ptr);
+    switch_current_and_run_next(TaskStatus::Ready, true);
+}
+
+/// 时间片用完，当前线程被抢占
+pub fn preempt_current_and_run_next() {
+    switch_current_and_run_next(TaskStatus::Ready, false);
 }
 
 pub fn block_current_and_run_next() {
+    switch_current_and_run_next(TaskStatus::Blocked, true);
+}
+
+/// 切换出当前线程，切换前统计它在内核中运行的时间和上下文切换的次数
+fn switch_current_and_run_next(status: TaskStatus, voluntary: bool) {
     let task = take_current_task().unwrap();
     let mut task_inner = task.inner_exclusive_access();
     let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
-    task_inner.task_status = TaskStatus::Blocked;
+    task_inner.task_status = status;
+    task_inner.usage.stime += get_time() - task_inner.kernel_enter_time;
+    if voluntary {
+        task_inner.usage.nvcsw += 1;
+    } else {
+        task_inner.usage.nivcsw += 1;
+    }
     drop(task_inner);
+    if status == TaskStatus::Ready {
+        if voluntary {
+            add_yielded_task(task);
+        } else {
+            add_task(task);
+        }
+    }
     schedule(task_cx_ptr);
 }
 
@@ -58,10 +97,32 @@ pub fn exit_current_and_run_next(exit_code: i32) {
     // record exit code
     task_inner.exit_code = Some(exit_code);
     task_inner.res = None;
-    // here we do not remove the thread since we are still using the kstack
-    // it will be deallocated when sys_waittid is called
+    // 线程的资源使用统计并入进程
+    task_inner.usage.stime += get_time() - task_inner.kernel_enter_time;
+    let usage = core::mem::take(&mut task_inner.usage);
+    let mut process_inner = process.inner_exclusive_access();
+    process_inner.exited_usage += usage;
+    process_inner.deadlock.release_thread(tid);
+    drop(process_inner);
+    let detached = task_inner.detached;
+    // wake up threads waiting for this thread in sys_waittid
+    let join_waiters: Vec<_> = task_inner.join_waiters.drain(..).collect();
     drop(task_inner);
-    drop(task);
+    for waiter in join_waiters {
+        wakeup_task(waiter);
+    }
+    if tid != 0 && detached {
+        // nobody will wait for a detached thread, so remove it from the process now.
+        // we are still using its kstack, so it is released after switching to idle
+        let mut process_inner = process.inner_exclusive_access();
+        process_inner.tasks[tid] = None;
+        drop(process_inner);
+        defer_release_task(task);
+    } else {
+        // here we do not remove the thread since we are still using the kstack
+        // it will be deallocated when sys_waittid is called
+        drop(task);
+    }
     // however, if this is the main thread of current process
     // the process should terminate at once
     if tid == 0 {
@@ -71,6 +132,8 @@ pub fn exit_current_and_run_next(exit_code: i32) {
                 "[kernel] Idle process exit with exit_code {} ...",
                 exit_code
             );
+            // 关机前把缓存中的脏块写回磁盘
+            block_cache_sync_all();
             if exit_code != 0 {
                 //crate::sbi::shutdown(255); //255 == -1 for err hint
                 shutdown(true);
@@ -85,6 +148,9 @@ pub fn exit_current_and_run_next(exit_code: i32) {
         process_inner.is_zombie = true;
         // record exit code of main process
         process_inner.exit_code = exit_code;
+        if let Some(handle) = process_inner.real_timer.take() {
+            cancel_timer(&handle);
+        }
 
         {
             // move all child processes under init process
@@ -99,6 +165,7 @@ pub fn exit_current_and_run_next(exit_code: i32) {
         // it has to be done before we dealloc the whole memory_set
         // otherwise they will be deallocated twice
         let mut recycle_res = Vec::<TaskUserRes>::new();
+        let mut usage = TaskUsage::default();
         for task in process_inner.tasks.iter().filter(|t| t.is_some()) {
             let task = task.as_ref().unwrap();
             // if other tasks are Ready in TaskManager or waiting for a timer to be
@@ -112,7 +179,9 @@ pub fn exit_current_and_run_next(exit_code: i32) {
             if let Some(res) = task_inner.res.take() {
                 recycle_res.push(res);
             }
+            usage += core::mem::take(&mut task_inner.usage);
         }
+        process_inner.exited_usage += usage;
         // dealloc_tid and dealloc_user_res require access to PCB inner, so we
         // need to collect those user res first, then release process_inner
         // for now to avoid deadlock/double borrow problem.
@@ -122,16 +191,21 @@ pub fn exit_current_and_run_next(exit_code: i32) {
         let mut process_inner = process.inner_exclusive_access();
         process_inner.children.clear();
         // deallocate other data in user space i.e. program code/data section
-        process_inner.memory_set.recycle_data_pages();
+        let data_pages = process_inner.memory_set.take_data_pages();
         // drop file descriptors
-        process_inner.fd_table.clear();
+        let fd_table = core::mem::take(&mut process_inner.fd_table);
         // Remove all tasks except for the main thread itself.
         // This is because we are still using the kstack under the TCB
         // of the main thread. This TCB, including its kstack, will be
         // deallocated when the process is reaped via waitpid.
-        while process_inner.tasks.len() > 1 {
-            process_inner.tasks.pop();
-        }
+        let tasks = process_inner.tasks.split_off(1);
+        drop(process_inner);
+        // 这些资源交给工作队列释放，尽快切换到下一个线程
+        queue_work(move || {
+            drop(data_pages);
+            drop(fd_table);
+            drop(tasks);
+        });
     }
     drop(process);
     // we do not have to save task context
@@ -145,9 +219,9 @@ lazy_static! {
     /// 内嵌 initproc 在操作系统中
     pub static ref INITPROC: Arc<ProcessControlBlock> = {
         // 解析 elf 文件，并建立应用的地址空间，内核栈，形成一个就绪的进程控制块
-        let inode = open_file("initproc", OpenFlags::RDONLY).unwrap();
+        let inode = open_file("initproc", OpenFlags::RDONLY, &Credentials::root()).unwrap();
         let v = inode.read_all();
-        ProcessControlBlock::new(v.as_slice())
+        ProcessControlBlock::new("initproc", v.as_slice())
     };
 }
 
@@ -177,8 +251,49 @@ pub fn current_add_signal(signal: SignalFlags) {
     // );
 }
 
+/// 从用户态陷入内核时调用，统计当前线程在用户态运行的时间
+pub fn current_trap_enter() {
+    let now = get_time();
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let user_time = now - task_inner.user_enter_time;
+    task_inner.usage.utime += user_time;
+    task_inner.kernel_enter_time = now;
+    drop(task_inner);
+    let process = task.process.upgrade().unwrap();
+    process
+        .inner_exclusive_access()
+        .charge_cpu_time(user_time, 0);
+}
+
+/// 返回用户态之前调用，统计当前线程在内核中运行的时间
+/// ITIMER_PROF 只推进最近一次被调度运行以来的内核时间
+pub fn current_trap_leave() {
+    let now = get_time();
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let kernel_time = now - task_inner.kernel_enter_time;
+    task_inner.usage.stime += kernel_time;
+    task_inner.user_enter_time = now;
+    drop(task_inner);
+    let process = task.process.upgrade().unwrap();
+    process
+        .inner_exclusive_access()
+        .charge_cpu_time(0, kernel_time);
+}
+
+/// 当前线程触发了一次访存异常
+pub fn current_add_page_fault() {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .usage
+        .page_faults += 1;
+}
+
 /// 移除阻塞队列中的线程
 pub fn remove_inactive_task(task: Arc<TaskControlBlock>) {
     remove_task(Arc::clone(&task));
     remove_timer(Arc::clone(&task));
+    futex_remove_task(&task);
 }
@@ -1,11 +1,17 @@
+use super::cred::Credentials;
 use super::id::RecycleAllocator;
 use super::manager::insert_into_pid2process;
-use super::TaskControlBlock;
+use super::rlimit::{
+    default_rlimits, RLimit, RLIMIT_AS, RLIMIT_CPU, RLIMIT_NLIMITS, RLIMIT_NOFILE,
+};
 use super::{add_task, SignalFlags};
 use super::{pid_alloc, PidHandle};
+use super::{TaskControlBlock, TaskUsage, TraceBuffer};
+use crate::config::{CLOCK_FREQ, PAGE_SIZE, USER_STACK_SIZE, USER_TLS_SIZE};
 use crate::fs::{File, Stdin, Stdout};
-use crate::mm::{translated_refmut, MemorySet, KERNEL_SPACE};
-use crate::sync::{Condvar, Mutex, Semaphore, UPSafeCell};
+use crate::mm::{copy_to_user, MemorySet, KERNEL_SPACE};
+use crate::sync::{Barrier, Condvar, DeadlockDetector, Mutex, RwLock, Semaphore, UPSafeCell};
+use crate::timer::{ITimer, TimerHandle, ITIMER_PROF, ITIMER_VIRTUAL};
 use crate::trap::{trap_handler, TrapContext};
 use alloc::string::String;
 use alloc::sync::{Arc, Weak};
@@ -21,6 +27,10 @@ pub struct ProcessControlBlock {
 }
 
 pub struct ProcessControlBlockInner {
+    /// 进程名，exec 时更新为新程序的名字
+    pub name: String,
+    /// 进程的用户和组
+    pub cred: Credentials,
     /// 进程状态是否为僵尸进程
     pub is_zombie: bool,
     /// 进程的地址空间
@@ -57,6 +67,26 @@ pub struct ProcessControlBlockInner {
     pub semaphore_list: Vec<Option<Arc<Semaphore>>>,
     /// 条件变量也是一种资源
     pub condvar_list: Vec<Option<Arc<Condvar>>>,
+    /// 读写锁表
+    pub rwlock_list: Vec<Option<Arc<RwLock>>>,
+    /// 屏障表
+    pub barrier_list: Vec<Option<Arc<Barrier>>>,
+    /// 互斥锁和信号量的死锁检测，默认关闭
+    pub deadlock: DeadlockDetector,
+    /// 间隔定时器，分别对应 ITIMER_REAL/ITIMER_VIRTUAL/ITIMER_PROF
+    pub itimers: [ITimer; 3],
+    /// ITIMER_REAL 在定时器队列中的句柄，重新设置时取消
+    pub real_timer: Option<TimerHandle>,
+    /// 已经退出的线程的资源使用统计
+    pub exited_usage: TaskUsage,
+    /// 已经被 waitpid 回收的子进程（包括它们回收的子进程）的资源使用统计
+    pub children_usage: TaskUsage,
+    /// 资源限制，按 RLIMIT_* 索引
+    pub rlimits: [RLimit; RLIMIT_NLIMITS],
+    /// 进程消耗的 CPU 时间，用于检查 RLIMIT_CPU
+    pub cpu_time: usize,
+    /// 开启系统调用跟踪时记录写入的缓冲区，子进程继承
+    pub trace: Option<Arc<TraceBuffer>>,
 }
 
 impl ProcessControlBlockInner {
@@ -65,13 +95,26 @@ impl ProcessControlBlockInner {
         self.memory_set.token()
     }
 
-    pub fn alloc_fd(&mut self) -> usize {
-        if let Some(fd) = (0..self.fd_table.len()).find(|fd| self.fd_table[*fd].is_none()) {
+    /// 分配最小的空闲文件描述符，超出 RLIMIT_NOFILE 时返回 None
+    pub fn alloc_fd(&mut self) -> Option<usize> {
+        let fd = if let Some(fd) = (0..self.fd_table.len()).find(|fd| self.fd_table[*fd].is_none())
+        {
             fd
         } else {
+            self.fd_table.len()
+        };
+        if fd >= self.rlimits[RLIMIT_NOFILE].rlim_cur {
+            return None;
+        }
+        if fd == self.fd_table.len() {
             self.fd_table.push(None);
-            self.fd_table.len() - 1
         }
+        Some(fd)
+    }
+
+    /// 地址空间再映射 size 字节后是否超过 RLIMIT_AS
+    pub fn exceeds_as_limit(&self, size: usize) -> bool {
+        self.memory_set.mapped_size() + size > self.rlimits[RLIMIT_AS].rlim_cur
     }
 
     pub fn alloc_tid(&mut self) -> usize {
@@ -86,9 +129,50 @@ impl ProcessControlBlockInner {
         self.tasks.len()
     }
 
+    /// 尚未被回收的线程数量，用于检查 RLIMIT_NTHREAD
+    pub fn live_thread_count(&self) -> usize {
+        self.tasks.iter().filter(|task| task.is_some()).count()
+    }
+
     pub fn get_task(&self, tid: usize) -> Arc<TaskControlBlock> {
         self.tasks[tid].as_ref().unwrap().clone()
     }
+
+    /// 进程自身的资源使用统计，包括已经退出的线程和仍在运行的线程
+    pub fn usage(&self) -> TaskUsage {
+        let mut usage = self.exited_usage;
+        for task in self.tasks.iter().flatten() {
+            usage += task.inner_exclusive_access().usage;
+        }
+        usage
+    }
+
+    /// 线程消耗了 CPU 时间，推进 ITIMER_VIRTUAL 与 ITIMER_PROF 并检查 RLIMIT_CPU
+    pub fn charge_cpu_time(&mut self, user: usize, kernel: usize) {
+        self.charge_itimer(ITIMER_VIRTUAL, user, SignalFlags::SIGVTALRM);
+        self.charge_itimer(ITIMER_PROF, user + kernel, SignalFlags::SIGPROF);
+        self.cpu_time += user + kernel;
+        let limit = self.rlimits[RLIMIT_CPU];
+        if self.cpu_time >= limit.rlim_max.saturating_mul(CLOCK_FREQ) {
+            self.signals |= SignalFlags::SIGKILL;
+        } else if self.cpu_time >= limit.rlim_cur.saturating_mul(CLOCK_FREQ) {
+            self.signals |= SignalFlags::SIGXCPU;
+        }
+    }
+
+    fn charge_itimer(&mut self, which: usize, ticks: usize, signal: SignalFlags) {
+        let itimer = &mut self.itimers[which];
+        if itimer.value == 0 || ticks == 0 {
+            return;
+        }
+        if itimer.value > ticks {
+            itimer.value -= ticks;
+        } else {
+            // 到期，周期定时器重新开始计时
+            itimer.value = itimer.interval;
+            self.signals |= signal;
+        }
+    }
 }
 
 impl ProcessControlBlock {
@@ -96,7 +180,7 @@ impl ProcessControlBlock {
         self.inner.exclusive_access()
     }
 
-    pub fn new(elf_data: &[u8]) -> Arc<Self> {
+    pub fn new(name: &str, elf_data: &[u8]) -> Arc<Self> {
         // memory_set with elf program headers/trampoline/trap context/user stack
         // 解析传入的 elf 格式数据结构，构造应用的地址空间 memory_set 并获取其他信息
         let (memory_set, ustack_base, entry_point) = MemorySet::from_elf(elf_data);
@@ -108,6 +192,8 @@ impl ProcessControlBlock {
             pid: pid_handle,
             inner: unsafe {
                 UPSafeCell::new(ProcessControlBlockInner {
+                    name: String::from(name),
+                    cred: Credentials::root(),
                     is_zombie: false,
                     memory_set,
                     parent: None,
@@ -117,7 +203,7 @@ impl ProcessControlBlock {
                     // 当一个进程被创建的时候，内核会默认为其打开三个缺省就存在的文件：
                     fd_table: vec![
                         // 0 -> stdin 文件描述符 0； 标准输入
-                        Some(Arc::new(Stdin)),
+                        Some(Arc::new(Stdin::new())),
                         // 1 -> stdout 文件描述符 1: 标准输出
                         Some(Arc::new(Stdout)),
                         // 2 -> stderr 文件描述符 2: 标准错误的输出
@@ -129,6 +215,16 @@ impl ProcessControlBlock {
                     mutex_list: Vec::new(),
                     semaphore_list: Vec::new(),
                     condvar_list: Vec::new(),
+                    rwlock_list: Vec::new(),
+                    barrier_list: Vec::new(),
+                    deadlock: DeadlockDetector::new(),
+                    itimers: [ITimer::default(); 3],
+                    real_timer: None,
+                    exited_usage: TaskUsage::default(),
+                    children_usage: TaskUsage::default(),
+                    rlimits: default_rlimits(),
+                    cpu_time: 0,
+                    trace: None,
                 })
             },
         });
@@ -137,6 +233,8 @@ impl ProcessControlBlock {
         let task = Arc::new(TaskControlBlock::new(
             Arc::clone(&process),
             ustack_base,
+            USER_STACK_SIZE,
+            USER_TLS_SIZE,
             true,
         ));
         // prepare trap_cx of main thread
@@ -144,6 +242,7 @@ impl ProcessControlBlock {
         let task_inner = task.inner_exclusive_access();
         let trap_cx = task_inner.get_trap_cx();
         let ustack_top = task_inner.res.as_ref().unwrap().ustack_top();
+        let tls_base = task_inner.res.as_ref().unwrap().tls_base();
         let kstack_top = task.kstack.get_top();
         drop(task_inner);
         *trap_cx = TrapContext::app_init_context(
@@ -153,6 +252,7 @@ impl ProcessControlBlock {
             kstack_top,
             trap_handler as usize,
         );
+        trap_cx.set_tp(tls_base);
         // add main thread to the process
         // 将主线程插入到进程的线程列表中，此时列表为空，可直接插入
         let mut process_inner = process.inner_exclusive_access();
@@ -167,16 +267,33 @@ impl ProcessControlBlock {
     }
 
     /// Only support processes with a single thread.
-    pub fn exec(self: &Arc<Self>, elf_data: &[u8], args: Vec<String>) {
+    /// 新的地址空间超过 RLIMIT_AS 时返回 false，原来的地址空间保持不变
+    pub fn exec(self: &Arc<Self>, elf_data: &[u8], args: Vec<String>) -> bool {
         assert_eq!(self.inner_exclusive_access().thread_count(), 1);
         // memory_set with elf program headers/trampoline/trap context/user stack
         let (memory_set, ustack_base, entry_point) = MemorySet::from_elf(elf_data);
+        // 主线程沿用原来的用户栈和 TLS 大小，它们和 Trap 上下文页面之后才会映射
+        let task = self.inner_exclusive_access().get_task(0);
+        let res_size = {
+            let task_inner = task.inner_exclusive_access();
+            let res = task_inner.res.as_ref().unwrap();
+            res.ustack_size + res.tls_size + PAGE_SIZE
+        };
+        if memory_set.mapped_size() + res_size
+            > self.inner_exclusive_access().rlimits[RLIMIT_AS].rlim_cur
+        {
+            return false;
+        }
         let new_token = memory_set.token();
         // substitute memory_set
-        self.inner_exclusive_access().memory_set = memory_set;
+        let mut inner = self.inner_exclusive_access();
+        inner.memory_set = memory_set;
+        if let Some(name) = args.first() {
+            inner.name.clone_from(name);
+        }
+        drop(inner);
         // then we alloc user resource for main thread again
         // since memory_set has been changed
-        let task = self.inner_exclusive_access().get_task(0);
         let mut task_inner = task.inner_exclusive_access();
         task_inner.res.as_mut().unwrap().ustack_base = ustack_base;
         task_inner.res.as_mut().unwrap().alloc_user_res();
@@ -190,29 +307,23 @@ impl ProcessControlBlock {
         user_sp -= (args.len() + 1) * core::mem::size_of::<usize>();
         let argv_base = user_sp;
         // 最开始我们只是分配空间，具体的值要等到字符串被放到用户栈上之后才能确定更新
-        let mut argv: Vec<_> = (0..=args.len())
-            .map(|arg| {
-                translated_refmut(
-                    new_token,
-                    (argv_base + arg * core::mem::size_of::<usize>()) as *mut usize,
-                )
-            })
-            .collect();
+        // sys_exec 限制了参数的总长度，它们一定位于刚刚映射的用户栈中
+        let argv = argv_base as *mut usize;
 
         // 将传入的 args 中的字符串压入到用户栈中
         // 我们在用户栈上预留空间之后逐字节进行复制
-        *argv[args.len()] = 0;
-        for i in 0..args.len() {
-            user_sp -= args[i].len() + 1;
-            *argv[i] = user_sp;
+        copy_to_user(new_token, unsafe { argv.add(args.len()) }, &0usize).unwrap();
+        for (i, arg) in args.iter().enumerate() {
+            user_sp -= arg.len() + 1;
+            copy_to_user(new_token, unsafe { argv.add(i) }, &user_sp).unwrap();
             let mut p = user_sp;
-            for c in args[i].as_bytes() {
+            for c in arg.as_bytes() {
                 // translated_str 从应用地址空间取出的，它的末尾不包含 \0 。
                 // 为了应用能知道每个字符串的长度，我们需要手动在末尾加入 \0
-                *translated_refmut(new_token, p as *mut u8) = *c;
+                copy_to_user(new_token, p as *mut u8, c).unwrap();
                 p += 1;
             }
-            *translated_refmut(new_token, p as *mut u8) = 0;
+            copy_to_user(new_token, p as *mut u8, &0u8).unwrap();
         }
         // make the user_sp aligned to 8B for k210 platform
         // 将 user_sp 以 8 字节对齐
@@ -229,7 +340,9 @@ impl ProcessControlBlock {
         );
         trap_cx.x[10] = args.len();
         trap_cx.x[11] = argv_base;
+        trap_cx.set_tp(task_inner.res.as_ref().unwrap().tls_base());
         *task_inner.get_trap_cx() = trap_cx;
+        true
     }
 
     /// Only support processes with a single thread.
@@ -255,6 +368,8 @@ impl ProcessControlBlock {
             pid,
             inner: unsafe {
                 UPSafeCell::new(ProcessControlBlockInner {
+                    name: parent.name.clone(),
+                    cred: parent.cred,
                     is_zombie: false,
                     memory_set,
                     parent: Some(Arc::downgrade(self)),
@@ -267,27 +382,37 @@ impl ProcessControlBlock {
                     mutex_list: Vec::new(),
                     semaphore_list: Vec::new(),
                     condvar_list: Vec::new(),
+                    rwlock_list: Vec::new(),
+                    barrier_list: Vec::new(),
+                    deadlock: DeadlockDetector::new(),
+                    itimers: [ITimer::default(); 3],
+                    real_timer: None,
+                    exited_usage: TaskUsage::default(),
+                    children_usage: TaskUsage::default(),
+                    rlimits: parent.rlimits,
+                    cpu_time: 0,
+                    trace: parent.trace.clone(),
                 })
             },
         });
         // add child
         parent.children.push(Arc::clone(&child));
         // create main thread of child process
-        //创建子进程的主线程控制块，注意它继承了父进程的 ustack_base ，
+        //创建子进程的主线程控制块，注意它继承了父进程的 ustack_base 以及栈和 TLS 的大小，
         //并且不用重新分配用户栈和 Trap 上下文。将主线程加入到子进程中
+        let parent_task = parent.get_task(0);
+        let parent_task_inner = parent_task.inner_exclusive_access();
+        let parent_res = parent_task_inner.res.as_ref().unwrap();
         let task = Arc::new(TaskControlBlock::new(
             Arc::clone(&child),
-            parent
-                .get_task(0)
-                .inner_exclusive_access()
-                .res
-                .as_ref()
-                .unwrap()
-                .ustack_base(),
+            parent_res.ustack_base(),
+            parent_res.ustack_size,
+            parent_res.tls_size,
             // here we do not allocate trap_cx or ustack again
             // but mention that we allocate a new kstack here
             false,
         ));
+        drop(parent_task_inner);
         // attach task to child process
         let mut child_inner = child.inner_exclusive_access();
         child_inner.tasks.push(Some(Arc::clone(&task)));
@@ -1,8 +1,11 @@
 use super::process::ProcessControlBlock;
 use super::{fetch_task, TaskStatus};
 use super::{TaskContext, TaskControlBlock, __switch};
+use crate::timer::{checker_timer, get_time, set_next_trigger};
+use crate::trap::wait_for_interrupt;
 use crate::{sync::UPSafeCell, trap::TrapContext};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use lazy_static::*;
 
 /// 处理器管理结构，描述 CPU 执行状态
@@ -11,6 +14,11 @@ pub struct Processor {
     current: Option<Arc<TaskControlBlock>>,
     // 空闲任务
     idle_task_cx: TaskContext,
+    // 已经退出的分离线程，它们退出时仍在使用自己的内核栈，
+    // 需要切换回 idle 控制流之后才能释放
+    exited_tasks: Vec<Arc<TaskControlBlock>>,
+    // 处理器处于空闲状态的累计时间，以 time 寄存器的计数表示
+    idle_time: usize,
 }
 
 impl Processor {
@@ -18,6 +26,8 @@ impl Processor {
         Self {
             current: None,
             idle_task_cx: TaskContext::zero_init(),
+            exited_tasks: Vec::new(),
+            idle_time: 0,
         }
     }
 
@@ -46,6 +56,8 @@ pub fn run_tasks() {
     loop {
         // 在这里完成 PROCESSOR 的 初始化
         let mut processor = PROCESSOR.exclusive_access();
+        // 此时已经不在任何线程的内核栈上，可以释放退出的分离线程
+        processor.exited_tasks.clear();
         // 选择一个用来切换的进程
         // 这里第一个进程是 initproc 程序，
         // 在切换到改进程执行的时候，会做一些其他操作，具体实现
@@ -59,6 +71,8 @@ pub fn run_tasks() {
             let next_task_cx_ptr = &task_inner.task_cx as *const TaskContext;
             // 修改进程状态
             task_inner.task_status = TaskStatus::Running;
+            // 在就绪队列中等待的时间不计入线程的 CPU 时间
+            task_inner.kernel_enter_time = get_time();
             drop(task_inner);
             processor.current = Some(task);
             drop(processor);
@@ -67,11 +81,34 @@ pub fn run_tasks() {
                 __switch(idle_task_cx_ptr, next_task_cx_ptr);
             }
         } else {
-            println!("no trasks available in run tasks");
+            drop(processor);
+            let idle_time = idle();
+            PROCESSOR.exclusive_access().idle_time += idle_time;
         }
     }
 }
 
+/// 没有就绪的线程时让处理器停下来，直到有中断到来，返回空闲的时间
+/// 中断唤醒处理器后在这里处理到期的定时器，并重新设置时钟以清除挂起的时钟中断
+fn idle() -> usize {
+    let start = get_time();
+    wait_for_interrupt();
+    let idle_time = get_time() - start;
+    checker_timer();
+    set_next_trigger();
+    idle_time
+}
+
+/// 处理器累计的空闲时间
+pub fn idle_time() -> usize {
+    PROCESSOR.exclusive_access().idle_time
+}
+
+/// 延迟释放一个已经退出的线程，直到切换回 idle 控制流
+pub fn defer_release_task(task: Arc<TaskControlBlock>) {
+    PROCESSOR.exclusive_access().exited_tasks.push(task);
+}
+
 pub fn take_current_task() -> Option<Arc<TaskControlBlock>> {
     PROCESSOR.exclusive_access().take_current()
 }
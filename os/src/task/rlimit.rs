@@ -0,0 +1,45 @@
+//! 进程的资源限制
+
+use crate::config::MAX_USER_STACK_SIZE;
+
+/// 表示不限制
+pub const RLIM_INFINITY: usize = usize::MAX;
+
+/// CPU 时间，单位为秒，超过软限制发送 SIGXCPU，超过硬限制发送 SIGKILL
+pub const RLIMIT_CPU: usize = 0;
+/// 线程用户栈的大小
+pub const RLIMIT_STACK: usize = 3;
+/// 尚未被回收的子进程数量
+pub const RLIMIT_NPROC: usize = 6;
+/// 文件描述符的数量，新分配的描述符必须小于该值
+pub const RLIMIT_NOFILE: usize = 7;
+/// 地址空间中映射的内存大小
+pub const RLIMIT_AS: usize = 9;
+/// 进程中的线程数量
+pub const RLIMIT_NTHREAD: usize = 16;
+pub const RLIMIT_NLIMITS: usize = 17;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RLimit {
+    /// 软限制，实际生效的限制
+    pub rlim_cur: usize,
+    /// 硬限制，软限制可以调整到的上限
+    pub rlim_max: usize,
+}
+
+impl RLimit {
+    const fn new(rlim_cur: usize, rlim_max: usize) -> Self {
+        Self { rlim_cur, rlim_max }
+    }
+}
+
+/// 新进程的默认资源限制，fork 时子进程继承父进程的设置
+pub fn default_rlimits() -> [RLimit; RLIMIT_NLIMITS] {
+    let mut rlimits = [RLimit::new(RLIM_INFINITY, RLIM_INFINITY); RLIMIT_NLIMITS];
+    rlimits[RLIMIT_STACK] = RLimit::new(MAX_USER_STACK_SIZE, MAX_USER_STACK_SIZE);
+    rlimits[RLIMIT_NPROC] = RLimit::new(256, 256);
+    rlimits[RLIMIT_NOFILE] = RLimit::new(128, 1024);
+    rlimits[RLIMIT_NTHREAD] = RLimit::new(128, 1024);
+    rlimits
+}
@@ -53,6 +53,16 @@ impl SignalFlags {
             Some((-9, "Killed, SIGKILL=9"))
         } else if self.contains(Self::SIGSEGV) {
             Some((-11, "Segmentation Fault, SIGSEGV=11"))
+        } else if self.contains(Self::SIGPIPE) {
+            Some((-13, "Broken pipe, SIGPIPE=13"))
+        } else if self.contains(Self::SIGALRM) {
+            Some((-14, "Alarm clock, SIGALRM=14"))
+        } else if self.contains(Self::SIGXCPU) {
+            Some((-24, "CPU time limit exceeded, SIGXCPU=24"))
+        } else if self.contains(Self::SIGVTALRM) {
+            Some((-26, "Virtual timer expired, SIGVTALRM=26"))
+        } else if self.contains(Self::SIGPROF) {
+            Some((-27, "Profiling timer expired, SIGPROF=27"))
         } else {
             //println!("[K] signalflags check_error  {:?}", self);
             None
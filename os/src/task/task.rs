@@ -1,10 +1,15 @@
 //! Types related to task manager
 
 use super::id::TaskUserRes;
-use super::{kstack_alloc, KernelStack, ProcessControlBlock, TaskContext};
+use super::{kstack_alloc, KernelStack, ProcessControlBlock, TaskContext, TaskUsage};
+use crate::config::DEFAULT_PRIORITY;
+use crate::sync::MutexBlockingInner;
+use crate::timer::TimerHandle;
 use crate::trap::TrapContext;
 use crate::{mm::PhysPageNum, sync::UPSafeCell};
+use alloc::boxed::Box;
 use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
 use core::cell::RefMut;
 
 pub struct TaskControlBlock {
@@ -37,6 +42,30 @@ pub struct TaskControlBlockInner {
     pub task_status: TaskStatus,
     /// 退出码
     pub exit_code: Option<i32>,
+    /// 分离线程退出时立即回收，不能被 sys_waittid 等待
+    pub detached: bool,
+    /// 阻塞在 sys_waittid 上等待该线程退出的线程
+    pub join_waiters: Vec<Arc<TaskControlBlock>>,
+    /// 最近一次返回用户态的时间
+    pub user_enter_time: usize,
+    /// 最近一次开始在内核中为该线程运行的时间，被调度出去的时间不计入
+    pub kernel_enter_time: usize,
+    /// 睡眠时的唤醒定时器，线程退出或者被信号提前唤醒时取消
+    pub timer: Option<TimerHandle>,
+    /// 正在 nanosleep 中睡眠，可以被 SIGALRM 提前唤醒；带超时的同步等待不受影响
+    pub interruptible: bool,
+    /// 线程的资源使用统计，线程退出时并入进程
+    pub usage: TaskUsage,
+    /// 内核线程第一次被调度时执行的函数
+    pub kthread_entry: Option<Box<dyn FnOnce() + Send>>,
+    /// 线程自己设置的优先级，数值越大越优先
+    pub priority: usize,
+    /// 调度时使用的有效优先级，持有阻塞互斥锁时可能被等待者提升
+    pub effective_priority: usize,
+    /// 从持有的互斥锁的等待者那里继承的优先级，以互斥锁的地址为键
+    pub inherited_priorities: Vec<(usize, usize)>,
+    /// 正在等待的阻塞互斥锁，用于沿等待链传递优先级
+    pub blocked_on: Option<Weak<UPSafeCell<MutexBlockingInner>>>,
 }
 
 impl TaskControlBlockInner {
@@ -48,15 +77,44 @@ impl TaskControlBlockInner {
     fn get_status(&self) -> TaskStatus {
         self.task_status
     }
+
+    /// 有效优先级取自身优先级和继承的优先级中的最大值，返回是否发生了变化
+    pub fn update_effective_priority(&mut self) -> bool {
+        let effective = self
+            .inherited_priorities
+            .iter()
+            .map(|&(_, priority)| priority)
+            .fold(self.priority, usize::max);
+        let changed = effective != self.effective_priority;
+        self.effective_priority = effective;
+        changed
+    }
+
+    /// 更新从互斥锁 key 继承的优先级，为 None 时不再继承，返回有效优先级是否发生了变化
+    pub fn set_inherited_priority(&mut self, key: usize, priority: Option<usize>) -> bool {
+        self.inherited_priorities.retain(|&(k, _)| k != key);
+        if let Some(priority) = priority {
+            self.inherited_priorities.push((key, priority));
+        }
+        self.update_effective_priority()
+    }
 }
 
 impl TaskControlBlock {
     pub fn new(
         process: Arc<ProcessControlBlock>,
         ustack_base: usize,
+        ustack_size: usize,
+        tls_size: usize,
         alloc_user_res: bool,
     ) -> Self {
-        let res = TaskUserRes::new(Arc::clone(&process), ustack_base, alloc_user_res);
+        let res = TaskUserRes::new(
+            Arc::clone(&process),
+            ustack_base,
+            ustack_size,
+            tls_size,
+            alloc_user_res,
+        );
         let trap_cx_ppn = res.trap_cx_ppn();
         let kstack = kstack_alloc();
         let kstack_top = kstack.get_top();
@@ -70,6 +128,50 @@ impl TaskControlBlock {
                     task_cx: TaskContext::goto_trap_return(kstack_top),
                     task_status: TaskStatus::Ready,
                     exit_code: None,
+                    detached: false,
+                    join_waiters: Vec::new(),
+                    user_enter_time: 0,
+                    kernel_enter_time: 0,
+                    timer: None,
+                    interruptible: false,
+                    usage: TaskUsage::default(),
+                    kthread_entry: None,
+                    priority: DEFAULT_PRIORITY,
+                    effective_priority: DEFAULT_PRIORITY,
+                    inherited_priorities: Vec::new(),
+                    blocked_on: None,
+                })
+            },
+        }
+    }
+
+    /// 创建内核线程，它不属于任何进程，也没有用户态资源和 Trap 上下文，
+    /// 只在自己的内核栈上执行 entry
+    pub fn new_kernel(entry: Box<dyn FnOnce() + Send>) -> Self {
+        let kstack = kstack_alloc();
+        let kstack_top = kstack.get_top();
+        Self {
+            process: Weak::new(),
+            kstack,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    res: None,
+                    trap_cx_ppn: PhysPageNum(0),
+                    task_cx: TaskContext::goto_kthread_entry(kstack_top),
+                    task_status: TaskStatus::Ready,
+                    exit_code: None,
+                    detached: true,
+                    join_waiters: Vec::new(),
+                    user_enter_time: 0,
+                    kernel_enter_time: 0,
+                    timer: None,
+                    interruptible: false,
+                    usage: TaskUsage::default(),
+                    kthread_entry: Some(entry),
+                    priority: DEFAULT_PRIORITY,
+                    effective_priority: DEFAULT_PRIORITY,
+                    inherited_priorities: Vec::new(),
+                    blocked_on: None,
                 })
             },
         }
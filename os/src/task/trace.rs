@@ -0,0 +1,106 @@
+//! 系统调用跟踪
+//!
+//! 开启跟踪的进程在每次系统调用返回后向跟踪缓冲区追加一条记录，fork 出的子进程
+//! 共享父进程的缓冲区，由开启跟踪的进程的父进程通过 trace_read 读出
+
+use crate::sync::UPSafeCell;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt::Write;
+
+/// 缓冲区中最多保存的记录数，超出时丢弃最早的记录
+const TRACE_MAX_RECORDS: usize = 512;
+
+/// 一次系统调用的记录
+pub struct TraceRecord {
+    pub pid: usize,
+    pub tid: usize,
+    pub name: &'static str,
+    /// 已经解码的参数，以逗号分隔
+    pub args: String,
+    /// 返回值，不会返回的系统调用（exit）为 None
+    pub ret: Option<isize>,
+    /// 系统调用的耗时，单位为微秒，包括阻塞的时间
+    pub duration_us: usize,
+}
+
+struct TraceBufferInner {
+    records: VecDeque<TraceRecord>,
+    /// 尚未报告给读者的被丢弃的记录数
+    dropped: usize,
+}
+
+/// 跟踪缓冲区，被开启跟踪的进程及其子进程共享
+pub struct TraceBuffer {
+    inner: UPSafeCell<TraceBufferInner>,
+}
+
+impl TraceBuffer {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            inner: unsafe {
+                UPSafeCell::new(TraceBufferInner {
+                    records: VecDeque::new(),
+                    dropped: 0,
+                })
+            },
+        })
+    }
+
+    pub fn push(&self, record: TraceRecord) {
+        let mut inner = self.inner.exclusive_access();
+        if inner.records.len() == TRACE_MAX_RECORDS {
+            inner.records.pop_front();
+            inner.dropped += 1;
+        }
+        inner.records.push_back(record);
+    }
+
+    /// 按顺序取出能放进 len 字节的整条记录，每条记录占一行，字段以制表符分隔：
+    ///
+    /// ```text
+    /// pid  tid  name  args  ret  duration_us
+    /// ```
+    ///
+    /// 不会返回的系统调用的 ret 为 `?`。如果有记录被丢弃，先输出一行 `dropped  n`
+    pub fn read(&self, len: usize) -> String {
+        let mut inner = self.inner.exclusive_access();
+        let mut s = String::new();
+        if inner.dropped > 0 {
+            let line = format!("dropped\t{}\n", inner.dropped);
+            if line.len() > len {
+                return s;
+            }
+            s.push_str(&line);
+            inner.dropped = 0;
+        }
+        while let Some(record) = inner.records.front() {
+            let line = record.line();
+            if s.len() + line.len() > len {
+                break;
+            }
+            s.push_str(&line);
+            inner.records.pop_front();
+        }
+        s
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let inner = self.inner.exclusive_access();
+        inner.records.is_empty() && inner.dropped == 0
+    }
+}
+
+impl TraceRecord {
+    fn line(&self) -> String {
+        let mut s = format!("{}\t{}\t{}\t{}\t", self.pid, self.tid, self.name, self.args);
+        match self.ret {
+            Some(ret) => write!(s, "{}", ret).unwrap(),
+            None => s.push('?'),
+        }
+        writeln!(s, "\t{}", self.duration_us).unwrap();
+        s
+    }
+}
@@ -0,0 +1,28 @@
+//! 线程与进程的资源使用统计
+
+use core::ops::AddAssign;
+
+/// 资源使用统计，时间以 time 寄存器的计数表示
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TaskUsage {
+    /// 用户态运行时间
+    pub utime: usize,
+    /// 内核态运行时间，不包括被调度出去的时间
+    pub stime: usize,
+    /// 主动让出处理器的次数，例如阻塞或者 yield
+    pub nvcsw: usize,
+    /// 时间片用完被抢占的次数
+    pub nivcsw: usize,
+    /// 访存异常的次数
+    pub page_faults: usize,
+}
+
+impl AddAssign for TaskUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.utime += other.utime;
+        self.stime += other.stime;
+        self.nvcsw += other.nvcsw;
+        self.nivcsw += other.nivcsw;
+        self.page_faults += other.page_faults;
+    }
+}
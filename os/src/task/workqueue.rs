@@ -0,0 +1,90 @@
+//! 工作队列：把不急于完成的工作交给内核线程在系统调用之外执行
+
+use super::kthread::kthread_spawn;
+use super::{block_current_and_run_next, current_task, wakeup_task, TaskControlBlock};
+use crate::sync::UPSafeCell;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+type Work = Box<dyn FnOnce() + Send>;
+
+/// 由若干个内核线程按提交顺序执行的工作
+pub struct WorkQueue {
+    inner: UPSafeCell<WorkQueueInner>,
+}
+
+struct WorkQueueInner {
+    /// 等待执行的工作
+    works: VecDeque<Work>,
+    /// 没有工作可做而阻塞的内核线程
+    idle_workers: Vec<Arc<TaskControlBlock>>,
+}
+
+impl WorkQueue {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(WorkQueueInner {
+                    works: VecDeque::new(),
+                    idle_workers: Vec::new(),
+                })
+            },
+        }
+    }
+
+    /// 提交一项工作，如果有空闲的内核线程则唤醒一个
+    pub fn queue(&self, work: Work) {
+        let mut inner = self.inner.exclusive_access();
+        inner.works.push_back(work);
+        let worker = inner.idle_workers.pop();
+        drop(inner);
+        if let Some(worker) = worker {
+            wakeup_task(worker);
+        }
+    }
+
+    /// 内核线程的主循环，依次取出工作执行，没有工作时阻塞
+    fn run(&self) -> ! {
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            if let Some(work) = inner.works.pop_front() {
+                drop(inner);
+                work();
+            } else {
+                inner.idle_workers.push(current_task().unwrap());
+                drop(inner);
+                block_current_and_run_next();
+            }
+        }
+    }
+}
+
+impl Default for WorkQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static! {
+    /// 内核的全局工作队列
+    pub static ref SYSTEM_WQ: Arc<WorkQueue> = Arc::new(WorkQueue::new());
+}
+
+/// 为 wq 创建 workers 个执行工作的内核线程
+pub fn workqueue_start(wq: &Arc<WorkQueue>, workers: usize) {
+    for _ in 0..workers {
+        let wq = Arc::clone(wq);
+        kthread_spawn(move || wq.run());
+    }
+}
+
+/// 把工作提交到全局工作队列
+pub fn queue_work<F>(work: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    SYSTEM_WQ.queue(Box::new(work));
+}
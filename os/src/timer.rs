@@ -1,21 +1,37 @@
 //! RISC-V timer-related functionality
 
 use core::cmp::Ordering;
+use core::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 
-use crate::config::CLOCK_FREQ;
+use crate::config::{CLOCK_FREQ, TICKLESS};
 use crate::sbi::set_timer;
 use crate::sync::UPSafeCell;
-use crate::task::{wakeup_task, TaskControlBlock};
+use crate::task::{
+    current_task, ready_task_count, wakeup_task, ProcessControlBlock, SignalFlags,
+    TaskControlBlock, RLIMIT_CPU, RLIM_INFINITY,
+};
 use alloc::collections::binary_heap::BinaryHeap;
-use alloc::sync::Arc;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
 use lazy_static::*;
 use riscv::register::time;
 
-const TICKS_PER_SEC: usize = 100;
+/// 时钟中断频率，也是 times 返回的时钟计数的频率
+pub const TICKS_PER_SEC: usize = 100;
 const MESC_PER_SEC: usize = 1000;
-
-#[allow(unused)]
 const USEC_PER_SEC: usize = 1000000;
+const NSEC_PER_SEC: usize = 1000000000;
+
+/// 以真实时间计时，到期发送 SIGALRM
+pub const ITIMER_REAL: usize = 0;
+/// 以进程在用户态运行的时间计时，到期发送 SIGVTALRM
+pub const ITIMER_VIRTUAL: usize = 1;
+/// 以进程在用户态和内核态运行的时间计时，到期发送 SIGPROF
+pub const ITIMER_PROF: usize = 2;
+
+/// 系统没有实时时钟，两种时钟都从启动时开始计时
+pub const CLOCK_REALTIME: usize = 0;
+pub const CLOCK_MONOTONIC: usize = 1;
 
 pub fn get_time() -> usize {
     time::read()
@@ -25,74 +41,365 @@ pub fn get_time_ms() -> usize {
     time::read() / (CLOCK_FREQ / MESC_PER_SEC)
 }
 
-pub fn set_next_trigger() {
-    set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
-}
-
 /// 获取当前时间 微秒
 #[allow(unused)]
 pub fn get_time_us() -> usize {
     time::read() / (CLOCK_FREQ / USEC_PER_SEC)
 }
 
+/// 获取当前时间 纳秒，先拆分出整秒部分避免乘法溢出
+#[allow(unused)]
+pub fn get_time_ns() -> usize {
+    ticks_to_ns(time::read())
+}
+
+/// 换算成 times 使用的时钟计数
+pub fn ticks_to_clock_t(ticks: usize) -> usize {
+    ticks / (CLOCK_FREQ / TICKS_PER_SEC)
+}
+
+pub fn ticks_to_us(ticks: usize) -> usize {
+    ticks / CLOCK_FREQ * USEC_PER_SEC + ticks % CLOCK_FREQ * USEC_PER_SEC / CLOCK_FREQ
+}
+
+fn ticks_to_ns(ticks: usize) -> usize {
+    ticks / CLOCK_FREQ * NSEC_PER_SEC + ticks % CLOCK_FREQ * NSEC_PER_SEC / CLOCK_FREQ
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeSpec {
+    pub tv_sec: usize,
+    pub tv_nsec: usize,
+}
+
+impl TimeSpec {
+    pub fn from_ticks(ticks: usize) -> Self {
+        Self {
+            tv_sec: ticks / CLOCK_FREQ,
+            tv_nsec: ticks % CLOCK_FREQ * NSEC_PER_SEC / CLOCK_FREQ,
+        }
+    }
+
+    /// 换算成 time 寄存器的计数，不足一个计数的部分向上取整
+    /// 超出表示范围时取 usize::MAX，即永远不会到期，调用前需要检查 tv_nsec 合法
+    pub fn to_ticks(self) -> usize {
+        self.tv_sec
+            .saturating_mul(CLOCK_FREQ)
+            .saturating_add((self.tv_nsec * CLOCK_FREQ + NSEC_PER_SEC - 1) / NSEC_PER_SEC)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.tv_nsec < NSEC_PER_SEC
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeVal {
+    pub tv_sec: usize,
+    pub tv_usec: usize,
+}
+
+impl TimeVal {
+    pub fn from_ticks(ticks: usize) -> Self {
+        Self {
+            tv_sec: ticks / CLOCK_FREQ,
+            tv_usec: ticks % CLOCK_FREQ * USEC_PER_SEC / CLOCK_FREQ,
+        }
+    }
+
+    /// 与 TimeSpec::to_ticks 相同，超出表示范围时取 usize::MAX
+    pub fn to_ticks(self) -> usize {
+        self.tv_sec
+            .saturating_mul(CLOCK_FREQ)
+            .saturating_add((self.tv_usec * CLOCK_FREQ + USEC_PER_SEC - 1) / USEC_PER_SEC)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.tv_usec < USEC_PER_SEC
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ITimerVal {
+    /// 周期，为 0 表示只触发一次
+    pub it_interval: TimeVal,
+    /// 距离下一次到期的时间，为 0 表示定时器未启用
+    pub it_value: TimeVal,
+}
+
+/// 进程的间隔定时器，时间都以 time 寄存器的计数表示
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ITimer {
+    pub interval: usize,
+    /// ITIMER_REAL 保存到期的绝对时间，其余两种保存剩余的 CPU 时间，0 表示未启用
+    pub value: usize,
+}
+
+/// 定时器到期时要做的事情
+pub enum TimerEvent {
+    /// 唤醒睡眠的线程
+    Wakeup(Arc<TaskControlBlock>),
+    /// 进程的 ITIMER_REAL 到期
+    Alarm(Weak<ProcessControlBlock>),
+}
+
+/// 定时器句柄，取消时只做标记，已取消的定时器在到期或者堆被压缩时丢弃，
+/// 因此取消操作是 O(1) 的，不需要在堆中查找
+#[derive(Clone)]
+pub struct TimerHandle(Arc<AtomicBool>);
+
+impl TimerHandle {
+    fn new() -> Self {
+        Self(Arc::new(AtomicBool::new(false)))
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(AtomicOrdering::Relaxed)
+    }
+}
+
 pub struct TimerCondVar {
-    /// 等待事件的超时时间
-    pub expire_ms: usize,
-    /// 等待它的线程集合
-    pub task: Arc<TaskControlBlock>,
+    /// 到期时间，以 time 寄存器的计数表示
+    pub expire: usize,
+    pub event: TimerEvent,
+    handle: TimerHandle,
 }
 
 impl PartialEq for TimerCondVar {
     fn eq(&self, other: &Self) -> bool {
-        self.expire_ms == other.expire_ms
+        self.expire == other.expire
     }
 }
 impl Eq for TimerCondVar {}
 impl PartialOrd for TimerCondVar {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let a = -(self.expire_ms as isize);
-        let b = -(other.expire_ms as isize);
-        Some(a.cmp(&b))
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for TimerCondVar {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        // BinaryHeap 是大顶堆，反过来比较使最早到期的定时器位于堆顶
+        other.expire.cmp(&self.expire)
+    }
+}
+
+/// 定时器队列，在小顶堆的基础上记录已经取消但仍留在堆中的定时器数量
+struct TimerQueue {
+    heap: BinaryHeap<TimerCondVar>,
+    cancelled: usize,
+}
+
+impl TimerQueue {
+    fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            cancelled: 0,
+        }
+    }
+
+    fn push(&mut self, expire: usize, event: TimerEvent) -> TimerHandle {
+        // 向上对齐到合并粒度，对齐后超出表示范围的保持 usize::MAX，即永远不会到期
+        let expire = match expire.checked_add(TIMER_SLACK - 1) {
+            Some(expire) => expire / TIMER_SLACK * TIMER_SLACK,
+            None => usize::MAX,
+        };
+        let handle = TimerHandle::new();
+        self.heap.push(TimerCondVar {
+            expire,
+            event,
+            handle: handle.clone(),
+        });
+        handle
+    }
+
+    /// 丢弃堆顶已经取消的定时器，返回最早到期的有效定时器
+    fn peek(&mut self) -> Option<&TimerCondVar> {
+        while self.heap.peek()?.handle.is_cancelled() {
+            self.heap.pop();
+            self.cancelled -= 1;
+        }
+        self.heap.peek()
+    }
+
+    fn pop_expired(&mut self, now: usize) -> Option<TimerCondVar> {
+        if matches!(self.peek(), Some(timer) if timer.expire <= now) {
+            self.heap.pop()
+        } else {
+            None
+        }
+    }
+
+    fn cancel(&mut self, handle: &TimerHandle) {
+        if handle.0.swap(true, AtomicOrdering::Relaxed) {
+            return;
+        }
+        self.cancelled += 1;
+        // 已取消的定时器超过一半时压缩一次，均摊下来仍然是 O(1)
+        if self.cancelled > COMPACT_THRESHOLD && self.cancelled * 2 > self.heap.len() {
+            self.heap.retain(|timer| !timer.handle.is_cancelled());
+            self.cancelled = 0;
+        }
     }
 }
 
+/// 堆中已取消的定时器数量超过该值才考虑压缩
+const COMPACT_THRESHOLD: usize = 64;
+/// 定时器合并的粒度，落在同一个粒度内的定时器在同一次时钟中断中到期
+const TIMER_SLACK: usize = CLOCK_FREQ / 20000;
+/// 时间片长度
+const TIME_SLICE: usize = CLOCK_FREQ / TICKS_PER_SEC;
+
 lazy_static! {
-    static ref TIMERS: UPSafeCell<BinaryHeap<TimerCondVar>> =
-        unsafe { UPSafeCell::new(BinaryHeap::<TimerCondVar>::new()) };
+    static ref TIMERS: UPSafeCell<TimerQueue> = unsafe { UPSafeCell::new(TimerQueue::new()) };
+    /// 当前设置给 SBI 的下一次时钟中断的时间
+    static ref NEXT_TRIGGER: UPSafeCell<usize> = unsafe { UPSafeCell::new(0) };
+}
+
+fn program_timer(deadline: usize) {
+    *NEXT_TRIGGER.exclusive_access() = deadline;
+    set_timer(deadline);
+}
+
+/// 当前进程启用了按 CPU 时间计时的定时器或者设置了 RLIMIT_CPU，需要时间片中断来推进
+fn current_cpu_timer_armed() -> bool {
+    current_task()
+        .and_then(|task| task.process.upgrade())
+        .map_or(false, |process| {
+            let process_inner = process.inner_exclusive_access();
+            process_inner.itimers[ITIMER_VIRTUAL].value != 0
+                || process_inner.itimers[ITIMER_PROF].value != 0
+                || process_inner.rlimits[RLIMIT_CPU].rlim_cur != RLIM_INFINITY
+        })
+}
+
+/// tickless 模式下只有存在其他就绪线程，或者需要统计 CPU 时间时才需要时间片中断
+fn need_time_slice() -> bool {
+    !TICKLESS || ready_task_count() > 0 || current_cpu_timer_armed()
 }
 
-pub fn add_timer(expire_ms: usize, task: Arc<TaskControlBlock>) {
+/// 设置下一次时钟中断：最早到期的定时器和当前时间片的结束时间中较早的一个
+pub fn set_next_trigger() {
+    let mut deadline = if need_time_slice() {
+        get_time() + TIME_SLICE
+    } else {
+        usize::MAX
+    };
+    if let Some(timer) = TIMERS.exclusive_access().peek() {
+        deadline = deadline.min(timer.expire);
+    }
+    program_timer(deadline);
+}
+
+/// 返回用户态前调用，tickless 模式下如果需要时间片中断而当前没有设置，重新开始计算时间片
+pub fn arm_time_slice() {
+    if !TICKLESS || !need_time_slice() {
+        return;
+    }
+    let slice_end = get_time() + TIME_SLICE;
+    let next_trigger = *NEXT_TRIGGER.exclusive_access();
+    if slice_end < next_trigger {
+        program_timer(slice_end);
+    }
+}
+
+fn push_timer(expire: usize, event: TimerEvent) -> TimerHandle {
     let mut timers = TIMERS.exclusive_access();
-    timers.push(TimerCondVar { expire_ms, task });
+    let handle = timers.push(expire, event);
+    let earliest = timers.peek().unwrap().expire;
+    drop(timers);
+    // 比已经设置的时钟中断更早到期，需要提前触发
+    let next_trigger = *NEXT_TRIGGER.exclusive_access();
+    if earliest < next_trigger {
+        program_timer(earliest);
+    }
+    handle
+}
+
+/// 添加一个到期唤醒线程的定时器，句柄保存在线程中以便取消
+pub fn add_timer(expire: usize, task: Arc<TaskControlBlock>) {
+    let handle = push_timer(expire, TimerEvent::Wakeup(Arc::clone(&task)));
+    task.inner_exclusive_access().timer = Some(handle);
+}
+
+/// 为进程的 ITIMER_REAL 添加一次到期事件
+pub fn add_alarm(expire: usize, process: &Arc<ProcessControlBlock>) -> TimerHandle {
+    push_timer(expire, TimerEvent::Alarm(Arc::downgrade(process)))
+}
+
+pub fn cancel_timer(handle: &TimerHandle) {
+    TIMERS.exclusive_access().cancel(handle);
 }
 
+/// 取消线程的睡眠定时器
 pub fn remove_timer(task: Arc<TaskControlBlock>) {
-    let mut timers = TIMERS.exclusive_access();
-    let mut temp = BinaryHeap::<TimerCondVar>::new();
-    for condvar in timers.drain() {
-        if Arc::as_ptr(&task) != Arc::as_ptr(&condvar.task) {
-            temp.push(condvar);
-        }
+    let handle = task.inner_exclusive_access().timer.take();
+    if let Some(handle) = handle {
+        cancel_timer(&handle);
+    }
+}
+
+/// ITIMER_REAL 到期，向进程发送 SIGALRM，周期定时器重新排入队列
+fn fire_alarm(timers: &mut TimerQueue, process: &Arc<ProcessControlBlock>) -> bool {
+    let mut process_inner = process.inner_exclusive_access();
+    if process_inner.is_zombie {
+        return false;
+    }
+    let interval = process_inner.itimers[ITIMER_REAL].interval;
+    if interval > 0 {
+        // 错过的周期不再补发
+        let next = get_time().saturating_add(interval);
+        process_inner.itimers[ITIMER_REAL].value = next;
+        let handle = timers.push(next, TimerEvent::Alarm(Arc::downgrade(process)));
+        process_inner.real_timer = Some(handle);
+    } else {
+        process_inner.itimers[ITIMER_REAL].value = 0;
+        process_inner.real_timer = None;
     }
-    timers.clear();
-    timers.append(&mut temp);
+    process_inner.signals |= SignalFlags::SIGALRM;
+    true
 }
 
 pub fn checker_timer() {
-    let current_time = get_time_ms();
+    let current_time = get_time();
     let mut timers = TIMERS.exclusive_access();
-    while let Some(timer) = timers.peek() {
-        if timer.expire_ms <= current_time {
-            wakeup_task(Arc::clone(&timer.task));
-            timers.pop();
-        } else {
-            break;
+    let mut signaled: Vec<Arc<ProcessControlBlock>> = Vec::new();
+    while let Some(timer) = timers.pop_expired(current_time) {
+        match timer.event {
+            TimerEvent::Wakeup(task) => {
+                task.inner_exclusive_access().timer = None;
+                wakeup_task(task);
+            }
+            TimerEvent::Alarm(process) => {
+                if let Some(process) = process.upgrade() {
+                    if fire_alarm(&mut timers, &process) {
+                        signaled.push(process);
+                    }
+                }
+            }
+        }
+    }
+    drop(timers);
+    // 收到信号的进程中正在 nanosleep 的线程被提前唤醒
+    for process in signaled {
+        let process_inner = process.inner_exclusive_access();
+        let sleeping: Vec<_> = process_inner
+            .tasks
+            .iter()
+            .flatten()
+            .filter(|task| {
+                let task_inner = task.inner_exclusive_access();
+                task_inner.interruptible && task_inner.timer.is_some()
+            })
+            .cloned()
+            .collect();
+        drop(process_inner);
+        for task in sleeping {
+            remove_timer(Arc::clone(&task));
+            wakeup_task(task);
         }
     }
 }
@@ -24,6 +24,11 @@ impl TrapContext {
         self.x[2] = sp;
     }
 
+    /// 设置 tp 寄存器，指向线程的 TLS 块
+    pub fn set_tp(&mut self, tp: usize) {
+        self.x[4] = tp;
+    }
+
     pub fn app_init_context(
         entry: usize,
         sp: usize,
@@ -5,20 +5,21 @@
 mod context;
 
 use crate::config::TRAMPOLINE;
+use crate::syscall::syscall;
 use crate::task::{
-    check_signals_error_of_current, current_add_signal, current_trap_cx, current_trap_cx_user_va,
-    current_user_token, exit_current_and_run_next, SignalFlags,
+    check_signals_error_of_current, current_add_page_fault, current_add_signal, current_trap_cx,
+    current_trap_cx_user_va, current_trap_enter, current_trap_leave, current_user_token,
+    exit_current_and_run_next, preempt_current_and_run_next, SignalFlags,
 };
-use crate::timer::{checker_timer, set_next_trigger};
-use crate::{syscall::syscall, task::suspend_current_and_run_next};
+use crate::timer::{arm_time_slice, checker_timer, set_next_trigger};
 use core::arch::{asm, global_asm};
 use log::{debug, error, info};
-use riscv::register::sie;
 use riscv::register::{
     mtvec::TrapMode,
     scause::{self, Exception, Interrupt, Trap},
     stval, stvec,
 };
+use riscv::register::{sie, sstatus};
 
 // 引入了一个外部符号 __alltraps ，并将 stvec 设置为 Direct 模式指向它的地址
 // 在 os/src/trap/trap.S 中实现 Trap 上下文保存/恢复的汇编代码，分别用外部符号
@@ -27,6 +28,18 @@ use riscv::register::{
 
 global_asm!(include_str!("trap.S"));
 
+// 空闲时使用的 trap 入口，只用来把处理器从 wfi 中唤醒。
+// 清除 sstatus.SPIE 使 sret 返回后中断保持关闭，不修改任何通用寄存器
+global_asm!(
+    "
+    .section .text
+    .align 2
+__idle_trap:
+    csrci sstatus, 0x20
+    sret
+"
+);
+
 pub fn init() {
     set_kernel_trap_entry();
 }
@@ -49,6 +62,22 @@ fn set_user_trap_entry() {
     }
 }
 
+/// 打开 S 态中断并执行 wfi，直到 sie 中使能的中断到来
+/// 中断由 __idle_trap 接收后立即返回，具体的处理交给调用者
+pub fn wait_for_interrupt() {
+    extern "C" {
+        fn __idle_trap();
+    }
+    unsafe {
+        stvec::write(__idle_trap as usize, TrapMode::Direct);
+        sstatus::set_sie();
+        asm!("wfi");
+        // wfi 也可能在没有进入 trap 的情况下返回
+        sstatus::clear_sie();
+    }
+    set_kernel_trap_entry();
+}
+
 pub fn enable_timer_interrupt() {
     unsafe {
         // 调用 sie 设置时间中断
@@ -60,6 +89,7 @@ pub fn enable_timer_interrupt() {
 pub fn trap_handler() -> ! {
     // set_kernel_trap_entry 将 stvec 修改为同模块下另一个函数 trap_from_kernel 的地址
     set_kernel_trap_entry();
+    current_trap_enter();
     let cx = current_trap_cx();
     // 进入用户态的时候，可以统计用户态的运行时间
     let scause = scause::read();
@@ -77,9 +107,12 @@ pub fn trap_handler() -> ! {
             // 这样在 __restore 的时候 sepc 在恢复之后就会指向 ecall 的下一条指令，并在 sret 之后从那里开始执行。
             cx.sepc += 4;
             cx = current_trap_cx();
-            // Trap 上下文取出作为 syscall ID 的 a7 和系统调用的三个参数 a0~a2 传给 syscall 函数并获取返回值。
+            // Trap 上下文取出作为 syscall ID 的 a7 和系统调用的六个参数 a0~a5 传给 syscall 函数并获取返回值。
             // syscall 函数是在 syscall 子模块中实现的。 这段代码是处理正常系统调用的控制逻辑。
-            cx.x[10] = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]) as usize;
+            cx.x[10] = syscall(
+                cx.x[17],
+                [cx.x[10], cx.x[11], cx.x[12], cx.x[13], cx.x[14], cx.x[15]],
+            ) as usize;
         }
 
         Trap::Exception(Exception::StoreFault)
@@ -92,6 +125,7 @@ pub fn trap_handler() -> ! {
             //run_next_app();
             // exit_current_and_run_next(-2);
             //panic!("[kernel] not continue!");
+            current_add_page_fault();
             current_add_signal(SignalFlags::SIGSEGV);
         }
         Trap::Exception(Exception::IllegalInstruction) => {
@@ -104,11 +138,11 @@ pub fn trap_handler() -> ! {
             debug!("[kernel] SupervisorTimer in application.");
             //suspend_current_and_run_next();
             //panic!("[kernel] not continue!");
-            // 当触发一个 S 特权级时钟中断，首先重置计时器
-            set_next_trigger();
+            // 当触发一个 S 特权级时钟中断，先处理到期的定时器，再根据最早到期的定时器重置计时器
             checker_timer();
-            // 调用函数
-            suspend_current_and_run_next();
+            set_next_trigger();
+            // 时间片用完，切换到下一个线程
+            preempt_current_and_run_next();
         }
         _ => {
             error!("[kernel] error in trap mod");
@@ -137,6 +171,8 @@ pub fn trap_return() -> ! {
     // set_user_trap_entry ，来让应用 Trap 到 S 的时候可以跳转到 __alltraps 。
     // 注：我们把 stvec 设置为内核和应用地址空间共享的跳板页面的起始地址
     set_user_trap_entry();
+    current_trap_leave();
+    arm_time_slice();
     // 准备好 __restore 需要两个参数：分别是 Trap 上下文在应用地址空间中的虚拟地址和要继续执行的应用地址空间的 token
     // 最后我们需要跳转到 __restore ，以执行：切换到应用地址空间、从 Trap 上下文中恢复通用寄存器、 sret 继续执行应用。
     // 它的关键在于如何找到 __restore 在内核/应用地址空间中共同的虚拟地址。
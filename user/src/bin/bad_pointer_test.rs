@@ -0,0 +1,605 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicU32;
+use user_lib::{
+    close, condvar_create, exit, mq_getattr, mq_open, mq_unlink, mutex_create, mutex_unlock, open,
+    pipe, raw_syscall, read, recvmsg, semaphore_create, sendmsg, sigaction, sigreturn, socket,
+    socketpair, write, Errno, ITimerVal, MqAttr, MsgHdr, OpenFlags, PollEvents, PollFd,
+    SignalAction, TimeSpec, TimeVal, AF_UNIX, EAGAIN, EFAULT, EINTR, EINVAL, FUTEX_WAIT,
+    FUTEX_WAKE, ITIMER_REAL, RLIMIT_NOFILE, SIGALRM, SOCK_STREAM,
+};
+
+// 系统调用号，与 user_lib 中的定义相同
+const SYSCALL_MKFIFO: usize = 33;
+const SYSCALL_OPEN: usize = 56;
+const SYSCALL_PIPE: usize = 59;
+const SYSCALL_READ: usize = 63;
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_PPOLL: usize = 73;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_NANOSLEEP: usize = 101;
+const SYSCALL_GETITIMER: usize = 102;
+const SYSCALL_SETITIMER: usize = 103;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_GETRLIMIT: usize = 163;
+const SYSCALL_SETRLIMIT: usize = 164;
+const SYSCALL_GETRUSAGE: usize = 165;
+const SYSCALL_MQ_OPEN: usize = 180;
+const SYSCALL_MQ_UNLINK: usize = 181;
+const SYSCALL_MQ_TIMEDSEND: usize = 182;
+const SYSCALL_MQ_TIMEDRECEIVE: usize = 183;
+const SYSCALL_MQ_GETATTR: usize = 185;
+const SYSCALL_SOCKETPAIR: usize = 199;
+const SYSCALL_BIND: usize = 200;
+const SYSCALL_CONNECT: usize = 203;
+const SYSCALL_SENDMSG: usize = 211;
+const SYSCALL_RECVMSG: usize = 212;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_CPU_STAT: usize = 411;
+const SYSCALL_THREAD_CREATE: usize = 1000;
+const SYSCALL_WAITTID: usize = 1002;
+const SYSCALL_MUTEX_LOCK_TIMEOUT: usize = 1014;
+const SYSCALL_SEMAPHORE_DOWN_TIMEOUT: usize = 1024;
+const SYSCALL_CONDVAR_WAIT_TIMEOUT: usize = 1033;
+
+const PAGE_SIZE: usize = 4096;
+
+/// 空指针、没有映射的低地址、内核的物理地址、超出 SV39 用户地址空间的地址以及跳板页面
+const BAD_POINTERS: [usize; 5] = [0, 0x10, 0x8020_0000, 1 << 38, usize::MAX - 0xfff];
+
+/// 被替换成非法指针的参数的用途
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Ptr {
+    /// 以 \0 结尾的字符串
+    Str,
+    /// 内核读取的缓冲区或者结构体
+    In,
+    /// 内核写入的缓冲区或者结构体
+    Out,
+}
+
+struct Case {
+    name: &'static str,
+    id: usize,
+    args: [usize; 6],
+    /// 被替换的参数的下标
+    arg: usize,
+    ptr: Ptr,
+    /// 内核访问的字节数，超过 8 字节时还会测试跨越到没有映射的页面的指针
+    size: usize,
+    /// 空指针表示省略这个参数
+    nullable: bool,
+}
+
+impl Case {
+    fn new(name: &'static str, id: usize, args: [usize; 6], arg: usize, ptr: Ptr) -> Self {
+        Self {
+            name,
+            id,
+            args,
+            arg,
+            ptr,
+            size: 0,
+            nullable: false,
+        }
+    }
+
+    fn size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    fn check(&self, ptr: usize) {
+        let mut args = self.args;
+        args[self.arg] = ptr;
+        let ret = raw_syscall(self.id, args);
+        assert_eq!(
+            Errno::result(ret),
+            Err(EFAULT),
+            "{} with {:#x}",
+            self.name,
+            ptr
+        );
+    }
+}
+
+fn ptr<T>(value: &T) -> usize {
+    value as *const T as usize
+}
+
+/// 内核会写入的参数，调用之后直接读取结果
+fn ptr_mut<T>(value: &mut T) -> usize {
+    value as *mut T as usize
+}
+
+fn thread_entry() {
+    exit(0);
+}
+
+fn on_alarm() {
+    sigreturn();
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    extern "C" {
+        fn end_bss();
+    }
+    // .bss 之后是没有映射的保护页面，紧挨着它的指针指向的对象一半可以访问，另一半不能访问
+    let guard = (end_bss as usize + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+    // 代码段可读不可写
+    let text = main as usize & !(PAGE_SIZE - 1);
+
+    let mut pipe_fd = [0usize; 2];
+    pipe(&mut pipe_fd).unwrap();
+    assert_eq!(write(pipe_fd[1], b"data"), Ok(4));
+    let mut sv = [0usize; 2];
+    socketpair(AF_UNIX, SOCK_STREAM, &mut sv).unwrap();
+    let sock = socket(AF_UNIX, SOCK_STREAM).unwrap();
+    let mq = mq_open(
+        "bad_pointer_test\0",
+        OpenFlags::RDWR | OpenFlags::CREATE,
+        None,
+    )
+    .unwrap();
+    let mut mq_attr = MqAttr::default();
+    mq_getattr(mq, &mut mq_attr).unwrap();
+    let mutex_id = mutex_create().unwrap();
+    let sem_id = semaphore_create(0).unwrap();
+    let condvar_id = condvar_create().unwrap();
+    let first_free_fd = open("bad_pointer_test\0", OpenFlags::READONLY).unwrap();
+    close(first_free_fd).unwrap();
+
+    let zero = TimeSpec::default();
+    let itimer = ITimerVal::default();
+    let futex_word = AtomicU32::new(0);
+    let pollfd = PollFd::new(pipe_fd[0], PollEvents::IN);
+    let argv = [0usize];
+    let missing = "bad_pointer_test_missing\0".as_ptr() as usize;
+    let mut buf = vec![0u8; mq_attr.msgsize];
+    let buf_ptr = buf.as_mut_ptr() as usize;
+
+    use Ptr::*;
+    let cases = [
+        Case::new("write", SYSCALL_WRITE, [1, 0, 16, 0, 0, 0], 1, In).size(16),
+        Case::new("read", SYSCALL_READ, [pipe_fd[0], 0, 16, 0, 0, 0], 1, Out).size(16),
+        Case::new("open", SYSCALL_OPEN, [0; 6], 0, Str),
+        Case::new("mkfifo", SYSCALL_MKFIFO, [0, 0o644, 0, 0, 0, 0], 0, Str),
+        Case::new("pipe", SYSCALL_PIPE, [0; 6], 0, Out).size(16),
+        Case::new("ppoll fds", SYSCALL_PPOLL, [0, 1, 0, 0, 0, 0], 0, Out).size(8),
+        Case::new(
+            "ppoll timeout",
+            SYSCALL_PPOLL,
+            [ptr(&pollfd), 1, 0, 0, 0, 0],
+            2,
+            In,
+        )
+        .size(16)
+        .nullable(),
+        Case::new("futex", SYSCALL_FUTEX, [0, FUTEX_WAKE, 1, 0, 0, 0], 0, In).size(4),
+        Case::new(
+            "futex timeout",
+            SYSCALL_FUTEX,
+            [ptr(&futex_word), FUTEX_WAIT, 0, 0, 0, 0],
+            3,
+            In,
+        )
+        .size(16)
+        .nullable(),
+        Case::new("nanosleep req", SYSCALL_NANOSLEEP, [0; 6], 0, In).size(16),
+        Case::new(
+            "nanosleep rem",
+            SYSCALL_NANOSLEEP,
+            [ptr(&zero), 0, 0, 0, 0, 0],
+            1,
+            Out,
+        )
+        .size(16)
+        .nullable(),
+        Case::new(
+            "getitimer",
+            SYSCALL_GETITIMER,
+            [ITIMER_REAL, 0, 0, 0, 0, 0],
+            1,
+            Out,
+        )
+        .size(32),
+        Case::new(
+            "setitimer new",
+            SYSCALL_SETITIMER,
+            [ITIMER_REAL, 0, 0, 0, 0, 0],
+            1,
+            In,
+        )
+        .size(32),
+        Case::new(
+            "setitimer old",
+            SYSCALL_SETITIMER,
+            [ITIMER_REAL, ptr(&itimer), 0, 0, 0, 0],
+            2,
+            Out,
+        )
+        .size(32)
+        .nullable(),
+        Case::new("clock_gettime", SYSCALL_CLOCK_GETTIME, [0; 6], 1, Out).size(16),
+        Case::new("times", SYSCALL_TIMES, [0; 6], 0, Out).size(32),
+        Case::new(
+            "getrlimit",
+            SYSCALL_GETRLIMIT,
+            [RLIMIT_NOFILE, 0, 0, 0, 0, 0],
+            1,
+            Out,
+        )
+        .size(16),
+        Case::new(
+            "setrlimit",
+            SYSCALL_SETRLIMIT,
+            [RLIMIT_NOFILE, 0, 0, 0, 0, 0],
+            1,
+            In,
+        )
+        .size(16),
+        Case::new("getrusage", SYSCALL_GETRUSAGE, [0; 6], 1, Out).size(64),
+        Case::new("mq_open name", SYSCALL_MQ_OPEN, [0; 6], 0, Str),
+        Case::new(
+            "mq_open attr",
+            SYSCALL_MQ_OPEN,
+            [
+                "bad_pointer_test\0".as_ptr() as usize,
+                OpenFlags::RDWR.bits() as usize,
+                0,
+                0,
+                0,
+                0,
+            ],
+            2,
+            In,
+        )
+        .size(32)
+        .nullable(),
+        Case::new("mq_unlink", SYSCALL_MQ_UNLINK, [0; 6], 0, Str),
+        Case::new(
+            "mq_timedsend msg",
+            SYSCALL_MQ_TIMEDSEND,
+            [mq, 0, 16, 0, 0, 0],
+            1,
+            In,
+        )
+        .size(16),
+        Case::new(
+            "mq_timedsend timeout",
+            SYSCALL_MQ_TIMEDSEND,
+            [mq, buf_ptr, 4, 0, 0, 0],
+            4,
+            In,
+        )
+        .size(16)
+        .nullable(),
+        Case::new(
+            "mq_timedreceive buf",
+            SYSCALL_MQ_TIMEDRECEIVE,
+            [mq, 0, mq_attr.msgsize, 0, 0, 0],
+            1,
+            Out,
+        )
+        .size(mq_attr.msgsize),
+        Case::new(
+            "mq_timedreceive prio",
+            SYSCALL_MQ_TIMEDRECEIVE,
+            [mq, buf_ptr, mq_attr.msgsize, 0, 0, 0],
+            3,
+            Out,
+        )
+        .size(4)
+        .nullable(),
+        Case::new(
+            "mq_timedreceive timeout",
+            SYSCALL_MQ_TIMEDRECEIVE,
+            [mq, buf_ptr, mq_attr.msgsize, 0, 0, 0],
+            4,
+            In,
+        )
+        .size(16)
+        .nullable(),
+        Case::new(
+            "mq_getattr",
+            SYSCALL_MQ_GETATTR,
+            [mq, 0, 0, 0, 0, 0],
+            1,
+            Out,
+        )
+        .size(32),
+        Case::new(
+            "socketpair",
+            SYSCALL_SOCKETPAIR,
+            [AF_UNIX, SOCK_STREAM, 0, 0, 0, 0],
+            3,
+            Out,
+        )
+        .size(16),
+        Case::new("bind", SYSCALL_BIND, [sock, 0, 0, 0, 0, 0], 1, Str),
+        Case::new("connect", SYSCALL_CONNECT, [sock, 0, 0, 0, 0, 0], 1, Str),
+        Case::new("sendmsg", SYSCALL_SENDMSG, [sv[0], 0, 0, 0, 0, 0], 1, In).size(32),
+        Case::new("recvmsg", SYSCALL_RECVMSG, [sv[1], 0, 0, 0, 0, 0], 1, Out).size(32),
+        Case::new(
+            "exec path",
+            SYSCALL_EXEC,
+            [0, ptr(&argv), 0, 0, 0, 0],
+            0,
+            Str,
+        ),
+        // argv 跨页时读到的 .bss 中的内容不确定，不测试跨页的指针
+        Case::new("exec argv", SYSCALL_EXEC, [missing, 0, 0, 0, 0, 0], 1, In),
+        Case::new(
+            "waitpid",
+            SYSCALL_WAITPID,
+            [usize::MAX, 0, 0, 0, 0, 0],
+            1,
+            Out,
+        )
+        .size(4)
+        .nullable(),
+        Case::new("cpu_stat", SYSCALL_CPU_STAT, [0; 6], 0, Out).size(32),
+        Case::new(
+            "thread_create",
+            SYSCALL_THREAD_CREATE,
+            [thread_entry as usize, 0, 0, 0, 0, 0],
+            2,
+            In,
+        )
+        .size(24)
+        .nullable(),
+        Case::new("waittid", SYSCALL_WAITTID, [0; 6], 1, Out).size(4),
+        Case::new(
+            "mutex_lock_timeout",
+            SYSCALL_MUTEX_LOCK_TIMEOUT,
+            [mutex_id, 0, 0, 0, 0, 0],
+            1,
+            In,
+        )
+        .size(16),
+        Case::new(
+            "semaphore_down_timeout",
+            SYSCALL_SEMAPHORE_DOWN_TIMEOUT,
+            [sem_id, 0, 0, 0, 0, 0],
+            1,
+            In,
+        )
+        .size(16),
+        Case::new(
+            "condvar_wait_timeout",
+            SYSCALL_CONDVAR_WAIT_TIMEOUT,
+            [condvar_id, mutex_id, 0, 0, 0, 0],
+            2,
+            In,
+        )
+        .size(16),
+    ];
+
+    for case in cases.iter() {
+        for &bad in BAD_POINTERS.iter() {
+            if bad == 0 && case.nullable {
+                continue;
+            }
+            case.check(bad);
+        }
+        case.check(guard);
+        if case.ptr == Out {
+            case.check(text);
+        }
+        if case.size > 8 {
+            case.check(guard - 8);
+        }
+    }
+
+    // msghdr 中的缓冲区和文件描述符数组也要检查
+    for &bad in BAD_POINTERS[1..].iter() {
+        let msgs = [
+            MsgHdr {
+                buf: bad,
+                len: 8,
+                ..Default::default()
+            },
+            MsgHdr {
+                buf: buf_ptr,
+                len: 8,
+                fds: bad,
+                nfds: 1,
+            },
+        ];
+        for msg in msgs {
+            let ret = raw_syscall(SYSCALL_SENDMSG, [sv[0], ptr(&msg), 0, 0, 0, 0]);
+            assert_eq!(Errno::result(ret), Err(EFAULT));
+            let ret = raw_syscall(SYSCALL_RECVMSG, [sv[1], ptr(&msg), 0, 0, 0, 0]);
+            assert_eq!(Errno::result(ret), Err(EFAULT));
+        }
+        // exec 的参数字符串不可读
+        let argv = [bad, 0];
+        let ret = raw_syscall(SYSCALL_EXEC, [missing, ptr(&argv), 0, 0, 0, 0]);
+        assert_eq!(Errno::result(ret), Err(EFAULT));
+    }
+
+    // 接收时文件描述符数组不可写，数据和文件留在套接字中
+    assert_eq!(sendmsg(sv[0], b"with fds", &[pipe_fd[0]]), Ok(8));
+    let msg = MsgHdr {
+        buf: buf_ptr,
+        len: 8,
+        fds: text,
+        nfds: 1,
+    };
+    let ret = raw_syscall(SYSCALL_RECVMSG, [sv[1], ptr(&msg), 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Err(EFAULT));
+    let mut fds = [0usize; 1];
+    let mut nfds = 0;
+    assert_eq!(recvmsg(sv[1], &mut buf[..8], &mut fds, &mut nfds), Ok(8));
+    assert_eq!((&buf[..8], nfds), (&b"with fds"[..], 1));
+    close(fds[0]).unwrap();
+
+    // 出错的 pipe 和 socketpair 没有分配文件描述符
+    let fd = open("bad_pointer_test\0", OpenFlags::READONLY).unwrap();
+    assert_eq!(fd, first_free_fd);
+    close(fd).unwrap();
+
+    // 跨页的合法缓冲区和字符串可以正常访问
+    let mut pages: Vec<u8> = vec![0; 3 * PAGE_SIZE];
+    let boundary = (pages.as_ptr() as usize + PAGE_SIZE) & !(PAGE_SIZE - 1);
+    let offset = boundary - pages.as_ptr() as usize;
+    let msg = b"across the page boundary";
+    let (start, end) = (offset - 5, offset - 5 + msg.len());
+    pages[start..end].copy_from_slice(msg);
+    assert_eq!(write(pipe_fd[1], &pages[start..end]), Ok(msg.len()));
+    pages[start..end].fill(0);
+    // 管道中还有开头写入的 "data"
+    assert_eq!(read(pipe_fd[0], &mut pages[start..start + 4]), Ok(4));
+    assert_eq!(read(pipe_fd[0], &mut pages[start..end]), Ok(msg.len()));
+    assert_eq!(&pages[start..end], msg);
+    let name = b"bad_pointer_test\0";
+    let start = offset - 3;
+    pages[start..start + name.len()].copy_from_slice(name);
+    let path = core::str::from_utf8(&pages[start..start + name.len()]).unwrap();
+    let fd = open(path, OpenFlags::READONLY).unwrap();
+    close(fd).unwrap();
+
+    // 跨页的结构体也可以正常读写，两个页面对应的物理页帧不一定连续
+    let spec = (boundary - 8) as *mut TimeSpec;
+    unsafe {
+        spec.write(TimeSpec {
+            tv_sec: 0,
+            tv_nsec: 1000,
+        });
+    }
+    let ret = raw_syscall(SYSCALL_NANOSLEEP, [spec as usize, 0, 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Ok(0));
+    let ret = raw_syscall(SYSCALL_CLOCK_GETTIME, [0, spec as usize, 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Ok(0));
+    let now = unsafe { spec.read() };
+    assert!(now.tv_sec > 0 || now.tv_nsec > 0);
+    let value = (boundary - 16) as *mut ITimerVal;
+    unsafe { value.write(ITimerVal::default()) };
+    let ret = raw_syscall(SYSCALL_SETITIMER, [ITIMER_REAL, value as usize, 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Ok(0));
+    let ret = raw_syscall(SYSCALL_GETITIMER, [ITIMER_REAL, value as usize, 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Ok(0));
+
+    // 指针合法但内容越界的结构体返回错误，过长的时间被当作永远不会到期，而不是让内核崩溃
+    let huge = TimeSpec {
+        tv_sec: usize::MAX / 2,
+        tv_nsec: 0,
+    };
+    let bad_nsec = TimeSpec {
+        tv_sec: usize::MAX,
+        tv_nsec: 1_000_000_000,
+    };
+    let ret = raw_syscall(SYSCALL_NANOSLEEP, [ptr(&bad_nsec), 0, 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Err(EINVAL));
+    let bad_usec = ITimerVal {
+        it_value: TimeVal {
+            tv_sec: usize::MAX,
+            tv_usec: 1_000_000,
+        },
+        ..Default::default()
+    };
+    let ret = raw_syscall(SYSCALL_SETITIMER, [ITIMER_REAL, ptr(&bad_usec), 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Err(EINVAL));
+    let far = ITimerVal {
+        it_value: TimeVal {
+            tv_sec: usize::MAX,
+            tv_usec: 0,
+        },
+        ..Default::default()
+    };
+    let ret = raw_syscall(SYSCALL_SETITIMER, [ITIMER_REAL, ptr(&far), 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Ok(0));
+    let mut current = ITimerVal::default();
+    let ret = raw_syscall(
+        SYSCALL_GETITIMER,
+        [ITIMER_REAL, ptr_mut(&mut current), 0, 0, 0, 0],
+    );
+    assert_eq!(Errno::result(ret), Ok(0));
+    assert!(current.it_value.tv_sec > 1 << 32);
+    // 周期极长的定时器第一次到期后重新排入队列，打断同样极长的 nanosleep
+    let action = SignalAction {
+        handler: on_alarm as usize,
+        ..Default::default()
+    };
+    sigaction(SIGALRM, Some(&action), None).unwrap();
+    let alarm = ITimerVal {
+        it_interval: far.it_value,
+        it_value: TimeVal {
+            tv_sec: 0,
+            tv_usec: 20000,
+        },
+    };
+    let ret = raw_syscall(SYSCALL_SETITIMER, [ITIMER_REAL, ptr(&alarm), 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Ok(0));
+    let mut rem = TimeSpec::default();
+    let ret = raw_syscall(
+        SYSCALL_NANOSLEEP,
+        [ptr(&huge), ptr_mut(&mut rem), 0, 0, 0, 0],
+    );
+    assert_eq!(Errno::result(ret), Err(EINTR));
+    assert!(rem.tv_sec > 0);
+    let off = ITimerVal::default();
+    let ret = raw_syscall(SYSCALL_SETITIMER, [ITIMER_REAL, ptr(&off), 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Ok(0));
+    // 带超时的同步操作：值已经改变的 futex、可以立即加锁的互斥锁和已经就绪的文件
+    let ret = raw_syscall(
+        SYSCALL_FUTEX,
+        [ptr(&futex_word), FUTEX_WAIT, 1, ptr(&huge), 0, 0],
+    );
+    assert_eq!(Errno::result(ret), Err(EAGAIN));
+    let ret = raw_syscall(
+        SYSCALL_FUTEX,
+        [ptr(&futex_word), FUTEX_WAIT, 0, ptr(&bad_nsec), 0, 0],
+    );
+    assert_eq!(Errno::result(ret), Err(EINVAL));
+    let ret = raw_syscall(
+        SYSCALL_MUTEX_LOCK_TIMEOUT,
+        [mutex_id, ptr(&huge), 0, 0, 0, 0],
+    );
+    assert_eq!(Errno::result(ret), Ok(0));
+    mutex_unlock(mutex_id).unwrap();
+    let ret = raw_syscall(
+        SYSCALL_SEMAPHORE_DOWN_TIMEOUT,
+        [sem_id, ptr(&bad_nsec), 0, 0, 0, 0],
+    );
+    assert_eq!(Errno::result(ret), Err(EINVAL));
+    let writable = PollFd::new(pipe_fd[1], PollEvents::OUT);
+    let ret = raw_syscall(SYSCALL_PPOLL, [ptr(&writable), 1, ptr(&huge), 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Ok(1));
+    // 数量越界的数组
+    let ret = raw_syscall(SYSCALL_PPOLL, [ptr(&pollfd), usize::MAX, 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Err(EINVAL));
+    let msg = MsgHdr {
+        buf: buf_ptr,
+        len: 8,
+        fds: ptr(&argv),
+        nfds: usize::MAX,
+    };
+    let ret = raw_syscall(SYSCALL_SENDMSG, [sv[0], ptr(&msg), 0, 0, 0, 0]);
+    assert_eq!(Errno::result(ret), Err(EINVAL));
+
+    mq_unlink("bad_pointer_test\0").unwrap();
+    close(mq).unwrap();
+    close(sock).unwrap();
+    close(sv[0]).unwrap();
+    close(sv[1]).unwrap();
+    close(pipe_fd[0]).unwrap();
+    close(pipe_fd[1]).unwrap();
+    println!("bad_pointer_test passed!");
+    0
+}
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{barrier_create, barrier_wait, spawn, waittid, yield_, EINVAL};
+
+const THREAD_NUM: usize = 4;
+const ROUNDS: usize = 3;
+
+static ARRIVED: [AtomicUsize; ROUNDS] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+static SERIAL: AtomicUsize = AtomicUsize::new(0);
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(barrier_create(0), Err(EINVAL));
+    let barrier = barrier_create(THREAD_NUM).unwrap();
+    let mut tids = Vec::new();
+    for i in 0..THREAD_NUM {
+        tids.push(
+            spawn(move || {
+                for arrived in ARRIVED.iter() {
+                    // 让各个线程以不同的顺序到达屏障
+                    for _ in 0..i {
+                        yield_();
+                    }
+                    arrived.fetch_add(1, Ordering::Relaxed);
+                    if barrier_wait(barrier) == Ok(1) {
+                        SERIAL.fetch_add(1, Ordering::Relaxed);
+                    }
+                    // 通过屏障时本轮的所有线程都已经到达
+                    assert_eq!(arrived.load(Ordering::Relaxed), THREAD_NUM);
+                }
+                0
+            })
+            .unwrap(),
+        );
+    }
+    for tid in tids {
+        assert_eq!(waittid(tid), Ok(0));
+    }
+    // 每一轮恰好有一个线程是最后到达的
+    assert_eq!(SERIAL.load(Ordering::Relaxed), ROUNDS);
+    println!("barrier_test passed!");
+    0
+}
@@ -10,19 +10,18 @@ use user_lib::{close, open, read, OpenFlags};
 #[no_mangle]
 pub fn main(argc: usize, argv: &[&str]) -> i32 {
     assert!(argc == 2);
-    let fd = open(argv[1], OpenFlags::READONLY);
-    if fd == -1 {
-        panic!("Error occured when opening file");
-    }
-    let fd = fd as usize;
+    let fd = match open(argv[1], OpenFlags::READONLY) {
+        Ok(fd) => fd,
+        Err(err) => panic!("Error occured when opening file: {}", err),
+    };
     let mut buf = [0u8; 256];
     loop {
-        let size = read(fd, &mut buf) as usize;
+        let size = read(fd, &mut buf).unwrap();
         if size == 0 {
             break;
         }
         print!("{}", core::str::from_utf8(&buf[..size]).unwrap());
     }
-    close(fd);
+    close(fd).unwrap();
     0
 }
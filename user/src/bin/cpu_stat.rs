@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{cpu_stat, sleep, CpuStat};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = CpuStat::default();
+    assert_eq!(cpu_stat(&mut before), Ok(()));
+    // 忙碌时间和空闲时间分别取整
+    assert!(before.busy + before.idle <= before.uptime + 1);
+    assert!(before.busy + before.idle + 1 >= before.uptime);
+    sleep(50);
+    let mut after = CpuStat::default();
+    assert_eq!(cpu_stat(&mut after), Ok(()));
+    assert!(after.uptime >= before.uptime + 50 * 1000);
+    assert!(after.idle >= before.idle);
+    assert!(after.busy >= before.busy);
+    println!(
+        "uptime {} us, busy {} us, idle {} us, {} ready",
+        after.uptime, after.busy, after.idle, after.nr_ready
+    );
+    println!("cpu_stat passed!");
+    0
+}
@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use user_lib::{
+    close, exec, exit, fork, getegid, geteuid, getgid, getpid, getuid, open, read_to_string,
+    setgid, setuid, waitpid, write, OpenFlags, EACCES, EPERM,
+};
+
+const ROOT_FILE: &str = "cred_file\0";
+const USER_FILE: &str = "cred_user\0";
+
+fn as_user() -> i32 {
+    assert_eq!(setgid(1000), Ok(()));
+    assert_eq!(setuid(1000), Ok(()));
+    assert_eq!((getuid(), geteuid()), (1000, 1000));
+    assert_eq!((getgid(), getegid()), (1000, 1000));
+    // 放弃的权限不能再恢复
+    assert_eq!(setuid(0), Err(EPERM));
+    assert_eq!(setgid(0), Err(EPERM));
+    assert_eq!(setuid(1000), Ok(()));
+
+    // 属于 root、权限为 0o644 的文件
+    assert_eq!(open(ROOT_FILE, OpenFlags::WRONLY), Err(EACCES));
+    assert_eq!(
+        open(ROOT_FILE, OpenFlags::CREATE | OpenFlags::WRONLY),
+        Err(EACCES)
+    );
+    let fd = open(ROOT_FILE, OpenFlags::READONLY).unwrap();
+    close(fd).unwrap();
+    assert!(read_to_string("passwd").is_none());
+
+    // 可以在根目录中创建文件，文件属于当前用户
+    let fd = open(USER_FILE, OpenFlags::CREATE | OpenFlags::WRONLY).unwrap();
+    close(fd).unwrap();
+
+    let status = read_to_string(format!("/proc/{}/status", getpid()).as_str()).unwrap();
+    assert!(status.lines().any(|line| line == "Uid:\t1000\t1000"));
+    0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!((getuid(), geteuid()), (0, 0));
+    let fd = open(ROOT_FILE, OpenFlags::CREATE | OpenFlags::WRONLY).unwrap();
+    write(fd, b"not an executable").unwrap();
+    close(fd).unwrap();
+    // 即使是 root 也需要有执行权限位
+    assert_eq!(exec(ROOT_FILE, &[core::ptr::null::<u8>()]), Err(EACCES));
+    assert!(read_to_string("passwd").is_some());
+
+    let pid = fork().unwrap();
+    if pid == 0 {
+        exit(as_user());
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, 0);
+    // 父进程的身份不受影响
+    assert_eq!(getuid(), 0);
+    println!("cred_test passed!");
+    0
+}
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    enable_deadlock_detect, mutex_blocking_create, mutex_lock, mutex_unlock, semaphore_create,
+    semaphore_down, semaphore_up, sleep, spawn, waittid, EDEADLK,
+};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(enable_deadlock_detect(true), Ok(()));
+
+    // 对只有一个资源的信号量连续两次 P 操作，第二次会永远阻塞
+    let sem = semaphore_create(1).unwrap();
+    assert_eq!(semaphore_down(sem), Ok(()));
+    assert_eq!(semaphore_down(sem), Err(EDEADLK));
+    assert_eq!(semaphore_up(sem), Ok(()));
+
+    // 主线程持有 a 申请 b，子线程持有 b 申请 a
+    let a = mutex_blocking_create().unwrap();
+    let b = mutex_blocking_create().unwrap();
+    assert_eq!(mutex_lock(a), Ok(()));
+    let tid = spawn(move || {
+        assert_eq!(mutex_lock(b), Ok(()));
+        // 主线程不需要其他资源，可以先结束，所以这里可以安全地阻塞
+        assert_eq!(mutex_lock(a), Ok(()));
+        mutex_unlock(a).unwrap();
+        mutex_unlock(b).unwrap();
+        0
+    })
+    .unwrap();
+    // 等子线程持有 b 并阻塞在 a 上
+    sleep(50);
+    assert_eq!(mutex_lock(b), Err(EDEADLK));
+    assert_eq!(mutex_unlock(a), Ok(()));
+    assert_eq!(waittid(tid), Ok(0));
+    // 子线程已经释放了 b
+    assert_eq!(mutex_lock(b), Ok(()));
+    assert_eq!(mutex_unlock(b), Ok(()));
+
+    assert_eq!(enable_deadlock_detect(false), Ok(()));
+    println!("deadlock_test passed!");
+    0
+}
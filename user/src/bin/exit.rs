@@ -10,7 +10,7 @@ const MAGIC: i32 = -0x10384;
 #[no_mangle]
 pub fn main() -> i32 {
     println!("I am the parent. Forking the child...");
-    let pid = fork();
+    let pid = fork().unwrap();
     if pid == 0 {
         println!("I am the child.");
         for _ in 0..7 {
@@ -22,8 +22,8 @@ pub fn main() -> i32 {
     }
     println!("I am the parent, waiting now..");
     let mut xstate: i32 = 0;
-    assert!(waitpid(pid as usize, &mut xstate) == pid && xstate == MAGIC);
-    assert!(waitpid(pid as usize, &mut xstate) < 0 && wait(&mut xstate) <= 0);
+    assert!(waitpid(pid, &mut xstate) == Ok(pid) && xstate == MAGIC);
+    assert!(waitpid(pid, &mut xstate).is_err() && wait(&mut xstate).is_err());
     println!("waitpid {} ok.", pid);
     println!("exit pass.");
     0
@@ -0,0 +1,70 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exec, exit, fork, mkfifo, open, read, sleep, waitpid, write, OpenFlags, EEXIST, ENXIO,
+};
+
+const FIFO: &str = "fifo_test_pipe\0";
+const MESSAGE: &[u8] = b"hello through fifo";
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 文件系统镜像中可能已经有上一次运行留下的命名管道
+    mkfifo(FIFO, 0o600).ok();
+    assert_eq!(mkfifo(FIFO, 0o600), Err(EEXIST));
+    // 命名管道不是可执行文件
+    assert_eq!(exec(FIFO, &[core::ptr::null::<u8>()]), Err(ENXIO));
+
+    // 非阻塞模式下没有读端时只写打开失败，只读打开立即成功并读到文件结尾
+    assert_eq!(
+        open(FIFO, OpenFlags::WRONLY | OpenFlags::NONBLOCK),
+        Err(ENXIO)
+    );
+    let fd = open(FIFO, OpenFlags::READONLY | OpenFlags::NONBLOCK).unwrap();
+    let mut buf = [0u8; MESSAGE.len()];
+    assert_eq!(read(fd, &mut buf), Ok(0));
+    close(fd).unwrap();
+
+    // 写者先打开，阻塞到读者打开之后才返回
+    let pid = fork().unwrap();
+    if pid == 0 {
+        let fd = open(FIFO, OpenFlags::WRONLY).unwrap();
+        assert_eq!(write(fd, MESSAGE), Ok(MESSAGE.len()));
+        close(fd).unwrap();
+        exit(0);
+    }
+    sleep(10);
+    let fd = open(FIFO, OpenFlags::READONLY).unwrap();
+    assert_eq!(read(fd, &mut buf), Ok(MESSAGE.len()));
+    assert_eq!(&buf, MESSAGE);
+    // 写者关闭后读到文件结尾
+    assert_eq!(read(fd, &mut buf), Ok(0));
+    close(fd).unwrap();
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, 0);
+
+    // 读者先打开，阻塞到写者打开之后才返回；以读写方式打开不会阻塞
+    let pid = fork().unwrap();
+    if pid == 0 {
+        let fd = open(FIFO, OpenFlags::READONLY).unwrap();
+        let mut buf = [0u8; MESSAGE.len()];
+        assert_eq!(read(fd, &mut buf), Ok(MESSAGE.len()));
+        assert_eq!(&buf, MESSAGE);
+        close(fd).unwrap();
+        exit(0);
+    }
+    sleep(10);
+    let fd = open(FIFO, OpenFlags::RDWR).unwrap();
+    assert_eq!(write(fd, MESSAGE), Ok(MESSAGE.len()));
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, 0);
+    close(fd).unwrap();
+
+    println!("fifo_test passed!");
+    0
+}
@@ -0,0 +1,83 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use user_lib::{
+    futex_wait, futex_wake, sleep, spawn, waittid, yield_, Condvar, Mutex, TimeSpec, EAGAIN,
+    ETIMEDOUT,
+};
+
+const THREAD_NUM: usize = 4;
+const PER_THREAD: usize = 200;
+
+static MUTEX: Mutex = Mutex::new();
+static mut COUNTER: usize = 0;
+static CONDVAR: Condvar = Condvar::new();
+static READY: AtomicBool = AtomicBool::new(false);
+
+fn ms(ms: usize) -> TimeSpec {
+    TimeSpec {
+        tv_sec: ms / 1000,
+        tv_nsec: ms % 1000 * 1_000_000,
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 值不相等时立即返回，超时后返回 ETIMEDOUT，没有等待者时唤醒 0 个线程
+    let word = AtomicU32::new(1);
+    assert_eq!(futex_wait(&word, 0, None), Err(EAGAIN));
+    assert_eq!(futex_wait(&word, 1, Some(&ms(10))), Err(ETIMEDOUT));
+    assert_eq!(futex_wake(&word, 1), Ok(0));
+
+    // 在临界区中让出处理器，制造竞争
+    let mut tids = Vec::new();
+    for _ in 0..THREAD_NUM {
+        tids.push(
+            spawn(|| {
+                for i in 0..PER_THREAD {
+                    MUTEX.lock();
+                    let value = unsafe { COUNTER };
+                    if i % 10 == 0 {
+                        yield_();
+                    }
+                    unsafe {
+                        COUNTER = value + 1;
+                    }
+                    MUTEX.unlock();
+                }
+                0
+            })
+            .unwrap(),
+        );
+    }
+    for tid in tids {
+        assert_eq!(waittid(tid), Ok(0));
+    }
+    assert_eq!(unsafe { COUNTER }, THREAD_NUM * PER_THREAD);
+
+    // 条件变量：等待另一个线程设置 READY
+    MUTEX.lock();
+    assert!(!CONDVAR.wait_timeout(&MUTEX, &ms(10)));
+    let tid = spawn(|| {
+        sleep(20);
+        MUTEX.lock();
+        READY.store(true, Ordering::Relaxed);
+        CONDVAR.notify_one();
+        MUTEX.unlock();
+        0
+    })
+    .unwrap();
+    while !READY.load(Ordering::Relaxed) {
+        CONDVAR.wait(&MUTEX);
+    }
+    MUTEX.unlock();
+    assert_eq!(waittid(tid), Ok(0));
+    println!("futex_test passed!");
+    0
+}
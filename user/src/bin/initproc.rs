@@ -7,13 +7,13 @@ use user_lib::{exec, fork, wait, yield_};
 
 #[no_mangle]
 fn main() -> i32 {
-    if fork() == 0 {
-        exec("user_shell\0", &[core::ptr::null::<u8>()]);
+    if fork() == Ok(0) {
+        exec("user_shell\0", &[core::ptr::null::<u8>()]).ok();
     } else {
         loop {
             let mut exit_code: i32 = 0;
             let pid = wait(&mut exit_code);
-            if pid == -1 {
+            if pid.is_err() {
                 yield_();
                 continue;
             }
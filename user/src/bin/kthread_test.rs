@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, get_time, open, proc_field, read_to_string, sleep, waitpid, write, OpenFlags,
+};
+
+const CHILDREN: usize = 8;
+/// 等待工作队列释放子进程地址空间的最长时间
+const TIMEOUT_MS: isize = 2000;
+
+/// 读取 /proc 文件中的一个数值，忽略 kB 单位
+fn value(path: &str, key: &str) -> usize {
+    let content = read_to_string(path).unwrap();
+    proc_field(&content, key)
+        .unwrap()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 写入只修改块缓存，写回线程会在一个周期后把脏块写回磁盘
+    let fd = open("kthread_test_file\0", OpenFlags::CREATE | OpenFlags::WRONLY).unwrap();
+    write(fd, b"written back by the writeback thread").unwrap();
+    close(fd).unwrap();
+    sleep(1000);
+    assert_eq!(value("/proc/blockcache", "Dirty"), 0);
+
+    // 子进程的地址空间由工作队列释放，回收后空闲页帧数应当恢复
+    let free_before = value("/proc/meminfo", "MemFree");
+    for _ in 0..CHILDREN {
+        let pid = fork().unwrap();
+        if pid == 0 {
+            exit(0);
+        }
+        let mut exit_code = 0;
+        assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    }
+    // 工作队列什么时候运行取决于调度，轮询直到页帧全部归还或者超时
+    let deadline = get_time() + TIMEOUT_MS;
+    while value("/proc/meminfo", "MemFree") != free_before {
+        assert!(get_time() < deadline, "子进程的地址空间没有被释放");
+        sleep(10);
+    }
+    println!("kthread_test passed!");
+    0
+}
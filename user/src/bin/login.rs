@@ -0,0 +1,95 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+const LF: u8 = 0x0au8;
+const CR: u8 = 0x0du8;
+const DL: u8 = 0x7fu8;
+const BS: u8 = 0x08u8;
+
+use alloc::string::String;
+use user_lib::console::getchar;
+use user_lib::{exec, read_to_string, setgid, setuid};
+
+/// 读入一行，echo 为 false 时不回显输入的字符
+fn read_line(prompt: &str, echo: bool) -> String {
+    print!("{}", prompt);
+    let mut line = String::new();
+    loop {
+        let c = getchar();
+        match c {
+            LF | CR => {
+                println!("");
+                return line;
+            }
+            BS | DL => {
+                if line.pop().is_some() && echo {
+                    print!("{}", BS as char);
+                    print!(" ");
+                    print!("{}", BS as char);
+                }
+            }
+            _ => {
+                if echo {
+                    print!("{}", c as char);
+                }
+                line.push(c as char);
+            }
+        }
+    }
+}
+
+/// 用法: login [用户名 [密码]]，缺少的部分从终端读入
+/// passwd 的每一行为 用户名:密码:uid:gid:登录后执行的程序
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    let passwd = match read_to_string("passwd") {
+        Some(passwd) => passwd,
+        None => {
+            println!("login: cannot read passwd");
+            return -1;
+        }
+    };
+    let name = if argc > 1 {
+        String::from(argv[1])
+    } else {
+        read_line("login: ", true)
+    };
+    let password = if argc > 2 {
+        String::from(argv[2])
+    } else {
+        read_line("password: ", false)
+    };
+    let entry = passwd
+        .lines()
+        .map(|line| line.split(':').collect::<alloc::vec::Vec<_>>())
+        .find(|fields| fields.len() == 5 && fields[0] == name);
+    let fields = match entry {
+        Some(fields) if fields[1] == password => fields,
+        _ => {
+            println!("login: incorrect login");
+            return -1;
+        }
+    };
+    let (uid, gid) = match (fields[2].parse(), fields[3].parse()) {
+        (Ok(uid), Ok(gid)) => (uid, gid),
+        _ => {
+            println!("login: bad passwd entry for {}", name);
+            return -1;
+        }
+    };
+    // 先设置组 id，放弃 root 权限之后就不能再修改了
+    if let Err(err) = setgid(gid).and_then(|_| setuid(uid)) {
+        println!("login: cannot switch to {}: {}", name, err);
+        return -1;
+    }
+    let mut shell = String::from(fields[4]);
+    shell.push('\0');
+    if let Err(err) = exec(shell.as_str(), &[shell.as_ptr(), core::ptr::null::<u8>()]) {
+        println!("login: cannot execute {}: {}", fields[4], err);
+    }
+    -1
+}
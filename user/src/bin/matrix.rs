@@ -47,7 +47,7 @@ fn work(times: isize) {
 #[no_mangle]
 pub fn main() -> i32 {
     for _ in 0..NUM {
-        let pid = fork();
+        let pid = fork().unwrap();
         if pid == 0 {
             let current_time = get_time();
             let times = (current_time as i32 as isize) * (current_time as i32 as isize) % 1000;
@@ -59,11 +59,11 @@ pub fn main() -> i32 {
 
     let mut exit_code: i32 = 0;
     for _ in 0..NUM {
-        if wait(&mut exit_code) < 0 {
+        if wait(&mut exit_code).is_err() {
             panic!("wait failed.");
         }
     }
-    assert!(wait(&mut exit_code) < 0);
+    assert!(wait(&mut exit_code).is_err());
     println!("matrix passed.");
     0
 }
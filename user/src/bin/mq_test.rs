@@ -0,0 +1,106 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, mq_getattr, mq_open, mq_receive, mq_send, mq_timedreceive, mq_unlink, sleep,
+    waitpid, MqAttr, OpenFlags, TimeSpec, EAGAIN, EINVAL, EMSGSIZE, ENOENT, ETIMEDOUT,
+};
+
+const NAME: &str = "/mq_test\0";
+const MAXMSG: usize = 4;
+const MSGSIZE: usize = 64;
+const ROUNDS: usize = 8;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 名字必须以 '/' 开头，不存在的队列不能在没有 CREATE 时打开
+    assert_eq!(
+        mq_open("mq_test\0", OpenFlags::RDWR | OpenFlags::CREATE, None),
+        Err(EINVAL)
+    );
+    assert_eq!(mq_open(NAME, OpenFlags::RDWR, None), Err(ENOENT));
+    let attr = MqAttr {
+        maxmsg: MAXMSG,
+        msgsize: MSGSIZE,
+        ..Default::default()
+    };
+    let fd = mq_open(NAME, OpenFlags::RDWR | OpenFlags::CREATE, Some(&attr)).unwrap();
+    let nonblock_fd = mq_open(NAME, OpenFlags::RDWR | OpenFlags::NONBLOCK, None).unwrap();
+
+    // 优先级高的消息先被接收，优先级相同时按发送顺序
+    let mut buf = [0u8; MSGSIZE];
+    let mut prio = 0u32;
+    for (msg, prio) in [("low", 1), ("high", 5), ("mid", 3), ("low2", 1)] {
+        assert_eq!(mq_send(fd, msg.as_bytes(), prio), Ok(()));
+    }
+    for (msg, expected_prio) in [("high", 5), ("mid", 3), ("low", 1), ("low2", 1)] {
+        let len = mq_receive(fd, &mut buf, Some(&mut prio)).unwrap();
+        assert_eq!(&buf[..len], msg.as_bytes());
+        assert_eq!(prio, expected_prio);
+    }
+
+    // 容量有限，非阻塞描述符在队列满或者空时立即返回
+    for i in 0..MAXMSG {
+        assert_eq!(mq_send(nonblock_fd, &[i as u8], 0), Ok(()));
+    }
+    assert_eq!(mq_send(nonblock_fd, b"full", 0), Err(EAGAIN));
+    let mut attr = MqAttr::default();
+    assert_eq!(mq_getattr(nonblock_fd, &mut attr), Ok(()));
+    assert_eq!(
+        (attr.maxmsg, attr.msgsize, attr.curmsgs),
+        (MAXMSG, MSGSIZE, MAXMSG)
+    );
+    for i in 0..MAXMSG {
+        assert_eq!(mq_receive(nonblock_fd, &mut buf, None), Ok(1));
+        assert_eq!(buf[0], i as u8);
+    }
+    assert_eq!(mq_receive(nonblock_fd, &mut buf, None), Err(EAGAIN));
+
+    // 超时、缓冲区过短和消息过长
+    let timeout = TimeSpec {
+        tv_sec: 0,
+        tv_nsec: 10_000_000,
+    };
+    assert_eq!(
+        mq_timedreceive(fd, &mut buf, None, &timeout),
+        Err(ETIMEDOUT)
+    );
+    assert_eq!(mq_receive(fd, &mut buf[..MSGSIZE - 1], None), Err(EMSGSIZE));
+    assert_eq!(mq_send(fd, &[0u8; MSGSIZE + 1], 0), Err(EMSGSIZE));
+
+    // 子进程继承描述符，发送的消息多于容量时阻塞，直到父进程接收
+    let pid = fork().unwrap();
+    if pid == 0 {
+        for i in 0..ROUNDS {
+            let msg = [i as u8; 3];
+            assert_eq!(mq_send(fd, &msg[..i % 3 + 1], 0), Ok(()));
+        }
+        exit(0);
+    }
+    sleep(10);
+    assert_eq!(mq_getattr(fd, &mut attr), Ok(()));
+    assert_eq!(attr.curmsgs, MAXMSG);
+    for i in 0..ROUNDS {
+        // 每条消息保持自己的边界
+        let len = mq_receive(fd, &mut buf, None).unwrap();
+        assert_eq!(len, i % 3 + 1);
+        assert!(buf[..len].iter().all(|&byte| byte == i as u8));
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, 0);
+
+    // 删除名字后不能再打开，已经打开的描述符仍然可用
+    assert_eq!(mq_unlink(NAME), Ok(()));
+    assert_eq!(mq_unlink(NAME), Err(ENOENT));
+    assert_eq!(mq_open(NAME, OpenFlags::RDWR, None), Err(ENOENT));
+    assert_eq!(mq_send(fd, b"still", 0), Ok(()));
+    assert_eq!(mq_receive(nonblock_fd, &mut buf, None), Ok(5));
+    close(fd).unwrap();
+    close(nonblock_fd).unwrap();
+    println!("mq_test passed!");
+    0
+}
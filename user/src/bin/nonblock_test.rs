@@ -0,0 +1,172 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fcntl, fork, pipe, pipe2, read, set_nonblock, waitpid, write, yield_, OpenFlags,
+    EAGAIN, EBADF, EINVAL, F_GETFL, F_SETFL,
+};
+
+/// 流水线中传递的字节数，远大于管道的容量
+const TOTAL: usize = 4096;
+const CHUNK: usize = 100;
+
+fn byte_at(i: usize) -> u8 {
+    (i % 251) as u8
+}
+
+/// 把 buf 全部写入非阻塞的 fd，管道满时让出 CPU 后重试
+fn write_all(fd: usize, buf: &[u8]) {
+    let mut written = 0;
+    while written < buf.len() {
+        match write(fd, &buf[written..]) {
+            Err(EAGAIN) => {
+                yield_();
+            }
+            ret => {
+                let len = ret.unwrap();
+                assert!(len > 0);
+                written += len;
+            }
+        }
+    }
+}
+
+/// 从非阻塞的 fd 读取，没有数据时让出 CPU 后重试，读到文件结尾返回 0
+fn read_some(fd: usize, buf: &mut [u8]) -> usize {
+    loop {
+        match read(fd, buf) {
+            Err(EAGAIN) => {
+                yield_();
+            }
+            ret => return ret.unwrap(),
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut buf = [0u8; CHUNK];
+
+    // 非阻塞管道：空管道读返回 EAGAIN，写满后返回已经写入的字节数，再写返回 EAGAIN
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe2(&mut fds, OpenFlags::NONBLOCK), Ok(()));
+    assert_eq!(
+        fcntl(fds[0], F_GETFL, 0),
+        Ok((OpenFlags::READONLY | OpenFlags::NONBLOCK).bits() as usize)
+    );
+    assert_eq!(
+        fcntl(fds[1], F_GETFL, 0),
+        Ok((OpenFlags::WRONLY | OpenFlags::NONBLOCK).bits() as usize)
+    );
+    assert_eq!(read(fds[0], &mut buf), Err(EAGAIN));
+    let mut capacity = 0;
+    loop {
+        let len = match write(fds[1], &[7u8; CHUNK]) {
+            Err(EAGAIN) => break,
+            ret => ret.unwrap(),
+        };
+        assert!(len > 0 && len <= CHUNK);
+        capacity += len;
+    }
+    assert!(capacity > 0);
+    let mut drained = 0;
+    loop {
+        let len = match read(fds[0], &mut buf) {
+            Err(EAGAIN) => break,
+            ret => ret.unwrap(),
+        };
+        assert!(len > 0);
+        assert!(buf[..len].iter().all(|&byte| byte == 7));
+        drained += len;
+    }
+    assert_eq!(drained, capacity);
+    // 写端关闭后读到文件结尾而不是 EAGAIN
+    close(fds[1]).unwrap();
+    assert_eq!(read(fds[0], &mut buf), Ok(0));
+    close(fds[0]).unwrap();
+
+    // fcntl 可以切换普通管道的模式，只有 NONBLOCK 会被修改
+    assert_eq!(pipe(&mut fds), Ok(()));
+    assert_eq!(
+        fcntl(fds[0], F_GETFL, 0),
+        Ok(OpenFlags::READONLY.bits() as usize)
+    );
+    assert_eq!(
+        fcntl(
+            fds[0],
+            F_SETFL,
+            (OpenFlags::NONBLOCK | OpenFlags::RDWR).bits() as usize
+        ),
+        Ok(0)
+    );
+    assert_eq!(read(fds[0], &mut buf), Err(EAGAIN));
+    assert_eq!(set_nonblock(fds[0], false), Ok(()));
+    assert_eq!(
+        fcntl(fds[0], F_GETFL, 0),
+        Ok(OpenFlags::READONLY.bits() as usize)
+    );
+    close(fds[0]).unwrap();
+    close(fds[1]).unwrap();
+    assert_eq!(pipe2(&mut fds, OpenFlags::CREATE), Err(EINVAL));
+    assert_eq!(fcntl(100, F_GETFL, 0), Err(EBADF));
+    assert_eq!(fcntl(0, 100, 0), Err(EINVAL));
+
+    // 三级流水线：生产者 -> 转发者 -> 消费者（父进程），每一级都使用非阻塞读写
+    let mut first = [0usize; 2];
+    let mut second = [0usize; 2];
+    assert_eq!(pipe2(&mut first, OpenFlags::NONBLOCK), Ok(()));
+    assert_eq!(pipe2(&mut second, OpenFlags::NONBLOCK), Ok(()));
+    let producer = fork().unwrap();
+    if producer == 0 {
+        close(first[0]).unwrap();
+        close(second[0]).unwrap();
+        close(second[1]).unwrap();
+        let mut sent = 0;
+        while sent < TOTAL {
+            let len = CHUNK.min(TOTAL - sent);
+            let chunk: [u8; CHUNK] = core::array::from_fn(|i| byte_at(sent + i));
+            write_all(first[1], &chunk[..len]);
+            sent += len;
+        }
+        exit(0);
+    }
+    let relay = fork().unwrap();
+    if relay == 0 {
+        close(first[1]).unwrap();
+        close(second[0]).unwrap();
+        loop {
+            let len = read_some(first[0], &mut buf);
+            if len == 0 {
+                break;
+            }
+            write_all(second[1], &buf[..len]);
+        }
+        exit(0);
+    }
+    close(first[0]).unwrap();
+    close(first[1]).unwrap();
+    close(second[1]).unwrap();
+    let mut received = 0;
+    loop {
+        let len = read_some(second[0], &mut buf);
+        if len == 0 {
+            break;
+        }
+        for (i, &byte) in buf[..len].iter().enumerate() {
+            assert_eq!(byte, byte_at(received + i));
+        }
+        received += len;
+    }
+    assert_eq!(received, TOTAL);
+    close(second[0]).unwrap();
+    let mut exit_code = 0;
+    for pid in [producer, relay] {
+        assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+        assert_eq!(exit_code, 0);
+    }
+    println!("nonblock_test passed!");
+    0
+}
@@ -0,0 +1,115 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use user_lib::{
+    exit, fork, get_time, mutex_blocking_create, mutex_lock, mutex_unlock, set_priority, setuid,
+    sleep, spawn, waitpid, waittid, yield_, EINVAL, EPERM,
+};
+
+/// 记录高、中优先级线程完成的先后顺序
+static SEQ: AtomicUsize = AtomicUsize::new(0);
+static HIGH_SEQ: AtomicUsize = AtomicUsize::new(0);
+static MIDDLE_SEQ: AtomicUsize = AtomicUsize::new(0);
+static LOW_RAN: AtomicBool = AtomicBool::new(false);
+/// 记录三个等待者得到锁的先后顺序
+static HANDOFF_SEQ: [AtomicUsize; 3] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+
+fn busy_loop(ms: isize) {
+    let start = get_time();
+    while get_time() - start < ms {}
+}
+
+/// 经典的优先级反转：低优先级线程持有锁，高优先级线程等待这把锁，
+/// 中优先级线程一直占用处理器。有优先级继承时，低优先级线程被提升，
+/// 尽快释放锁，高优先级线程先于中优先级线程完成
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(set_priority(1), Err(EINVAL));
+    assert_eq!(set_priority(33), Err(EINVAL));
+    // 普通用户不能把优先级提高到默认优先级之上
+    let pid = fork().unwrap();
+    if pid == 0 {
+        setuid(1000).unwrap();
+        assert_eq!(set_priority(17), Err(EPERM));
+        assert_eq!(set_priority(16), Ok(16));
+        assert_eq!(set_priority(8), Ok(8));
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, 0);
+    // 主线程的优先级最高，保证它能按时创建其他线程
+    assert_eq!(set_priority(20), Ok(20));
+    // 主动让出处理器时，优先级更低的就绪线程也能得到运行
+    let low = spawn(|| {
+        LOW_RAN.store(true, Ordering::Relaxed);
+        0
+    })
+    .unwrap();
+    while !LOW_RAN.load(Ordering::Relaxed) {
+        yield_();
+    }
+    assert_eq!(waittid(low), Ok(0));
+    let mutex = mutex_blocking_create().unwrap();
+    let low = spawn(move || {
+        mutex_lock(mutex).unwrap();
+        busy_loop(100);
+        mutex_unlock(mutex).unwrap();
+        0
+    })
+    .unwrap();
+    sleep(10);
+    let high = spawn(move || {
+        set_priority(18).unwrap();
+        mutex_lock(mutex).unwrap();
+        HIGH_SEQ.store(SEQ.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        mutex_unlock(mutex).unwrap();
+        0
+    })
+    .unwrap();
+    sleep(10);
+    let middle = spawn(|| {
+        set_priority(17).unwrap();
+        busy_loop(300);
+        MIDDLE_SEQ.store(SEQ.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        0
+    })
+    .unwrap();
+    for tid in [low, high, middle] {
+        assert_eq!(waittid(tid), Ok(0));
+    }
+    assert!(HIGH_SEQ.load(Ordering::Relaxed) < MIDDLE_SEQ.load(Ordering::Relaxed));
+
+    // 释放锁时交给优先级最高的等待者，优先级相同的等待者按到达顺序得到锁
+    assert_eq!(mutex_lock(mutex), Ok(()));
+    let mut waiters = [0; 3];
+    for (i, priority) in [16, 16, 18].into_iter().enumerate() {
+        waiters[i] = spawn(move || {
+            set_priority(priority).unwrap();
+            mutex_lock(mutex).unwrap();
+            HANDOFF_SEQ[i].store(SEQ.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+            mutex_unlock(mutex).unwrap();
+            0
+        })
+        .unwrap();
+        sleep(10);
+    }
+    assert_eq!(mutex_unlock(mutex), Ok(()));
+    for tid in waiters {
+        assert_eq!(waittid(tid), Ok(0));
+    }
+    let handoff = HANDOFF_SEQ
+        .each_ref()
+        .map(|seq| seq.load(Ordering::Relaxed));
+    assert!(handoff[2] < handoff[0] && handoff[0] < handoff[1]);
+    println!("pi_test passed!");
+    0
+}
@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, pipe, poll, read, sleep, waitpid, write, PollEvents, PollFd};
+
+/// SIGPIPE 终止进程时的退出码
+const SIGPIPE_EXIT: i32 = -13;
+/// 比管道的容量大，写者一定会阻塞
+const LARGE: usize = 16 * 1024;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut exit_code = 0;
+
+    // 读端全部关闭后，poll 写端返回 ERR，写入的进程被 SIGPIPE 终止
+    let mut fds = [0usize; 2];
+    pipe(&mut fds).unwrap();
+    close(fds[0]).unwrap();
+    let mut pollfds = [PollFd::new(fds[1], PollEvents::OUT)];
+    assert_eq!(poll(&mut pollfds, 0), Ok(1));
+    assert_eq!(pollfds[0].revents(), PollEvents::ERR);
+    let pid = fork().unwrap();
+    if pid == 0 {
+        write(fds[1], b"nobody listens").ok();
+        exit(0);
+    }
+    close(fds[1]).unwrap();
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, SIGPIPE_EXIT);
+
+    // 阻塞在满管道上的写者在读端关闭后被唤醒：已经写入的部分照常返回，之后的写入触发 SIGPIPE
+    pipe(&mut fds).unwrap();
+    let pid = fork().unwrap();
+    if pid == 0 {
+        close(fds[0]).unwrap();
+        static DATA: [u8; LARGE] = [1u8; LARGE];
+        let written = write(fds[1], &DATA).unwrap();
+        assert!(written > 0 && written < LARGE);
+        write(fds[1], b"x").ok();
+        exit(0);
+    }
+    close(fds[1]).unwrap();
+    sleep(10);
+    let mut buf = [0u8; 64];
+    assert_eq!(read(fds[0], &mut buf), Ok(buf.len()));
+    assert!(buf.iter().all(|&byte| byte == 1));
+    close(fds[0]).unwrap();
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, SIGPIPE_EXIT);
+
+    // 阻塞的读者在所有写端关闭后读到文件结尾
+    pipe(&mut fds).unwrap();
+    let pid = fork().unwrap();
+    if pid == 0 {
+        close(fds[0]).unwrap();
+        sleep(10);
+        write(fds[1], b"tail").unwrap();
+        exit(0);
+    }
+    close(fds[1]).unwrap();
+    assert_eq!(read(fds[0], &mut buf), Ok(4));
+    assert_eq!(&buf[..4], b"tail");
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, 0);
+    close(fds[0]).unwrap();
+
+    println!("pipe_epipe_test passed!");
+    0
+}
@@ -0,0 +1,105 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, get_time, open, pipe, poll, read, sleep, socketpair, waitpid, write,
+    OpenFlags, PollEvents, PollFd, AF_UNIX, SOCK_STREAM,
+};
+
+const TIMEOUT_MS: isize = 20;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut buf = [0u8; 16];
+    let mut a = [0usize; 2];
+    let mut b = [0usize; 2];
+    pipe(&mut a).unwrap();
+    pipe(&mut b).unwrap();
+
+    // 空管道不可读，写端可写；timeout 为 0 时立即返回
+    let mut fds = [PollFd::new(a[0], PollEvents::IN)];
+    assert_eq!(poll(&mut fds, 0), Ok(0));
+    assert!(fds[0].revents().is_empty());
+    let mut fds = [PollFd::new(a[1], PollEvents::OUT)];
+    assert_eq!(poll(&mut fds, 0), Ok(1));
+    assert_eq!(fds[0].revents(), PollEvents::OUT);
+
+    // 超时返回 0
+    let mut fds = [PollFd::new(a[0], PollEvents::IN)];
+    let start = get_time();
+    assert_eq!(poll(&mut fds, TIMEOUT_MS), Ok(0));
+    assert!(get_time() - start >= TIMEOUT_MS);
+
+    // 同时等待两个管道，只有被写入的那个就绪
+    let pid = fork().unwrap();
+    if pid == 0 {
+        sleep(TIMEOUT_MS as usize);
+        assert_eq!(write(b[1], b"b"), Ok(1));
+        exit(0);
+    }
+    close(a[1]).unwrap();
+    close(b[1]).unwrap();
+    let mut fds = [
+        PollFd::new(a[0], PollEvents::IN),
+        PollFd::new(b[0], PollEvents::IN),
+    ];
+    assert_eq!(poll(&mut fds, -1), Ok(1));
+    assert!(fds[0].revents().is_empty());
+    assert!(fds[1].revents().contains(PollEvents::IN));
+    assert_eq!(read(b[0], &mut buf[..1]), Ok(1));
+
+    // 写端全部关闭后返回 HUP，即使没有关心这个事件
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    let mut fds = [PollFd::new(a[0], PollEvents::empty())];
+    assert_eq!(poll(&mut fds, -1), Ok(1));
+    assert_eq!(fds[0].revents(), PollEvents::HUP);
+    close(a[0]).unwrap();
+    close(b[0]).unwrap();
+
+    // 不存在的描述符返回 NVAL，负数被忽略
+    let mut fds = [
+        PollFd::new(100, PollEvents::IN),
+        PollFd {
+            fd: -1,
+            events: PollEvents::IN.bits(),
+            revents: 0,
+        },
+    ];
+    assert_eq!(poll(&mut fds, 0), Ok(1));
+    assert_eq!(fds[0].revents(), PollEvents::NVAL);
+    assert!(fds[1].revents().is_empty());
+
+    // 普通文件总是就绪
+    let fd = open("poll_test_file\0", OpenFlags::CREATE | OpenFlags::RDWR).unwrap();
+    let mut fds = [PollFd::new(fd, PollEvents::IN | PollEvents::OUT)];
+    assert_eq!(poll(&mut fds, 0), Ok(1));
+    assert_eq!(fds[0].revents(), PollEvents::IN | PollEvents::OUT);
+    close(fd).unwrap();
+
+    // 套接字：阻塞等待对端写入，对端关闭后返回 HUP
+    let mut sv = [0usize; 2];
+    assert_eq!(socketpair(AF_UNIX, SOCK_STREAM, &mut sv), Ok(()));
+    let mut fds = [PollFd::new(sv[1], PollEvents::IN)];
+    assert_eq!(poll(&mut fds, 0), Ok(0));
+    let pid = fork().unwrap();
+    if pid == 0 {
+        sleep(TIMEOUT_MS as usize);
+        assert_eq!(write(sv[0], b"sock"), Ok(4));
+        exit(0);
+    }
+    close(sv[0]).unwrap();
+    assert_eq!(poll(&mut fds, -1), Ok(1));
+    assert!(fds[0].revents().contains(PollEvents::IN));
+    assert_eq!(read(sv[1], &mut buf), Ok(4));
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(poll(&mut fds, -1), Ok(1));
+    assert!(fds[0].revents().contains(PollEvents::HUP));
+    close(sv[1]).unwrap();
+
+    println!("poll_test passed!");
+    0
+}
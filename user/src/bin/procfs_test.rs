@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use user_lib::{
+    exit, fork, getpid, open, proc_field, read_to_string, sleep, waitpid, OpenFlags, EACCES,
+};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    let root = read_to_string("/proc").unwrap();
+    assert!(root.lines().any(|line| line == format!("{}", pid)));
+    assert!(root.lines().any(|line| line == "meminfo"));
+
+    let status = read_to_string(format!("/proc/{}/status", pid).as_str()).unwrap();
+    assert_eq!(proc_field(&status, "Name").unwrap(), "procfs_test");
+    assert_eq!(proc_field(&status, "Pid").unwrap(), format!("{}", pid));
+    assert_eq!(proc_field(&status, "State").unwrap(), "R");
+    assert_eq!(proc_field(&status, "Threads").unwrap(), "1");
+
+    let threads = read_to_string(format!("/proc/{}/threads", pid).as_str()).unwrap();
+    assert!(threads.lines().nth(1).unwrap().starts_with("0\tR"));
+    let fds = read_to_string(format!("/proc/{}/fd", pid).as_str()).unwrap();
+    assert!(fds.starts_with("0\tr-\n1\t-w\n2\t-w\n"));
+    let maps = read_to_string(format!("/proc/{}/maps", pid).as_str()).unwrap();
+    assert!(maps.lines().any(|line| line.ends_with("r-xu")));
+
+    let meminfo = read_to_string("/proc/meminfo").unwrap();
+    assert!(proc_field(&meminfo, "MemFree").unwrap().ends_with("kB"));
+    assert!(read_to_string("/proc/uptime").is_some());
+    assert!(read_to_string("/proc/blockcache").is_some());
+    assert!(read_to_string("/proc/sched").is_some());
+
+    // procfs 是只读的，并且只包含仍然存在的进程
+    assert_eq!(open("/proc/meminfo\0", OpenFlags::WRONLY), Err(EACCES));
+    assert!(read_to_string("/proc/99999/status").is_none());
+    assert!(read_to_string("/proc/nothing").is_none());
+
+    // 已退出的子进程在被回收之前显示为僵尸进程
+    let child = fork().unwrap();
+    if child == 0 {
+        exit(7);
+    }
+    sleep(50);
+    let path = format!("/proc/{}/status", child);
+    let status = read_to_string(path.as_str()).unwrap();
+    assert_eq!(proc_field(&status, "State").unwrap(), "Z");
+    assert_eq!(proc_field(&status, "ExitCode").unwrap(), "7");
+    assert_eq!(proc_field(&status, "PPid").unwrap(), format!("{}", pid));
+    let mut exit_code = 0;
+    assert_eq!(waitpid(child, &mut exit_code), Ok(child));
+    assert!(read_to_string(path.as_str()).is_none());
+    println!("procfs_test passed!");
+    0
+}
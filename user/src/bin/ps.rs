@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use user_lib::{proc_field, read_to_string};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let root = match read_to_string("/proc") {
+        Some(root) => root,
+        None => {
+            println!("ps: cannot read /proc");
+            return -1;
+        }
+    };
+    println!("PID\tPPID\tS\tTHR\tTIME\tCMD");
+    for pid in root.lines().filter(|line| line.parse::<usize>().is_ok()) {
+        // 进程可能在读取目录之后退出并被回收
+        let status = match read_to_string(format!("/proc/{}/status", pid).as_str()) {
+            Some(status) => status,
+            None => continue,
+        };
+        let field = |key: &str| proc_field(&status, key).unwrap_or("?");
+        let ms = |key: &str| {
+            field(key)
+                .trim_end_matches(" ms")
+                .parse::<usize>()
+                .unwrap_or(0)
+        };
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            pid,
+            field("PPid"),
+            field("State"),
+            field("Threads"),
+            ms("Utime") + ms("Stime"),
+            field("Name")
+        );
+    }
+    0
+}
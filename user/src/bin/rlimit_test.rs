@@ -0,0 +1,104 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, dup, exit, fork, get_time, getrlimit, mq_open, mq_unlink, pipe, setrlimit,
+    thread_create, waitpid, waittid, OpenFlags, RLimit, EAGAIN, EINVAL, EMFILE, ENOENT, EPERM,
+    RLIMIT_CPU, RLIMIT_NOFILE, RLIMIT_NPROC, RLIMIT_NTHREAD, RLIMIT_STACK, RLIM_INFINITY,
+};
+
+fn set_cur(resource: usize, cur: usize) {
+    let mut rlim = RLimit::default();
+    assert_eq!(getrlimit(resource, &mut rlim), Ok(()));
+    rlim.rlim_cur = cur;
+    assert_eq!(setrlimit(resource, &rlim), Ok(()));
+}
+
+fn thread_main() {
+    exit(0);
+}
+
+fn test_nofile() {
+    set_cur(RLIMIT_NOFILE, 5);
+    // 文件描述符 0/1/2 已被占用，只能再创建一个管道
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), Ok(()));
+    assert_eq!(dup(fds[0]), Err(EMFILE));
+    assert_eq!(pipe(&mut [0usize; 2]), Err(EMFILE));
+    // 没有空闲的描述符时不会创建消息队列
+    assert_eq!(
+        mq_open("/rlimit_test\0", OpenFlags::RDWR | OpenFlags::CREATE, None),
+        Err(EMFILE)
+    );
+    assert_eq!(mq_unlink("/rlimit_test\0"), Err(ENOENT));
+    close(fds[1]).unwrap();
+    assert_eq!(dup(fds[0]), Ok(fds[1]));
+    close(fds[0]).unwrap();
+    close(fds[1]).unwrap();
+}
+
+fn test_nthread() {
+    set_cur(RLIMIT_NTHREAD, 2);
+    let tid = thread_create(thread_main as usize, 0).unwrap();
+    assert_eq!(thread_create(thread_main as usize, 0), Err(EAGAIN));
+    assert_eq!(waittid(tid), Ok(0));
+    // 退出的线程已经被回收，又可以创建新线程
+    let tid = thread_create(thread_main as usize, 0).unwrap();
+    assert_eq!(waittid(tid), Ok(0));
+}
+
+fn test_nproc() {
+    set_cur(RLIMIT_NPROC, 1);
+    let pid = fork().unwrap();
+    if pid == 0 {
+        // 资源限制被子进程继承
+        let mut rlim = RLimit::default();
+        getrlimit(RLIMIT_NPROC, &mut rlim).unwrap();
+        exit(rlim.rlim_cur as i32);
+    }
+    assert_eq!(fork(), Err(EAGAIN));
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, 1);
+}
+
+fn test_cpu() {
+    let pid = fork().unwrap();
+    if pid == 0 {
+        set_cur(RLIMIT_CPU, 1);
+        let start = get_time();
+        while get_time() - start < 5000 {}
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    // 被 SIGXCPU 终止
+    assert_eq!(exit_code, -24);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut rlim = RLimit::default();
+    assert_eq!(getrlimit(RLIMIT_CPU, &mut rlim), Ok(()));
+    assert_eq!(rlim.rlim_cur, RLIM_INFINITY);
+    assert_eq!(getrlimit(RLIMIT_STACK, &mut rlim), Ok(()));
+    // 硬限制可以降低，但降低后不能再提高
+    rlim.rlim_max -= 4096;
+    rlim.rlim_cur = rlim.rlim_max;
+    assert_eq!(setrlimit(RLIMIT_STACK, &rlim), Ok(()));
+    rlim.rlim_max += 4096;
+    assert_eq!(setrlimit(RLIMIT_STACK, &rlim), Err(EPERM));
+    rlim.rlim_cur = rlim.rlim_max + 1;
+    assert_eq!(setrlimit(RLIMIT_STACK, &rlim), Err(EINVAL));
+    assert_eq!(getrlimit(100, &mut rlim), Err(EINVAL));
+
+    test_cpu();
+    test_nofile();
+    test_nthread();
+    test_nproc();
+    println!("rlimit_test passed!");
+    0
+}
@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, getrusage, times, waitpid, yield_, RUsage, Tms, EINVAL, RUSAGE_CHILDREN,
+    RUSAGE_SELF, RUSAGE_THREAD,
+};
+
+fn usec(rusage: &RUsage) -> usize {
+    (rusage.ru_utime.tv_sec + rusage.ru_stime.tv_sec) * 1000000
+        + rusage.ru_utime.tv_usec
+        + rusage.ru_stime.tv_usec
+}
+
+fn spin(ms: isize) {
+    let start = get_time();
+    while get_time() - start < ms {}
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut rusage = RUsage::default();
+    assert_eq!(getrusage(RUSAGE_CHILDREN, &mut rusage), Ok(()));
+    assert_eq!(usec(&rusage), 0);
+
+    // 主动让出处理器的次数被统计
+    assert_eq!(getrusage(RUSAGE_THREAD, &mut rusage), Ok(()));
+    let nvcsw = rusage.ru_nvcsw;
+    for _ in 0..10 {
+        yield_();
+    }
+    assert_eq!(getrusage(RUSAGE_THREAD, &mut rusage), Ok(()));
+    assert!(rusage.ru_nvcsw >= nvcsw + 10);
+
+    // 进程自身的 CPU 时间
+    spin(20);
+    assert_eq!(getrusage(RUSAGE_SELF, &mut rusage), Ok(()));
+    assert!(usec(&rusage) > 0);
+    assert_eq!(getrusage(2, &mut rusage), Err(EINVAL));
+    println!("rusage_test: RUSAGE_SELF ok");
+
+    // 子进程被等待回收之后才计入
+    let pid = fork().unwrap();
+    if pid == 0 {
+        spin(50);
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(getrusage(RUSAGE_CHILDREN, &mut rusage), Ok(()));
+    assert!(usec(&rusage) > 0);
+    let mut tms = Tms::default();
+    assert!(times(&mut tms).unwrap() > 0);
+
+    // 子进程的缺页次数计入 RUSAGE_CHILDREN
+    let minflt = rusage.ru_minflt;
+    let pid = fork().unwrap();
+    if pid == 0 {
+        unsafe {
+            core::ptr::null_mut::<u8>().write_volatile(0);
+        }
+        exit(0);
+    }
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, -11);
+    assert_eq!(getrusage(RUSAGE_CHILDREN, &mut rusage), Ok(()));
+    assert_eq!(rusage.ru_minflt, minflt + 1);
+    println!("rusage_test passed!");
+    0
+}
@@ -0,0 +1,81 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use user_lib::{
+    rwlock_create, rwlock_read, rwlock_unlock, rwlock_write, sleep, spawn, waittid, EPERM,
+};
+
+static READ_SHARED: AtomicBool = AtomicBool::new(false);
+static SEQ: AtomicUsize = AtomicUsize::new(1);
+static WRITER_SEQ: AtomicUsize = AtomicUsize::new(0);
+static READER_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let rwlock = rwlock_create().unwrap();
+    assert_eq!(rwlock_unlock(rwlock), Err(EPERM));
+
+    // 多个读者可以同时持有读锁
+    assert_eq!(rwlock_read(rwlock), Ok(()));
+    let reader = spawn(move || {
+        rwlock_read(rwlock).unwrap();
+        READ_SHARED.store(true, Ordering::Relaxed);
+        rwlock_unlock(rwlock).unwrap();
+        0
+    })
+    .unwrap();
+    assert_eq!(waittid(reader), Ok(0));
+    assert!(READ_SHARED.load(Ordering::Relaxed));
+    // 其他线程不能释放当前线程持有的读锁
+    let other = spawn(move || rwlock_unlock(rwlock).unwrap_err().0).unwrap();
+    assert_eq!(waittid(other), Ok(EPERM.0));
+
+    // 写者等待读锁释放，之后到来的读者排在写者后面
+    let writer = spawn(move || {
+        rwlock_write(rwlock).unwrap();
+        WRITER_SEQ.store(SEQ.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        rwlock_unlock(rwlock).unwrap();
+        0
+    })
+    .unwrap();
+    sleep(20);
+    let reader = spawn(move || {
+        rwlock_read(rwlock).unwrap();
+        READER_SEQ.store(SEQ.fetch_add(1, Ordering::Relaxed), Ordering::Relaxed);
+        rwlock_unlock(rwlock).unwrap();
+        0
+    })
+    .unwrap();
+    sleep(20);
+    assert_eq!(WRITER_SEQ.load(Ordering::Relaxed), 0);
+    assert_eq!(READER_SEQ.load(Ordering::Relaxed), 0);
+    assert_eq!(rwlock_unlock(rwlock), Ok(()));
+    assert_eq!(waittid(writer), Ok(0));
+    assert_eq!(waittid(reader), Ok(0));
+    assert_eq!(WRITER_SEQ.load(Ordering::Relaxed), 1);
+    assert_eq!(READER_SEQ.load(Ordering::Relaxed), 2);
+
+    // 写锁是独占的
+    assert_eq!(rwlock_write(rwlock), Ok(()));
+    let other = spawn(move || rwlock_unlock(rwlock).unwrap_err().0).unwrap();
+    assert_eq!(waittid(other), Ok(EPERM.0));
+    READ_SHARED.store(false, Ordering::Relaxed);
+    let reader = spawn(move || {
+        rwlock_read(rwlock).unwrap();
+        READ_SHARED.store(true, Ordering::Relaxed);
+        rwlock_unlock(rwlock).unwrap();
+        0
+    })
+    .unwrap();
+    sleep(20);
+    assert!(!READ_SHARED.load(Ordering::Relaxed));
+    assert_eq!(rwlock_unlock(rwlock), Ok(()));
+    assert_eq!(waittid(reader), Ok(0));
+    assert!(READ_SHARED.load(Ordering::Relaxed));
+    println!("rwlock_test passed!");
+    0
+}
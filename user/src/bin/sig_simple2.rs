@@ -12,29 +12,29 @@ fn func() {
 
 #[no_mangle]
 pub fn main() -> i32 {
-    let pid = fork();
+    let pid = fork().unwrap();
     if pid == 0 {
         let mut new = SignalAction::default();
         let mut old = SignalAction::default();
         new.handler = func as usize;
 
         println!("signal_simple2: child sigaction");
-        if sigaction(SIGUSR1, Some(&new), Some(&mut old)) < 0 {
+        if sigaction(SIGUSR1, Some(&new), Some(&mut old)).is_err() {
             panic!("Sigaction failed!");
         }
         sleep(1000);
         println!("signal_simple2: child done");
         exit(0);
-    } else if pid > 0 {
+    } else {
         println!("signal_simple2: parent kill child");
         sleep(500);
-        if kill(pid as usize, SIGUSR1) < 0 {
+        if kill(pid, SIGUSR1).is_err() {
             println!("Kill failed!");
             exit(1);
         }
         println!("signal_simple2: parent wait child");
         let mut exit_code = 0;
-        waitpid(pid as usize, &mut exit_code);
+        waitpid(pid, &mut exit_code).unwrap();
         println!("signal_simple2: parent Done");
         exit(0);
     }
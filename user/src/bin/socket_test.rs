@@ -0,0 +1,120 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    accept, bind, close, connect, exit, fork, listen, open, pipe, read, recvmsg, sendmsg, socket,
+    socketpair, waitpid, write, OpenFlags, AF_UNIX, EADDRINUSE, EBADF, ECONNREFUSED, EINVAL, ENXIO,
+    EPIPE, EPROTOTYPE, SCM_MAX_FD, SOCK_DGRAM, SOCK_STREAM,
+};
+
+const STREAM_PATH: &str = "socket_test.stream\0";
+const DGRAM_PATH: &str = "socket_test.dgram\0";
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut buf = [0u8; 32];
+    let mut fds = [0usize; SCM_MAX_FD];
+    let mut nfds = 0;
+
+    // 流式 socketpair 是双向的，一端关闭后另一端读到文件结尾，再发送返回错误
+    let mut sv = [0usize; 2];
+    assert_eq!(socketpair(AF_UNIX, SOCK_STREAM, &mut sv), Ok(()));
+    assert_eq!(write(sv[0], b"ping"), Ok(4));
+    assert_eq!(read(sv[1], &mut buf), Ok(4));
+    assert_eq!(&buf[..4], b"ping");
+    assert_eq!(write(sv[1], b"pong"), Ok(4));
+    assert_eq!(read(sv[0], &mut buf), Ok(4));
+    assert_eq!(&buf[..4], b"pong");
+    assert_eq!(write(sv[0], b"last"), Ok(4));
+    close(sv[0]).unwrap();
+    assert_eq!(read(sv[1], &mut buf), Ok(4));
+    assert_eq!(read(sv[1], &mut buf), Ok(0));
+    assert_eq!(sendmsg(sv[1], b"lost", &[]), Err(EPIPE));
+    close(sv[1]).unwrap();
+
+    // 数据报保留边界，缓冲区不够时多出的部分被丢弃
+    assert_eq!(socketpair(AF_UNIX, SOCK_DGRAM, &mut sv), Ok(()));
+    for msg in [&b"a"[..], b"bc", b"def"] {
+        assert_eq!(write(sv[0], msg), Ok(msg.len()));
+    }
+    assert_eq!(read(sv[1], &mut buf), Ok(1));
+    assert_eq!(read(sv[1], &mut buf), Ok(2));
+    assert_eq!(read(sv[1], &mut buf[..2]), Ok(2));
+    assert_eq!(&buf[..2], b"de");
+    close(sv[0]).unwrap();
+    close(sv[1]).unwrap();
+
+    // 绑定到路径，同一个路径不能重复绑定，也不能当作普通文件打开
+    let listener = socket(AF_UNIX, SOCK_STREAM).unwrap();
+    assert_eq!(bind(listener, STREAM_PATH), Ok(()));
+    let other = socket(AF_UNIX, SOCK_STREAM).unwrap();
+    assert_eq!(bind(other, STREAM_PATH), Err(EADDRINUSE));
+    assert_eq!(open(STREAM_PATH, OpenFlags::READONLY), Err(ENXIO));
+    // 没有 listen 时不能连接
+    assert_eq!(connect(other, STREAM_PATH), Err(ECONNREFUSED));
+    assert_eq!(listen(listener, 4), Ok(()));
+
+    // 没有共同祖先的限制：子进程按路径连接，并把一个管道的读端传过来
+    let pid = fork().unwrap();
+    if pid == 0 {
+        let sock = socket(AF_UNIX, SOCK_STREAM).unwrap();
+        assert_eq!(connect(sock, STREAM_PATH), Ok(()));
+        let mut pipe_fd = [0usize; 2];
+        pipe(&mut pipe_fd).unwrap();
+        assert_eq!(write(pipe_fd[1], b"via pipe"), Ok(8));
+        close(pipe_fd[1]).unwrap();
+        // 附带的描述符必须合法，数量不能超过 SCM_MAX_FD
+        assert_eq!(sendmsg(sock, b"fd", &[100]), Err(EBADF));
+        assert_eq!(
+            sendmsg(sock, b"fd", &[pipe_fd[0]; SCM_MAX_FD + 1]),
+            Err(EINVAL)
+        );
+        // 流式套接字上的文件必须跟随至少一个字节的数据
+        assert_eq!(sendmsg(sock, b"", &[pipe_fd[0]]), Err(EINVAL));
+        assert_eq!(sendmsg(sock, b"fd", &[pipe_fd[0]]), Ok(2));
+        close(pipe_fd[0]).unwrap();
+        assert_eq!(write(sock, b"bye"), Ok(3));
+        exit(0);
+    }
+    let conn = accept(listener).unwrap();
+    assert_eq!(recvmsg(conn, &mut buf, &mut fds, &mut nfds), Ok(2));
+    assert_eq!(&buf[..2], b"fd");
+    assert_eq!(nfds, 1);
+    // 收到的描述符指向子进程中的同一个管道
+    assert_eq!(read(fds[0], &mut buf), Ok(8));
+    assert_eq!(&buf[..8], b"via pipe");
+    close(fds[0]).unwrap();
+    assert_eq!(read(conn, &mut buf), Ok(3));
+    assert_eq!(&buf[..3], b"bye");
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, 0);
+    assert_eq!(read(conn, &mut buf), Ok(0));
+    close(conn).unwrap();
+
+    // 监听的套接字关闭后路径可以重新绑定
+    close(listener).unwrap();
+    assert_eq!(connect(other, STREAM_PATH), Err(ECONNREFUSED));
+    assert_eq!(bind(other, STREAM_PATH), Ok(()));
+    close(other).unwrap();
+
+    // 数据报套接字按路径连接，类型不同不能连接
+    let server = socket(AF_UNIX, SOCK_DGRAM).unwrap();
+    assert_eq!(bind(server, DGRAM_PATH), Ok(()));
+    let client = socket(AF_UNIX, SOCK_DGRAM).unwrap();
+    assert_eq!(connect(client, DGRAM_PATH), Ok(()));
+    assert_eq!(write(client, b"dgram"), Ok(5));
+    assert_eq!(read(server, &mut buf), Ok(5));
+    assert_eq!(&buf[..5], b"dgram");
+    let stream = socket(AF_UNIX, SOCK_STREAM).unwrap();
+    assert_eq!(connect(stream, DGRAM_PATH), Err(EPROTOTYPE));
+    close(stream).unwrap();
+    close(client).unwrap();
+    close(server).unwrap();
+
+    println!("socket_test passed!");
+    0
+}
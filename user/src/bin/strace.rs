@@ -0,0 +1,89 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use user_lib::{exec, exit, fork, trace, trace_read, waitpid, yield_, Errno, EAGAIN};
+
+/// 把内核的一条跟踪记录格式化成 strace 的样式，无法识别的行原样打印
+fn print_record(line: &str) {
+    let fields: Vec<&str> = line.split('\t').collect();
+    match fields.as_slice() {
+        [pid, tid, name, args, ret, duration_us] => {
+            let duration_us: usize = duration_us.parse().unwrap_or(0);
+            print!("[{} {}] {}({}) = ", pid, tid, name, args);
+            match ret.parse::<isize>() {
+                Ok(ret) if ret < 0 && Errno(-ret as i32).name().is_some() => {
+                    let errno = Errno(-ret as i32);
+                    print!("-1 {:?} ({})", errno, errno)
+                }
+                Ok(ret) => print!("{}", ret),
+                Err(_) => print!("{}", ret),
+            }
+            println!(
+                " <{}.{:06}>",
+                duration_us / 1_000_000,
+                duration_us % 1_000_000
+            );
+        }
+        ["dropped", count] => println!("... {} records dropped ...", count),
+        _ => println!("{}", line),
+    }
+}
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    if argc < 2 {
+        println!("usage: strace <cmd> [args...]");
+        return -1;
+    }
+    let args_copy: Vec<String> = argv[1..]
+        .iter()
+        .map(|&arg| {
+            let mut string = String::from(arg);
+            string.push('\0');
+            string
+        })
+        .collect();
+    let mut args_addr: Vec<*const u8> = args_copy.iter().map(|arg| arg.as_ptr()).collect();
+    args_addr.push(core::ptr::null::<u8>());
+
+    let pid = fork().unwrap();
+    if pid == 0 {
+        trace(true).unwrap();
+        if let Err(err) = exec(args_copy[0].as_str(), args_addr.as_slice()) {
+            println!("strace: {}: {}", argv[1], err);
+            exit(-4);
+        }
+        unreachable!();
+    }
+
+    let mut buf = [0u8; 1024];
+    loop {
+        match trace_read(pid, &mut buf) {
+            Ok(0) => break,
+            Ok(len) => {
+                let records = core::str::from_utf8(&buf[..len]).unwrap();
+                for line in records.lines() {
+                    print_record(line);
+                }
+            }
+            // 子进程还在运行，稍后再读
+            Err(EAGAIN) => {
+                yield_();
+            }
+            Err(err) => {
+                println!("strace: trace_read: {}", err);
+                break;
+            }
+        }
+    }
+    let mut exit_code = 0;
+    waitpid(pid, &mut exit_code).unwrap();
+    println!("+++ exited with {} +++", exit_code);
+    exit_code
+}
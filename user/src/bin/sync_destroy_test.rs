@@ -0,0 +1,82 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    condvar_create, condvar_destroy, condvar_signal, condvar_wait, mutex_blocking_create,
+    mutex_create, mutex_destroy, mutex_lock, mutex_trylock, mutex_unlock, semaphore_create,
+    semaphore_destroy, semaphore_down, semaphore_up, sleep, spawn, waittid, EBUSY, EIDRM, EINVAL,
+    EPERM,
+};
+
+const BAD_ID: usize = 100;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 非法的编号返回错误而不是让内核崩溃
+    assert_eq!(mutex_lock(BAD_ID), Err(EINVAL));
+    assert_eq!(mutex_unlock(BAD_ID), Err(EINVAL));
+    assert_eq!(mutex_destroy(BAD_ID), Err(EINVAL));
+    assert_eq!(semaphore_up(BAD_ID), Err(EINVAL));
+    assert_eq!(semaphore_down(BAD_ID), Err(EINVAL));
+    assert_eq!(semaphore_destroy(BAD_ID), Err(EINVAL));
+    assert_eq!(condvar_signal(BAD_ID), Err(EINVAL));
+    assert_eq!(condvar_destroy(BAD_ID), Err(EINVAL));
+
+    // 销毁后编号失效，之后创建的对象复用它
+    let mutex = mutex_blocking_create().unwrap();
+    assert_eq!(mutex_unlock(mutex), Err(EPERM));
+    assert_eq!(condvar_wait(BAD_ID, mutex), Err(EINVAL));
+    assert_eq!(mutex_destroy(mutex), Ok(()));
+    assert_eq!(mutex_destroy(mutex), Err(EINVAL));
+    assert_eq!(mutex_lock(mutex), Err(EINVAL));
+    assert_eq!(mutex_blocking_create(), Ok(mutex));
+
+    // 只有持有者可以释放互斥锁，其他线程释放返回 EPERM，锁仍然被持有
+    for id in [mutex_create().unwrap(), mutex_blocking_create().unwrap()] {
+        assert_eq!(mutex_lock(id), Ok(()));
+        let other = spawn(move || {
+            let ret = mutex_unlock(id);
+            (ret == Err(EPERM) && mutex_trylock(id) == Err(EBUSY)) as i32
+        })
+        .unwrap();
+        assert_eq!(waittid(other), Ok(1));
+        assert_eq!(mutex_unlock(id), Ok(()));
+        assert_eq!(mutex_destroy(id), Ok(()));
+    }
+
+    // 阻塞在互斥锁上的线程在锁被销毁后返回 EIDRM
+    assert_eq!(mutex_lock(mutex), Ok(()));
+    let waiter = spawn(move || (mutex_lock(mutex) == Err(EIDRM)) as i32).unwrap();
+    sleep(10);
+    assert_eq!(mutex_destroy(mutex), Ok(()));
+    assert_eq!(waittid(waiter), Ok(1));
+
+    // 阻塞在信号量上的线程在信号量被销毁后返回 EIDRM
+    let sem = semaphore_create(0).unwrap();
+    let waiter = spawn(move || (semaphore_down(sem) == Err(EIDRM)) as i32).unwrap();
+    sleep(10);
+    assert_eq!(semaphore_destroy(sem), Ok(()));
+    assert_eq!(waittid(waiter), Ok(1));
+    assert_eq!(semaphore_up(sem), Err(EINVAL));
+
+    // 等待条件变量的线程在条件变量被销毁后重新持有互斥锁并返回 EIDRM
+    let mutex = mutex_blocking_create().unwrap();
+    let condvar = condvar_create().unwrap();
+    let waiter = spawn(move || {
+        mutex_lock(mutex).unwrap();
+        let ret = condvar_wait(condvar, mutex);
+        (ret == Err(EIDRM) && mutex_unlock(mutex).is_ok()) as i32
+    })
+    .unwrap();
+    sleep(10);
+    assert_eq!(condvar_destroy(condvar), Ok(()));
+    assert_eq!(waittid(waiter), Ok(1));
+    assert_eq!(condvar_signal(condvar), Err(EINVAL));
+    assert_eq!(mutex_destroy(mutex), Ok(()));
+
+    println!("sync_destroy_test passed!");
+    0
+}
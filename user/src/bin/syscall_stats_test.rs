@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use user_lib::{getpid, raw_syscall, read_to_string, Errno, ENOSYS};
+
+/// 从 /proc/syscalls 中读取未知系统调用的次数以及 `name` 的调用次数
+fn counters(name: &str) -> (usize, usize) {
+    let content = read_to_string("/proc/syscalls").unwrap();
+    let mut lines = content.lines();
+    let unknown = lines
+        .next()
+        .and_then(|line| line.strip_prefix("Unknown:\t"))
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(lines.next(), Some("NR\tNAME\tARGS\tCALLS"));
+    let calls = lines
+        .map(|line| line.split('\t').collect::<Vec<_>>())
+        .find(|fields| fields[1] == name)
+        .unwrap()[3]
+        .parse()
+        .unwrap();
+    (unknown, calls)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 未知的系统调用号返回 ENOSYS，而不是让内核崩溃
+    for id in [0, 4096, usize::MAX] {
+        assert_eq!(Errno::result(raw_syscall(id, [0; 6])), Err(ENOSYS));
+    }
+
+    let (unknown, calls) = counters("getpid");
+    for _ in 0..3 {
+        getpid();
+    }
+    assert_eq!(Errno::result(raw_syscall(4096, [0; 6])), Err(ENOSYS));
+    let (unknown2, calls2) = counters("getpid");
+    assert!(calls2 >= calls + 3);
+    assert!(unknown2 > unknown);
+    assert!(unknown >= 3);
+
+    let content = read_to_string("/proc/syscalls").unwrap();
+    assert!(content
+        .lines()
+        .any(|line| line.starts_with("64\twrite\t3\t")));
+    println!("syscall_stats_test passed!");
+    0
+}
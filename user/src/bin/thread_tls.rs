@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::hint::black_box;
+use user_lib::{
+    gettid, spawn, spawn_with_attr, tls_base, waittid, yield_, ThreadAttr, EINVAL, THREAD_DETACHED,
+};
+
+const THREAD_NUM: usize = 4;
+
+/// 每一层递归占用 1KiB 的栈空间
+fn deep_recursion(depth: usize) -> usize {
+    let buf = black_box([depth as u8; 1024]);
+    if depth == 0 {
+        buf[0] as usize
+    } else {
+        deep_recursion(depth - 1) + buf[1023] as usize
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let main_tls = tls_base() as *mut usize;
+    assert!(!main_tls.is_null());
+    unsafe {
+        *main_tls = 0x5a5a;
+    }
+    // 每个线程有自己的 TLS 块
+    let mut tids = Vec::new();
+    for i in 0..THREAD_NUM {
+        let tid = spawn(move || {
+            let tls = tls_base() as *mut usize;
+            unsafe {
+                *tls = gettid() as usize;
+            }
+            for _ in 0..10 {
+                yield_();
+            }
+            assert_eq!(unsafe { *tls }, gettid() as usize);
+            (i + 100) as i32
+        })
+        .unwrap();
+        tids.push(tid);
+    }
+    for (i, tid) in tids.iter().enumerate() {
+        assert_eq!(waittid(*tid), Ok((i + 100) as i32));
+    }
+    assert_eq!(unsafe { *main_tls }, 0x5a5a);
+    println!("thread_tls: tls blocks are private");
+
+    // 栈更大的线程可以比默认的 8KiB 栈递归得更深
+    let attr = ThreadAttr {
+        stack_size: 4096 * 16,
+        ..Default::default()
+    };
+    let tid = spawn_with_attr(|| (deep_recursion(40) != 0) as i32, &attr).unwrap();
+    assert_eq!(waittid(tid), Ok(1));
+    // 申请超过上限的栈会失败
+    let attr = ThreadAttr {
+        stack_size: 4096 * 1024,
+        ..Default::default()
+    };
+    assert_eq!(spawn_with_attr(|| 0, &attr), Err(EINVAL));
+    println!("thread_tls: stack size ok");
+
+    // 分离的线程不能被等待
+    let attr = ThreadAttr {
+        flags: THREAD_DETACHED,
+        ..Default::default()
+    };
+    let tid = spawn_with_attr(|| 0, &attr).unwrap();
+    assert_eq!(waittid(tid), Err(EINVAL));
+    println!("thread_tls passed!");
+    0
+}
@@ -0,0 +1,109 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    condvar_create, condvar_signal, condvar_wait_timeout, get_time, mutex_blocking_create,
+    mutex_lock, mutex_lock_timeout, mutex_trylock, mutex_unlock, semaphore_create,
+    semaphore_down_timeout, semaphore_trydown, semaphore_up, setitimer, sigaction, sigreturn,
+    sleep, spawn, waittid, ITimerVal, SignalAction, TimeSpec, TimeVal, EAGAIN, EBUSY, ETIMEDOUT,
+    ITIMER_REAL, SIGALRM,
+};
+
+fn ms(ms: usize) -> TimeSpec {
+    TimeSpec {
+        tv_sec: ms / 1000,
+        tv_nsec: ms % 1000 * 1_000_000,
+    }
+}
+
+fn on_alarm() {
+    sigreturn();
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 互斥锁：持有者释放之前 trylock 失败、短超时失败，长超时成功
+    let mutex = mutex_blocking_create().unwrap();
+    assert_eq!(mutex_lock(mutex), Ok(()));
+    let tid = spawn(move || {
+        assert_eq!(mutex_trylock(mutex), Err(EBUSY));
+        assert_eq!(mutex_lock_timeout(mutex, &ms(10)), Err(ETIMEDOUT));
+        assert_eq!(mutex_lock_timeout(mutex, &ms(1000)), Ok(()));
+        mutex_unlock(mutex).unwrap();
+        0
+    })
+    .unwrap();
+    sleep(50);
+    assert_eq!(mutex_unlock(mutex), Ok(()));
+    assert_eq!(waittid(tid), Ok(0));
+    assert_eq!(mutex_trylock(mutex), Ok(()));
+    assert_eq!(mutex_unlock(mutex), Ok(()));
+
+    // 信号量：超时的 P 操作不会占用资源
+    let sem = semaphore_create(0).unwrap();
+    assert_eq!(semaphore_trydown(sem), Err(EAGAIN));
+    assert_eq!(semaphore_down_timeout(sem, &ms(10)), Err(ETIMEDOUT));
+    assert_eq!(semaphore_up(sem), Ok(()));
+    assert_eq!(semaphore_trydown(sem), Ok(()));
+    assert_eq!(semaphore_trydown(sem), Err(EAGAIN));
+    assert_eq!(semaphore_up(sem), Ok(()));
+    assert_eq!(semaphore_down_timeout(sem, &ms(10)), Ok(()));
+
+    // 条件变量：超时后仍然持有互斥锁
+    let condvar = condvar_create().unwrap();
+    assert_eq!(mutex_lock(mutex), Ok(()));
+    assert_eq!(
+        condvar_wait_timeout(condvar, mutex, &ms(10)),
+        Err(ETIMEDOUT)
+    );
+    assert_eq!(mutex_trylock(mutex), Err(EBUSY));
+    let tid = spawn(move || {
+        mutex_lock(mutex).unwrap();
+        condvar_signal(condvar).unwrap();
+        mutex_unlock(mutex).unwrap();
+        0
+    })
+    .unwrap();
+    assert_eq!(condvar_wait_timeout(condvar, mutex, &ms(1000)), Ok(()));
+    assert_eq!(mutex_unlock(mutex), Ok(()));
+    assert_eq!(waittid(tid), Ok(0));
+
+    // 超时时间接近 usize::MAX 时一直等待，直到被其他线程唤醒
+    let forever = TimeSpec {
+        tv_sec: usize::MAX - 1,
+        tv_nsec: 999_999_999,
+    };
+    let tid = spawn(move || {
+        sleep(20);
+        semaphore_up(sem).unwrap();
+        0
+    })
+    .unwrap();
+    assert_eq!(semaphore_down_timeout(sem, &forever), Ok(()));
+    assert_eq!(waittid(tid), Ok(0));
+    assert_eq!(mutex_lock_timeout(mutex, &forever), Ok(()));
+    assert_eq!(mutex_unlock(mutex), Ok(()));
+
+    // SIGALRM 只会提前唤醒 nanosleep，带超时的等待仍然等到超时才返回
+    let action = SignalAction {
+        handler: on_alarm as usize,
+        ..Default::default()
+    };
+    sigaction(SIGALRM, Some(&action), None).unwrap();
+    let value = ITimerVal {
+        it_value: TimeVal {
+            tv_sec: 0,
+            tv_usec: 20000,
+        },
+        ..Default::default()
+    };
+    setitimer(ITIMER_REAL, &value, None).unwrap();
+    let start = get_time();
+    assert_eq!(semaphore_down_timeout(sem, &ms(200)), Err(ETIMEDOUT));
+    assert!(get_time() - start >= 200);
+    println!("timed_wait_test passed!");
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, sleep, spawn, usleep, waitpid};
+
+const THREAD_NUM: usize = 32;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 子进程在它的所有线程仍在睡眠时退出，
+    // 进程被销毁时取消这些线程的定时器
+    let pid = fork().unwrap();
+    if pid == 0 {
+        for i in 0..THREAD_NUM {
+            assert!(spawn(move || {
+                sleep(10000 + i);
+                0
+            })
+            .is_ok());
+        }
+        sleep(10);
+        exit(7);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    assert_eq!(exit_code, 7);
+
+    // 大量时间相近的短睡眠仍然按顺序到期
+    let start = get_time();
+    for _ in 0..20 {
+        usleep(500);
+    }
+    assert!(get_time() - start >= 10);
+    println!("timer_cancel passed!");
+    0
+}
@@ -0,0 +1,96 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    alarm, clock_gettime, exit, fork, get_time, getitimer, nanosleep, setitimer, sleep, usleep,
+    waitpid, yield_, ITimerVal, TimeSpec, TimeVal, CLOCK_MONOTONIC, EINVAL, ITIMER_REAL,
+    ITIMER_VIRTUAL, SIGALRM, SIGVTALRM,
+};
+
+fn now_ns() -> usize {
+    let mut ts = TimeSpec::default();
+    assert_eq!(clock_gettime(CLOCK_MONOTONIC, &mut ts), Ok(()));
+    assert!(ts.tv_nsec < 1000000000);
+    ts.tv_sec * 1000000000 + ts.tv_nsec
+}
+
+/// 在子进程中运行 f，返回子进程的退出码
+fn run_child(f: fn()) -> i32 {
+    let pid = fork().unwrap();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // clock_gettime 单调递增
+    let t0 = now_ns();
+    let t1 = now_ns();
+    assert!(t1 >= t0);
+
+    // 不足一毫秒的睡眠至少阻塞请求的时间
+    let start = now_ns();
+    usleep(1500);
+    assert!(now_ns() - start >= 1500 * 1000);
+    let bad = TimeSpec {
+        tv_sec: 0,
+        tv_nsec: 1000000000,
+    };
+    assert_eq!(nanosleep(&bad, None), Err(EINVAL));
+    println!("timer_test: nanosleep ok");
+
+    // 间隔定时器初始时未启用，alarm 返回原来的闹钟剩余的秒数
+    let mut value = ITimerVal::default();
+    assert_eq!(getitimer(ITIMER_REAL, &mut value), Ok(()));
+    assert_eq!(value.it_value.tv_sec + value.it_value.tv_usec, 0);
+    assert_eq!(alarm(10), 0);
+    assert_eq!(getitimer(ITIMER_REAL, &mut value), Ok(()));
+    assert!(value.it_value.tv_sec <= 10 && value.it_value.tv_sec + value.it_value.tv_usec > 0);
+    assert_eq!(alarm(0), 10);
+    println!("timer_test: alarm cancel ok");
+
+    // 闹钟到期时终止进程
+    let exit_code = run_child(|| {
+        alarm(1);
+        loop {
+            yield_();
+        }
+    });
+    assert_eq!(exit_code, -SIGALRM);
+
+    // 闹钟打断更长的睡眠
+    let start = get_time();
+    let exit_code = run_child(|| {
+        alarm(1);
+        sleep(5000);
+    });
+    assert_eq!(exit_code, -SIGALRM);
+    assert!(get_time() - start < 3000);
+    println!("timer_test: ITIMER_REAL ok");
+
+    // ITIMER_VIRTUAL 只在用户态运行时推进
+    let exit_code = run_child(|| {
+        let value = ITimerVal {
+            it_value: TimeVal {
+                tv_sec: 0,
+                tv_usec: 20000,
+            },
+            ..Default::default()
+        };
+        setitimer(ITIMER_VIRTUAL, &value, None).unwrap();
+        loop {
+            core::hint::spin_loop();
+        }
+    });
+    assert_eq!(exit_code, -SIGVTALRM);
+    println!("timer_test passed!");
+    0
+}
@@ -0,0 +1,82 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use user_lib::{proc_field, read_to_string, sleep};
+
+/// 采样间隔，单位为毫秒
+const INTERVAL_MS: usize = 1000;
+
+fn ms(content: &str, key: &str) -> usize {
+    proc_field(content, key)
+        .unwrap_or("")
+        .trim_end_matches(" ms")
+        .parse()
+        .unwrap_or(0)
+}
+
+/// 读取所有进程的状态，返回 pid -> (CPU 时间, 状态文件内容)
+fn sample() -> BTreeMap<usize, (usize, String)> {
+    let mut processes = BTreeMap::new();
+    let root = read_to_string("/proc").unwrap_or_default();
+    for pid in root.lines().filter_map(|line| line.parse::<usize>().ok()) {
+        if let Some(status) = read_to_string(format!("/proc/{}/status", pid).as_str()) {
+            let time = ms(&status, "Utime") + ms(&status, "Stime");
+            processes.insert(pid, (time, status));
+        }
+    }
+    processes
+}
+
+/// 用法: top [次数]，默认刷新 5 次
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    let rounds = if argc > 1 {
+        argv[1].parse().unwrap_or(5)
+    } else {
+        5
+    };
+    let mut last = sample();
+    for _ in 0..rounds {
+        sleep(INTERVAL_MS);
+        let now = sample();
+        let uptime = read_to_string("/proc/uptime").unwrap_or_default();
+        let meminfo = read_to_string("/proc/meminfo").unwrap_or_default();
+        let sched = read_to_string("/proc/sched").unwrap_or_default();
+        println!(
+            "up {}s, {} processes, {} ready",
+            uptime.split(' ').next().unwrap_or("?"),
+            now.len(),
+            proc_field(&sched, "Ready").unwrap_or("?")
+        );
+        println!(
+            "Mem: {} total, {} free; Heap: {} used of {}",
+            proc_field(&meminfo, "MemTotal").unwrap_or("?"),
+            proc_field(&meminfo, "MemFree").unwrap_or("?"),
+            proc_field(&meminfo, "HeapUsed").unwrap_or("?"),
+            proc_field(&meminfo, "HeapTotal").unwrap_or("?")
+        );
+        println!("PID\tS\tTHR\t%CPU\tTIME\tCMD");
+        for (pid, (time, status)) in now.iter() {
+            let prev = last.get(pid).map_or(0, |(time, _)| *time);
+            println!(
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                pid,
+                proc_field(status, "State").unwrap_or("?"),
+                proc_field(status, "Threads").unwrap_or("?"),
+                (time - prev.min(*time)) * 100 / INTERVAL_MS,
+                time,
+                proc_field(status, "Name").unwrap_or("?")
+            );
+        }
+        println!("");
+        last = now;
+    }
+    0
+}
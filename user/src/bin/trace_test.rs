@@ -0,0 +1,105 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use user_lib::{
+    close, exit, fork, getpid, open, trace, trace_read, waitpid, yield_, OpenFlags, EAGAIN, ECHILD,
+    EINVAL, ENOENT,
+};
+
+/// 读取 `pid` 的所有跟踪记录直到它退出，每条记录占一行
+fn read_trace(pid: usize) -> String {
+    let mut content = String::new();
+    let mut buf = [0u8; 256];
+    loop {
+        match trace_read(pid, &mut buf) {
+            Ok(0) => return content,
+            Ok(len) => content.push_str(core::str::from_utf8(&buf[..len]).unwrap()),
+            Err(EAGAIN) => {
+                yield_();
+            }
+            Err(err) => panic!("trace_read failed: {:?}", err),
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 只能读取子进程的记录，并且子进程必须已经开启跟踪
+    assert_eq!(trace_read(getpid() as usize, &mut [0u8; 64]), Err(ECHILD));
+    let untraced = fork().unwrap();
+    if untraced == 0 {
+        exit(0);
+    }
+    assert_eq!(trace_read(untraced, &mut [0u8; 64]), Err(EINVAL));
+    let mut exit_code = 0;
+    assert_eq!(waitpid(untraced, &mut exit_code), Ok(untraced));
+
+    let child = fork().unwrap();
+    if child == 0 {
+        trace(true).unwrap();
+        let pid = getpid();
+        assert_eq!(open("/nonexistent\0", OpenFlags::READONLY), Err(ENOENT));
+        let fd = open("filea\0", OpenFlags::READONLY).unwrap();
+        close(fd).unwrap();
+        // 子进程继承跟踪状态，并共享同一个缓冲区
+        let grandchild = fork().unwrap();
+        if grandchild == 0 {
+            exit(3);
+        }
+        let mut exit_code = 0;
+        assert_eq!(waitpid(grandchild, &mut exit_code), Ok(grandchild));
+        exit(pid as i32);
+    }
+    // 缓冲区放不下一条记录时返回错误，记录不会丢失
+    let mut small = [0u8; 4];
+    loop {
+        match trace_read(child, &mut small) {
+            Err(EAGAIN) => {
+                yield_();
+            }
+            result => {
+                assert_eq!(result, Err(EINVAL));
+                break;
+            }
+        }
+    }
+
+    let content = read_trace(child);
+    let records: Vec<Vec<&str>> = content
+        .lines()
+        .map(|line| line.split('\t').collect())
+        .collect();
+    assert!(records.iter().all(|fields| fields.len() == 6));
+    let find = |name: &str, args: &str| {
+        records
+            .iter()
+            .find(|fields| fields[2] == name && fields[3] == args)
+            .unwrap_or_else(|| panic!("no record for {}({})", name, args))
+    };
+    let pid = format!("{}", child);
+    let getpid = find("getpid", "");
+    assert_eq!(getpid[0], pid);
+    assert_eq!(getpid[4], pid);
+    assert_eq!(find("open", "\"/nonexistent\", 0x0")[4], "-2");
+    let close = find("close", find("open", "\"filea\", 0x0")[4]);
+    assert_eq!(close[4], "0");
+    let grandchild_exit = find("exit", "3");
+    assert_ne!(grandchild_exit[0], pid);
+    assert_eq!(grandchild_exit[4], "?");
+    let exit = find("exit", pid.as_str());
+    assert_eq!(exit[0], pid);
+    assert_eq!(exit[4], "?");
+    assert_eq!(exit, records.last().unwrap());
+
+    assert_eq!(waitpid(child, &mut exit_code), Ok(child));
+    assert_eq!(exit_code, child as i32);
+    println!("trace_test passed!");
+    0
+}
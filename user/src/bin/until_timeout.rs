@@ -12,9 +12,9 @@ pub fn main(argc: usize, argv: &[&str]) -> i32 {
     let timeout_ms = argv[2]
         .parse::<isize>()
         .expect("Error when parsing timeout!");
-    let pid = fork() as usize;
+    let pid = fork().unwrap();
     if pid == 0 {
-        if exec(argv[1], &[core::ptr::null::<u8>()]) != 0 {
+        if exec(argv[1], &[core::ptr::null::<u8>()]).is_err() {
             println!("Error when executing '{}'", argv[1]);
             return -4;
         }
@@ -26,7 +26,7 @@ pub fn main(argc: usize, argv: &[&str]) -> i32 {
             if get_time() - start_time > timeout_ms {
                 break;
             }
-            if waitpid(pid, &mut exit_code) as usize == pid {
+            if waitpid(pid, &mut exit_code) == Ok(pid) {
                 child_exited = true;
                 println!(
                     "child exited in {}ms, exit_code = {}",
@@ -37,8 +37,8 @@ pub fn main(argc: usize, argv: &[&str]) -> i32 {
         }
         if !child_exited {
             println!("child has run for {}ms, kill it!", timeout_ms);
-            kill(pid, SignalFlags::SIGINT.bits());
-            assert_eq!(waitpid(pid, &mut exit_code) as usize, pid);
+            kill(pid, SignalFlags::SIGINT.bits()).unwrap();
+            assert_eq!(waitpid(pid, &mut exit_code), Ok(pid));
             println!("exit code of the child is {}", exit_code);
         }
     }
@@ -116,13 +116,13 @@ pub fn main() -> i32 {
                         if !process_arguments_list.is_empty() {
                             for _ in 0..process_arguments_list.len() - 1 {
                                 let mut pipe_fd = [0usize; 2];
-                                pipe(&mut pipe_fd);
+                                pipe(&mut pipe_fd).unwrap();
                                 pipes_fd.push(pipe_fd);
                             }
                         }
                         let mut children: Vec<_> = Vec::new();
                         for (i, process_argument) in process_arguments_list.iter().enumerate() {
-                            let pid = fork();
+                            let pid = fork().unwrap();
                             if pid == 0 {
                                 let input = &process_argument.input;
                                 let output = &process_argument.output;
@@ -130,51 +130,54 @@ pub fn main() -> i32 {
                                 let args_addr = &process_argument.args_addr;
                                 // redirect input
                                 if !input.is_empty() {
-                                    let input_fd = open(input.as_str(), OpenFlags::READONLY);
-                                    if input_fd == -1 {
-                                        println!("Error when opening file {}", input);
-                                        return -4;
-                                    }
-                                    let input_fd = input_fd as usize;
-                                    close(0);
-                                    assert_eq!(dup(input_fd), 0);
-                                    close(input_fd);
+                                    let input_fd = match open(input.as_str(), OpenFlags::READONLY) {
+                                        Ok(fd) => fd,
+                                        Err(err) => {
+                                            println!("Error when opening file {}: {}", input, err);
+                                            return -4;
+                                        }
+                                    };
+                                    close(0).ok();
+                                    assert_eq!(dup(input_fd), Ok(0));
+                                    close(input_fd).ok();
                                 }
                                 // redirect output
                                 if !output.is_empty() {
-                                    let output_fd = open(
+                                    let output_fd = match open(
                                         output.as_str(),
                                         OpenFlags::CREATE | OpenFlags::WRONLY,
-                                    );
-                                    if output_fd == -1 {
-                                        println!("Error when opening file {}", output);
-                                        return -4;
-                                    }
-                                    let output_fd = output_fd as usize;
-                                    close(1);
-                                    assert_eq!(dup(output_fd), 1);
-                                    close(output_fd);
+                                    ) {
+                                        Ok(fd) => fd,
+                                        Err(err) => {
+                                            println!("Error when opening file {}: {}", output, err);
+                                            return -4;
+                                        }
+                                    };
+                                    close(1).ok();
+                                    assert_eq!(dup(output_fd), Ok(1));
+                                    close(output_fd).ok();
                                 }
                                 // receive input from the previous process
                                 if i > 0 {
-                                    close(0);
+                                    close(0).ok();
                                     let read_end = pipes_fd.get(i - 1).unwrap()[0];
-                                    assert_eq!(dup(read_end), 0);
+                                    assert_eq!(dup(read_end), Ok(0));
                                 }
                                 // send output to the next process
                                 if i < process_arguments_list.len() - 1 {
-                                    close(1);
+                                    close(1).ok();
                                     let write_end = pipes_fd.get(i).unwrap()[1];
-                                    assert_eq!(dup(write_end), 1);
+                                    assert_eq!(dup(write_end), Ok(1));
                                 }
                                 // close all pipe ends inherited from the parent process
                                 for pipe_fd in pipes_fd.iter() {
-                                    close(pipe_fd[0]);
-                                    close(pipe_fd[1]);
+                                    close(pipe_fd[0]).ok();
+                                    close(pipe_fd[1]).ok();
                                 }
                                 // execute new application
-                                if exec(args_copy[0].as_str(), args_addr.as_slice()) == -1 {
-                                    println!("Error when executing!");
+                                if let Err(err) = exec(args_copy[0].as_str(), args_addr.as_slice())
+                                {
+                                    println!("Error when executing: {}", err);
                                     return -4;
                                 }
                                 unreachable!();
@@ -183,13 +186,13 @@ pub fn main() -> i32 {
                             }
                         }
                         for pipe_fd in pipes_fd.iter() {
-                            close(pipe_fd[0]);
-                            close(pipe_fd[1]);
+                            close(pipe_fd[0]).ok();
+                            close(pipe_fd[1]).ok();
                         }
                         let mut exit_code: i32 = 0;
                         for pid in children.into_iter() {
-                            let exit_pid = waitpid(pid as usize, &mut exit_code);
-                            assert_eq!(pid, exit_pid);
+                            let exit_pid = waitpid(pid, &mut exit_code);
+                            assert_eq!(Ok(pid), exit_pid);
                             //println!("Shell: Process {} exited with code {}", pid, exit_code);
                         }
                     }
@@ -24,14 +24,14 @@ use user_lib::{exec, fork, waitpid};
 pub fn main() -> i32 {
     for test in TESTS {
         println!("Usertests: Running {}", test);
-        let pid = fork();
+        let pid = fork().unwrap();
         if pid == 0 {
-            exec(*test, &[core::ptr::null::<u8>()]);
+            exec(*test, &[core::ptr::null::<u8>()]).ok();
             panic!("unreachable!");
         } else {
             let mut exit_code: i32 = Default::default();
-            let wait_pid = waitpid(pid as usize, &mut exit_code);
-            assert_eq!(pid, wait_pid);
+            let wait_pid = waitpid(pid, &mut exit_code);
+            assert_eq!(Ok(pid), wait_pid);
             println!(
                 "\x1b[32mUsertests: Test {} in Process {} exited with code {}\x1b[0m",
                 test, pid, exit_code
@@ -11,24 +11,51 @@ extern crate user_lib;
 static SUCC_TESTS: &[(&str, &str, &str, &str, i32)] = &[
     ("filetest_simple\0", "\0", "\0", "\0", 0),
     ("cat\0", "filea\0", "\0", "\0", 0),
+    ("barrier_test\0", "\0", "\0", "\0", 0),
+    ("bad_pointer_test\0", "\0", "\0", "\0", 0),
     ("cmdline_args\0", "1\0", "2\0", "3\0", 0),
+    ("cpu_stat\0", "\0", "\0", "\0", 0),
+    ("cred_test\0", "\0", "\0", "\0", 0),
+    ("deadlock_test\0", "\0", "\0", "\0", 0),
     ("exit\0", "\0", "\0", "\0", 0),
     ("fantastic_text\0", "\0", "\0", "\0", 0),
+    ("fifo_test\0", "\0", "\0", "\0", 0),
+    ("futex_test\0", "\0", "\0", "\0", 0),
     ("forktest_simple\0", "\0", "\0", "\0", 0),
     ("forktest\0", "\0", "\0", "\0", 0),
     ("forktest2\0", "\0", "\0", "\0", 0),
     ("forktree\0", "\0", "\0", "\0", 0),
     ("hello_world\0", "\0", "\0", "\0", 0),
     ("huge_write\0", "\0", "\0", "\0", 0),
+    ("kthread_test\0", "\0", "\0", "\0", 0),
     ("matrix\0", "\0", "\0", "\0", 0),
+    ("mq_test\0", "\0", "\0", "\0", 0),
+    ("nonblock_test\0", "\0", "\0", "\0", 0),
+    ("pi_test\0", "\0", "\0", "\0", 0),
+    ("poll_test\0", "\0", "\0", "\0", 0),
+    ("pipe_epipe_test\0", "\0", "\0", "\0", 0),
     ("pipe_large_test\0", "\0", "\0", "\0", 0),
     ("pipetest\0", "\0", "\0", "\0", 0),
     ("run_pipe_test\0", "\0", "\0", "\0", 0),
+    ("procfs_test\0", "\0", "\0", "\0", 0),
+    ("ps\0", "\0", "\0", "\0", 0),
+    ("rlimit_test\0", "\0", "\0", "\0", 0),
+    ("rusage_test\0", "\0", "\0", "\0", 0),
+    ("rwlock_test\0", "\0", "\0", "\0", 0),
     ("sleep_simple\0", "\0", "\0", "\0", 0),
+    ("socket_test\0", "\0", "\0", "\0", 0),
     ("sleep\0", "\0", "\0", "\0", 0),
     ("sig_simple\0", "\0", "\0", "\0", 0),
     ("sig_simple2\0", "\0", "\0", "\0", 0),
     ("sig_tests\0", "\0", "\0", "\0", 0),
+    ("sync_destroy_test\0", "\0", "\0", "\0", 0),
+    ("syscall_stats_test\0", "\0", "\0", "\0", 0),
+    ("thread_tls\0", "\0", "\0", "\0", 0),
+    ("timer_test\0", "\0", "\0", "\0", 0),
+    ("timer_cancel\0", "\0", "\0", "\0", 0),
+    ("timed_wait_test\0", "\0", "\0", "\0", 0),
+    ("trace_test\0", "\0", "\0", "\0", 0),
+    ("strace\0", "hello_world\0", "\0", "\0", 0),
     ("yield\0", "\0", "\0", "\0", 0),
 ];
 
@@ -75,14 +102,14 @@ fn run_tests(tests: &[(&str, &str, &str, &str, i32)]) -> i32 {
             arr[3] = core::ptr::null::<u8>();
         }
 
-        let pid = fork();
+        let pid = fork().unwrap();
         if pid == 0 {
-            exec(test.0, &arr[..]);
+            exec(test.0, &arr[..]).ok();
             panic!("unreachable!");
         } else {
             let mut exit_code: i32 = Default::default();
-            let wait_pid = waitpid(pid as usize, &mut exit_code);
-            assert_eq!(pid, wait_pid);
+            let wait_pid = waitpid(pid, &mut exit_code);
+            assert_eq!(Ok(pid), wait_pid);
             if exit_code == test.4 {
                 // summary apps with  exit_code
                 pass_num = pass_num + 1;
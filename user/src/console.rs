@@ -11,7 +11,7 @@ const STDOUT: usize = 1;
 
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        write(STDOUT, s.as_bytes());
+        write(STDOUT, s.as_bytes()).ok();
         Ok(())
     }
 }
@@ -23,7 +23,7 @@ pub fn print(args: fmt::Arguments) {
 pub fn getchar() -> u8 {
     // 每次声明一个长度为 1 的缓冲区
     let mut c = [0u8; 1];
-    read(STDIN, &mut c);
+    read(STDIN, &mut c).ok();
     c[0]
 }
 
@@ -0,0 +1,143 @@
+//! 系统调用的错误码
+//!
+//! 内核出错时返回负的错误码，user_lib 的封装把它转换成 `Err(Errno)`。
+//! Display 输出与 strerror 相同的描述，Debug 输出错误码的名字
+
+use core::fmt;
+
+/// 错误码，取值与 Linux 相同
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Errno(pub i32);
+
+pub const EPERM: Errno = Errno(1);
+pub const ENOENT: Errno = Errno(2);
+pub const ESRCH: Errno = Errno(3);
+pub const EINTR: Errno = Errno(4);
+pub const EIO: Errno = Errno(5);
+pub const ENXIO: Errno = Errno(6);
+pub const E2BIG: Errno = Errno(7);
+pub const ENOEXEC: Errno = Errno(8);
+pub const EBADF: Errno = Errno(9);
+pub const ECHILD: Errno = Errno(10);
+pub const EAGAIN: Errno = Errno(11);
+pub const ENOMEM: Errno = Errno(12);
+pub const EACCES: Errno = Errno(13);
+pub const EFAULT: Errno = Errno(14);
+pub const EBUSY: Errno = Errno(16);
+pub const EEXIST: Errno = Errno(17);
+pub const EINVAL: Errno = Errno(22);
+pub const EMFILE: Errno = Errno(24);
+pub const ENOSPC: Errno = Errno(28);
+pub const EPIPE: Errno = Errno(32);
+pub const EDEADLK: Errno = Errno(35);
+pub const ENOSYS: Errno = Errno(38);
+pub const EIDRM: Errno = Errno(43);
+pub const ENOTSOCK: Errno = Errno(88);
+pub const EDESTADDRREQ: Errno = Errno(89);
+pub const EMSGSIZE: Errno = Errno(90);
+pub const EPROTOTYPE: Errno = Errno(91);
+pub const EPROTONOSUPPORT: Errno = Errno(93);
+pub const EOPNOTSUPP: Errno = Errno(95);
+pub const EAFNOSUPPORT: Errno = Errno(97);
+pub const EADDRINUSE: Errno = Errno(98);
+pub const EISCONN: Errno = Errno(106);
+pub const ENOTCONN: Errno = Errno(107);
+pub const ETIMEDOUT: Errno = Errno(110);
+pub const ECONNREFUSED: Errno = Errno(111);
+
+/// 错误码、名字以及描述
+const ERRNO_TABLE: &[(Errno, &str, &str)] = &[
+    (EPERM, "EPERM", "Operation not permitted"),
+    (ENOENT, "ENOENT", "No such file or directory"),
+    (ESRCH, "ESRCH", "No such process"),
+    (EINTR, "EINTR", "Interrupted system call"),
+    (EIO, "EIO", "Input/output error"),
+    (ENXIO, "ENXIO", "No such device or address"),
+    (E2BIG, "E2BIG", "Argument list too long"),
+    (ENOEXEC, "ENOEXEC", "Exec format error"),
+    (EBADF, "EBADF", "Bad file descriptor"),
+    (ECHILD, "ECHILD", "No child processes"),
+    (EAGAIN, "EAGAIN", "Resource temporarily unavailable"),
+    (ENOMEM, "ENOMEM", "Cannot allocate memory"),
+    (EACCES, "EACCES", "Permission denied"),
+    (EFAULT, "EFAULT", "Bad address"),
+    (EBUSY, "EBUSY", "Device or resource busy"),
+    (EEXIST, "EEXIST", "File exists"),
+    (EINVAL, "EINVAL", "Invalid argument"),
+    (EMFILE, "EMFILE", "Too many open files"),
+    (ENOSPC, "ENOSPC", "No space left on device"),
+    (EPIPE, "EPIPE", "Broken pipe"),
+    (EDEADLK, "EDEADLK", "Resource deadlock avoided"),
+    (ENOSYS, "ENOSYS", "Function not implemented"),
+    (EIDRM, "EIDRM", "Identifier removed"),
+    (ENOTSOCK, "ENOTSOCK", "Socket operation on non-socket"),
+    (EDESTADDRREQ, "EDESTADDRREQ", "Destination address required"),
+    (EMSGSIZE, "EMSGSIZE", "Message too long"),
+    (EPROTOTYPE, "EPROTOTYPE", "Protocol wrong type for socket"),
+    (EPROTONOSUPPORT, "EPROTONOSUPPORT", "Protocol not supported"),
+    (EOPNOTSUPP, "EOPNOTSUPP", "Operation not supported"),
+    (
+        EAFNOSUPPORT,
+        "EAFNOSUPPORT",
+        "Address family not supported by protocol",
+    ),
+    (EADDRINUSE, "EADDRINUSE", "Address already in use"),
+    (
+        EISCONN,
+        "EISCONN",
+        "Transport endpoint is already connected",
+    ),
+    (ENOTCONN, "ENOTCONN", "Transport endpoint is not connected"),
+    (ETIMEDOUT, "ETIMEDOUT", "Connection timed out"),
+    (ECONNREFUSED, "ECONNREFUSED", "Connection refused"),
+];
+
+impl Errno {
+    /// 把系统调用的返回值转换成 Result，负数为错误码
+    pub fn result(ret: isize) -> Result<usize, Errno> {
+        if ret < 0 {
+            Err(Errno(-ret as i32))
+        } else {
+            Ok(ret as usize)
+        }
+    }
+
+    /// 与 result 相同，丢弃成功时的返回值
+    pub fn unit(ret: isize) -> Result<(), Errno> {
+        Self::result(ret).map(|_| ())
+    }
+
+    /// 错误码的名字，例如 "ENOENT"，未知的错误码返回 None
+    pub fn name(self) -> Option<&'static str> {
+        ERRNO_TABLE
+            .iter()
+            .find(|(errno, _, _)| *errno == self)
+            .map(|(_, name, _)| *name)
+    }
+}
+
+/// 错误码的描述，与 C 库的 strerror 相同
+pub fn strerror(errno: Errno) -> &'static str {
+    ERRNO_TABLE
+        .iter()
+        .find(|(e, _, _)| *e == errno)
+        .map_or("Unknown error", |(_, _, message)| *message)
+}
+
+impl fmt::Display for Errno {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(_) => f.write_str(strerror(*self)),
+            None => write!(f, "Unknown error {}", self.0),
+        }
+    }
+}
+
+impl fmt::Debug for Errno {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "Errno({})", self.0),
+        }
+    }
+}
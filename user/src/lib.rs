@@ -5,15 +5,22 @@
 
 #[macro_use]
 pub mod console;
+mod errno;
 mod lang_items;
+mod sync;
 mod syscall;
 
 #[macro_use]
 extern crate bitflags;
 extern crate alloc;
 
+use alloc::boxed::Box;
+use alloc::string::String;
 use alloc::vec::Vec;
 use buddy_system_allocator::LockedHeap;
+use core::sync::atomic::AtomicU32;
+pub use errno::*;
+pub use sync::{Condvar, Mutex};
 use syscall::*;
 
 const USER_HEAP_SIZE: usize = 16384;
@@ -92,8 +99,29 @@ fn clear_bss() {
     });
 }
 
-pub fn dup(fd: usize) -> isize {
-    sys_dup(fd)
+/// 直接发起编号为 id 的系统调用，返回内核的原始返回值，用于测试内核对非法参数的处理
+pub fn raw_syscall(id: usize, args: [usize; 6]) -> isize {
+    syscall6(id, args)
+}
+
+pub fn dup(fd: usize) -> Result<usize, Errno> {
+    Errno::result(sys_dup(fd))
+}
+
+/// fcntl 的命令：读取文件的访问模式和状态标志
+pub const F_GETFL: usize = 3;
+/// fcntl 的命令：设置文件的状态标志，目前只有 NONBLOCK 可以修改
+pub const F_SETFL: usize = 4;
+
+pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> Result<usize, Errno> {
+    Errno::result(sys_fcntl(fd, cmd, arg))
+}
+
+/// 打开或者关闭文件的非阻塞模式，会阻塞的读写返回 EAGAIN
+pub fn set_nonblock(fd: usize, nonblock: bool) -> Result<(), Errno> {
+    let mut flags = OpenFlags::from_bits_truncate(fcntl(fd, F_GETFL, 0)? as u32);
+    flags.set(OpenFlags::NONBLOCK, nonblock);
+    fcntl(fd, F_SETFL, flags.bits as usize).map(|_| ())
 }
 
 /// 对 syscall 模块中的 sys_exit, sys_write 进一步封装
@@ -101,8 +129,8 @@ pub fn exit(exit_code: i32) -> isize {
     sys_exit(exit_code)
 }
 
-pub fn write(fd: usize, buf: &[u8]) -> isize {
-    sys_write(fd, buf)
+pub fn write(fd: usize, buf: &[u8]) -> Result<usize, Errno> {
+    Errno::result(sys_write(fd, buf))
 }
 
 pub fn yield_() -> isize {
@@ -113,30 +141,30 @@ pub fn get_time() -> isize {
     sys_get_time()
 }
 
-/// 等待任意一个子进程结束
-pub fn wait(exit_code: &mut i32) -> isize {
+/// 等待任意一个子进程结束，返回它的 pid，没有子进程时返回 ECHILD
+pub fn wait(exit_code: &mut i32) -> Result<usize, Errno> {
     loop {
         // 传入的参数是 -1
-        match sys_waitpid(-1, exit_code as *mut _) {
-            // 等待的进程存在，但是尚未结束返回 -2
-            -2 => {
+        match Errno::result(sys_waitpid(-1, exit_code as *mut _)) {
+            // 等待的进程存在，但是尚未结束返回 EAGAIN
+            Err(EAGAIN) => {
                 yield_();
             }
-            // -1 or real pid
+            // ECHILD or real pid
             exit_pid => return exit_pid,
         }
     }
 }
 
 /// 等待一个进程标识符为 pid 的进程结束
-pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
+pub fn waitpid(pid: usize, exit_code: &mut i32) -> Result<usize, Errno> {
     loop {
-        match sys_waitpid(pid as isize, exit_code as *mut _) {
+        match Errno::result(sys_waitpid(pid as isize, exit_code as *mut _)) {
             // 要等待的子进程存在但是尚未退出
-            -2 => {
+            Err(EAGAIN) => {
                 yield_();
             } // 调用 yield_ 主动让出 cpu
-            // -1 or real pid
+            // ECHILD or real pid
             exit_pid => return exit_pid,
         }
     }
@@ -146,16 +174,40 @@ pub fn getpid() -> isize {
     sys_getpid()
 }
 
-pub fn fork() -> isize {
-    sys_fork()
+pub fn getuid() -> isize {
+    sys_getuid()
+}
+
+pub fn geteuid() -> isize {
+    sys_geteuid()
+}
+
+pub fn getgid() -> isize {
+    sys_getgid()
+}
+
+pub fn getegid() -> isize {
+    sys_getegid()
 }
 
-pub fn exec(path: &str, args: &[*const u8]) -> isize {
-    sys_exec(path, args)
+pub fn setuid(uid: usize) -> Result<(), Errno> {
+    Errno::unit(sys_setuid(uid))
 }
 
-pub fn read(fd: usize, buf: &mut [u8]) -> isize {
-    sys_read(fd, buf)
+pub fn setgid(gid: usize) -> Result<(), Errno> {
+    Errno::unit(sys_setgid(gid))
+}
+
+pub fn fork() -> Result<usize, Errno> {
+    Errno::result(sys_fork())
+}
+
+pub fn exec(path: &str, args: &[*const u8]) -> Result<usize, Errno> {
+    Errno::result(sys_exec(path, args))
+}
+
+pub fn read(fd: usize, buf: &mut [u8]) -> Result<usize, Errno> {
+    Errno::result(sys_read(fd, buf))
 }
 
 bitflags! {
@@ -172,26 +224,633 @@ bitflags! {
         const CREATE = 1 << 9;
         // 第 10 位被设置为 0，则在打开时候清空文件内容，并将文件大小归为0
         const TRUNC = 1 << 10;
+        // 第 11 位被设置，读写时不阻塞
+        const NONBLOCK = 1 << 11;
+    }
+}
+
+pub fn open(path: &str, flags: OpenFlags) -> Result<usize, Errno> {
+    Errno::result(sys_open(path, flags.bits))
+}
+
+pub fn mkfifo(path: &str, mode: u32) -> Result<(), Errno> {
+    Errno::unit(sys_mkfifo(path, mode))
+}
+
+pub fn close(fd: usize) -> Result<(), Errno> {
+    Errno::unit(sys_close(fd))
+}
+
+/// 读出整个文件的内容，文件不存在或者不是 UTF-8 文本时返回 None
+/// 与 open 不同，path 不需要以 \0 结尾
+pub fn read_to_string(path: &str) -> Option<String> {
+    let mut path = String::from(path.trim_end_matches('\0'));
+    path.push('\0');
+    let fd = open(path.as_str(), OpenFlags::READONLY).ok()?;
+    let mut content = Vec::new();
+    let mut buf = [0u8; 256];
+    loop {
+        match read(fd, &mut buf) {
+            Ok(size) if size > 0 => content.extend_from_slice(&buf[..size]),
+            _ => break,
+        }
+    }
+    close(fd).ok();
+    String::from_utf8(content).ok()
+}
+
+/// 从 /proc 下 `key: value` 格式的文件内容中取出 key 对应的值，去掉两端的空白
+pub fn proc_field<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v.trim())
+}
+
+bitflags! {
+    /// poll 关心和返回的事件
+    pub struct PollEvents: u16 {
+        const IN = 1 << 0;
+        const OUT = 1 << 2;
+        // 以下三种事件总是会返回，不需要在 events 中指定
+        const ERR = 1 << 3;
+        const HUP = 1 << 4;
+        const NVAL = 1 << 5;
+    }
+}
+
+/// poll 的一项参数，fd 为负数时忽略这一项
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PollFd {
+    pub fd: i32,
+    pub events: u16,
+    pub revents: u16,
+}
+
+impl PollFd {
+    pub fn new(fd: usize, events: PollEvents) -> Self {
+        Self {
+            fd: fd as i32,
+            events: events.bits(),
+            revents: 0,
+        }
+    }
+
+    /// poll 返回的就绪事件
+    pub fn revents(&self) -> PollEvents {
+        PollEvents::from_bits_truncate(self.revents)
     }
 }
 
-pub fn open(path: &str, flags: OpenFlags) -> isize {
-    sys_open(path, flags.bits)
+/// 等待 fds 中的任意一个文件就绪，返回就绪的数量，超时返回 0，timeout 为 None 时一直等待
+pub fn ppoll(fds: &mut [PollFd], timeout: Option<&TimeSpec>) -> Result<usize, Errno> {
+    Errno::result(sys_ppoll(fds, timeout))
+}
+
+/// 与 ppoll 相同，timeout_ms 为负数时一直等待
+pub fn poll(fds: &mut [PollFd], timeout_ms: isize) -> Result<usize, Errno> {
+    if timeout_ms < 0 {
+        return ppoll(fds, None);
+    }
+    let timeout = TimeSpec {
+        tv_sec: timeout_ms as usize / 1000,
+        tv_nsec: timeout_ms as usize % 1000 * 1_000_000,
+    };
+    ppoll(fds, Some(&timeout))
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeSpec {
+    pub tv_sec: usize,
+    pub tv_nsec: usize,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeVal {
+    pub tv_sec: usize,
+    pub tv_usec: usize,
 }
 
-pub fn close(fd: usize) -> isize {
-    sys_close(fd)
+/// 间隔定时器的设置，it_value 为 0 表示关闭定时器，it_interval 为 0 表示只触发一次
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ITimerVal {
+    pub it_interval: TimeVal,
+    pub it_value: TimeVal,
 }
 
+pub const CLOCK_REALTIME: usize = 0;
+pub const CLOCK_MONOTONIC: usize = 1;
+
+/// 真实时间，到期发送 SIGALRM
+pub const ITIMER_REAL: usize = 0;
+/// 用户态运行时间，到期发送 SIGVTALRM
+pub const ITIMER_VIRTUAL: usize = 1;
+/// 用户态和内核态运行时间，到期发送 SIGPROF
+pub const ITIMER_PROF: usize = 2;
+
+pub fn nanosleep(req: &TimeSpec, rem: Option<&mut TimeSpec>) -> Result<(), Errno> {
+    Errno::unit(sys_nanosleep(req, rem.map_or(core::ptr::null_mut(), |r| r)))
+}
+
+/// 阻塞睡眠 period_ms 毫秒
 pub fn sleep(period_ms: usize) {
-    let start = sys_get_time();
-    while sys_get_time() < start + period_ms as isize {
-        sys_yield();
+    usleep(period_ms * 1000);
+}
+
+/// 阻塞睡眠 period_us 微秒
+pub fn usleep(period_us: usize) {
+    let req = TimeSpec {
+        tv_sec: period_us / 1000000,
+        tv_nsec: period_us % 1000000 * 1000,
+    };
+    nanosleep(&req, None).ok();
+}
+
+pub fn clock_gettime(clock_id: usize, tp: &mut TimeSpec) -> Result<(), Errno> {
+    Errno::unit(sys_clock_gettime(clock_id, tp))
+}
+
+pub fn getitimer(which: usize, curr_value: &mut ITimerVal) -> Result<(), Errno> {
+    Errno::unit(sys_getitimer(which, curr_value))
+}
+
+pub fn setitimer(
+    which: usize,
+    new_value: &ITimerVal,
+    old_value: Option<&mut ITimerVal>,
+) -> Result<(), Errno> {
+    Errno::unit(sys_setitimer(
+        which,
+        new_value,
+        old_value.map_or(core::ptr::null_mut(), |v| v),
+    ))
+}
+
+/// times 返回的时间，以时钟计数表示，每秒 CLOCKS_PER_SEC 个
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tms {
+    pub tms_utime: usize,
+    pub tms_stime: usize,
+    pub tms_cutime: usize,
+    pub tms_cstime: usize,
+}
+
+pub const CLOCKS_PER_SEC: usize = 100;
+
+pub fn times(tms: &mut Tms) -> Result<usize, Errno> {
+    Errno::result(sys_times(tms))
+}
+
+pub const RLIM_INFINITY: usize = usize::MAX;
+/// CPU 时间，单位为秒
+pub const RLIMIT_CPU: usize = 0;
+pub const RLIMIT_STACK: usize = 3;
+pub const RLIMIT_NPROC: usize = 6;
+pub const RLIMIT_NOFILE: usize = 7;
+pub const RLIMIT_AS: usize = 9;
+pub const RLIMIT_NTHREAD: usize = 16;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RLimit {
+    /// 软限制
+    pub rlim_cur: usize,
+    /// 硬限制
+    pub rlim_max: usize,
+}
+
+pub fn getrlimit(resource: usize, rlim: &mut RLimit) -> Result<(), Errno> {
+    Errno::unit(sys_getrlimit(resource, rlim))
+}
+
+pub fn setrlimit(resource: usize, rlim: &RLimit) -> Result<(), Errno> {
+    Errno::unit(sys_setrlimit(resource, rlim))
+}
+
+pub const RUSAGE_SELF: isize = 0;
+/// 已经被 wait 回收的子进程
+pub const RUSAGE_CHILDREN: isize = -1;
+pub const RUSAGE_THREAD: isize = 1;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RUsage {
+    pub ru_utime: TimeVal,
+    pub ru_stime: TimeVal,
+    pub ru_minflt: usize,
+    pub ru_majflt: usize,
+    /// 主动让出处理器的次数
+    pub ru_nvcsw: usize,
+    /// 被抢占的次数
+    pub ru_nivcsw: usize,
+}
+
+pub fn getrusage(who: isize, rusage: &mut RUsage) -> Result<(), Errno> {
+    Errno::unit(sys_getrusage(who, rusage))
+}
+
+/// 处理器的使用情况，时间以微秒为单位
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuStat {
+    /// 启动以来的时间
+    pub uptime: usize,
+    /// 处理器运行线程或者内核代码的时间
+    pub busy: usize,
+    /// 处理器在空闲循环中等待中断的时间
+    pub idle: usize,
+    /// 就绪队列中等待运行的线程数
+    pub nr_ready: usize,
+}
+
+pub fn cpu_stat(stat: &mut CpuStat) -> Result<(), Errno> {
+    Errno::unit(sys_cpu_stat(stat))
+}
+
+/// seconds 秒后向当前进程发送 SIGALRM，为 0 时取消，返回之前设置的闹钟剩余的秒数
+pub fn alarm(seconds: usize) -> usize {
+    let new_value = ITimerVal {
+        it_value: TimeVal {
+            tv_sec: seconds,
+            tv_usec: 0,
+        },
+        ..Default::default()
+    };
+    let mut old_value = ITimerVal::default();
+    setitimer(ITIMER_REAL, &new_value, Some(&mut old_value)).ok();
+    // 不足一秒的剩余时间向上取整，避免把未到期的闹钟报告为 0
+    old_value.it_value.tv_sec + (old_value.it_value.tv_usec > 0) as usize
+}
+
+pub fn pipe(pipe_fd: &mut [usize]) -> Result<(), Errno> {
+    Errno::unit(sys_pipe(pipe_fd, 0))
+}
+
+/// 创建管道，flags 只能含有 OpenFlags::NONBLOCK
+pub fn pipe2(pipe_fd: &mut [usize], flags: OpenFlags) -> Result<(), Errno> {
+    Errno::unit(sys_pipe(pipe_fd, flags.bits))
+}
+
+/// 创建线程时的属性，字段为 0 时使用内核的默认值
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadAttr {
+    /// 用户栈大小，按页向上取整
+    pub stack_size: usize,
+    /// TLS 块大小，按页向上取整
+    pub tls_size: usize,
+    /// THREAD_DETACHED 等标志位
+    pub flags: usize,
+}
+
+/// 线程创建后即处于分离状态，退出时由内核回收，不能被 waittid 等待
+pub const THREAD_DETACHED: usize = 1 << 0;
+
+pub fn thread_create(entry: usize, arg: usize) -> Result<usize, Errno> {
+    Errno::result(sys_thread_create(entry, arg, core::ptr::null()))
+}
+
+pub fn thread_create_with_attr(
+    entry: usize,
+    arg: usize,
+    attr: &ThreadAttr,
+) -> Result<usize, Errno> {
+    Errno::result(sys_thread_create(entry, arg, attr as *const _))
+}
+
+pub fn gettid() -> isize {
+    sys_gettid()
+}
+
+/// 设置当前线程的优先级，数值越大越优先
+pub fn set_priority(priority: isize) -> Result<usize, Errno> {
+    Errno::result(sys_set_priority(priority))
+}
+
+/// 阻塞等待线程 tid 退出，返回它的退出码
+pub fn waittid(tid: usize) -> Result<i32, Errno> {
+    let mut exit_code = 0;
+    Errno::unit(sys_waittid(tid, &mut exit_code))?;
+    Ok(exit_code)
+}
+
+pub fn thread_detach(tid: usize) -> Result<(), Errno> {
+    Errno::unit(sys_thread_detach(tid))
+}
+
+/// 当前线程 TLS 块的起始地址，由内核放在 tp 寄存器中
+pub fn tls_base() -> usize {
+    let tp: usize;
+    unsafe {
+        core::arch::asm!("mv {}, tp", out(reg) tp);
     }
+    tp
+}
+
+type ThreadMain = Box<dyn FnOnce() -> i32 + Send + 'static>;
+
+/// 新线程的入口，取回 spawn 传入的闭包并执行，用闭包的返回值退出线程
+extern "C" fn thread_start(arg: usize) -> ! {
+    let f = unsafe { Box::from_raw(arg as *mut ThreadMain) };
+    exit(f());
+    panic!("thread exit");
+}
+
+/// 在新线程中运行闭包，闭包的返回值作为线程的退出码
+pub fn spawn<F>(f: F) -> Result<usize, Errno>
+where
+    F: FnOnce() -> i32 + Send + 'static,
+{
+    spawn_with_attr(f, &ThreadAttr::default())
+}
+
+/// 按照 attr 创建线程并在其中运行闭包
+pub fn spawn_with_attr<F>(f: F, attr: &ThreadAttr) -> Result<usize, Errno>
+where
+    F: FnOnce() -> i32 + Send + 'static,
+{
+    // 闭包是胖指针，需要再装一层 Box 才能通过一个寄存器传给新线程
+    let main: Box<ThreadMain> = Box::new(Box::new(f));
+    let arg = Box::into_raw(main) as usize;
+    let tid = thread_create_with_attr(thread_start as usize, arg, attr);
+    if tid.is_err() {
+        drop(unsafe { Box::from_raw(arg as *mut ThreadMain) });
+    }
+    tid
+}
+
+pub fn mutex_create() -> Result<usize, Errno> {
+    Errno::result(sys_mutex_create(false))
+}
+
+pub fn mutex_blocking_create() -> Result<usize, Errno> {
+    Errno::result(sys_mutex_create(true))
+}
+
+pub fn mutex_lock(mutex_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_mutex_lock(mutex_id))
+}
+
+pub fn mutex_unlock(mutex_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_mutex_unlock(mutex_id))
+}
+
+/// 锁已经被持有时返回 EBUSY
+pub fn mutex_trylock(mutex_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_mutex_trylock(mutex_id))
+}
+
+/// 最多等待 timeout，超时返回 ETIMEDOUT
+pub fn mutex_lock_timeout(mutex_id: usize, timeout: &TimeSpec) -> Result<(), Errno> {
+    Errno::unit(sys_mutex_lock_timeout(mutex_id, timeout))
+}
+
+/// 销毁互斥锁，阻塞在锁上的线程的加锁操作返回 EIDRM
+pub fn mutex_destroy(mutex_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_mutex_destroy(mutex_id))
+}
+
+pub fn semaphore_create(res_count: usize) -> Result<usize, Errno> {
+    Errno::result(sys_semaphore_create(res_count))
+}
+
+pub fn semaphore_up(sem_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_semaphore_up(sem_id))
+}
+
+pub fn semaphore_down(sem_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_semaphore_down(sem_id))
+}
+
+/// 没有可用资源时返回 EAGAIN
+pub fn semaphore_trydown(sem_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_semaphore_trydown(sem_id))
+}
+
+/// 最多等待 timeout，超时返回 ETIMEDOUT
+pub fn semaphore_down_timeout(sem_id: usize, timeout: &TimeSpec) -> Result<(), Errno> {
+    Errno::unit(sys_semaphore_down_timeout(sem_id, timeout))
+}
+
+/// 销毁信号量，阻塞在信号量上的线程的 P 操作返回 EIDRM
+pub fn semaphore_destroy(sem_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_semaphore_destroy(sem_id))
+}
+
+pub fn condvar_create() -> Result<usize, Errno> {
+    Errno::result(sys_condvar_create())
+}
+
+pub fn condvar_signal(condvar_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_condvar_signal(condvar_id))
+}
+
+pub fn condvar_wait(condvar_id: usize, mutex_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_condvar_wait(condvar_id, mutex_id))
+}
+
+/// 最多等待 timeout，超时返回 ETIMEDOUT，返回时都重新持有互斥锁
+pub fn condvar_wait_timeout(
+    condvar_id: usize,
+    mutex_id: usize,
+    timeout: &TimeSpec,
+) -> Result<(), Errno> {
+    Errno::unit(sys_condvar_wait_timeout(condvar_id, mutex_id, timeout))
+}
+
+/// 销毁条件变量，等待的线程重新持有互斥锁后返回 EIDRM
+pub fn condvar_destroy(condvar_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_condvar_destroy(condvar_id))
+}
+
+pub fn rwlock_create() -> Result<usize, Errno> {
+    Errno::result(sys_rwlock_create())
+}
+
+pub fn rwlock_read(rwlock_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_rwlock_read(rwlock_id))
+}
+
+pub fn rwlock_write(rwlock_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_rwlock_write(rwlock_id))
+}
+
+pub fn rwlock_unlock(rwlock_id: usize) -> Result<(), Errno> {
+    Errno::unit(sys_rwlock_unlock(rwlock_id))
+}
+
+pub fn barrier_create(count: usize) -> Result<usize, Errno> {
+    Errno::result(sys_barrier_create(count))
+}
+
+/// 最后到达屏障的线程返回 1，其他线程返回 0
+pub fn barrier_wait(barrier_id: usize) -> Result<usize, Errno> {
+    Errno::result(sys_barrier_wait(barrier_id))
+}
+
+pub fn trace(enable: bool) -> Result<(), Errno> {
+    Errno::unit(sys_trace(enable as usize))
+}
+
+/// 读出子进程 pid 的跟踪记录，子进程已经退出且记录读完时返回 Ok(0)，尚未退出时返回 EAGAIN
+pub fn trace_read(pid: usize, buf: &mut [u8]) -> Result<usize, Errno> {
+    Errno::result(sys_trace_read(pid, buf))
+}
+
+pub const FUTEX_WAIT: usize = 0;
+pub const FUTEX_WAKE: usize = 1;
+
+/// 如果 futex 的值仍然是 val 则阻塞，直到被唤醒或超时
+/// 被唤醒返回 0，值已经改变返回 EAGAIN，超时返回 ETIMEDOUT
+pub fn futex_wait(futex: &AtomicU32, val: u32, timeout: Option<&TimeSpec>) -> Result<(), Errno> {
+    Errno::unit(sys_futex(futex.as_ptr(), FUTEX_WAIT, val, timeout))
+}
+
+/// 唤醒最多 count 个等待在 futex 上的线程，返回唤醒的数量
+pub fn futex_wake(futex: &AtomicU32, count: u32) -> Result<usize, Errno> {
+    Errno::result(sys_futex(futex.as_ptr(), FUTEX_WAKE, count, None))
+}
+
+/// 消息队列的属性
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MqAttr {
+    /// 打开时的标志，只有 OpenFlags::NONBLOCK 有意义
+    pub flags: usize,
+    /// 最多容纳的消息数量
+    pub maxmsg: usize,
+    /// 单条消息的最大长度
+    pub msgsize: usize,
+    /// 当前的消息数量
+    pub curmsgs: usize,
+}
+
+/// 按名字打开消息队列，name 以 '/' 开头并以 \0 结尾，attr 为空时使用默认属性
+pub fn mq_open(name: &str, flags: OpenFlags, attr: Option<&MqAttr>) -> Result<usize, Errno> {
+    Errno::result(sys_mq_open(name, flags.bits, attr))
+}
+
+pub fn mq_unlink(name: &str) -> Result<(), Errno> {
+    Errno::unit(sys_mq_unlink(name))
+}
+
+/// 发送一条消息，队列已满时一直等待
+pub fn mq_send(fd: usize, msg: &[u8], prio: u32) -> Result<(), Errno> {
+    Errno::unit(sys_mq_timedsend(fd, msg, prio, None))
+}
+
+/// 最多等待 timeout，超时返回 ETIMEDOUT
+pub fn mq_timedsend(fd: usize, msg: &[u8], prio: u32, timeout: &TimeSpec) -> Result<(), Errno> {
+    Errno::unit(sys_mq_timedsend(fd, msg, prio, Some(timeout)))
+}
+
+/// 接收一条消息，返回消息的长度，队列为空时一直等待
+pub fn mq_receive(fd: usize, buf: &mut [u8], prio: Option<&mut u32>) -> Result<usize, Errno> {
+    Errno::result(sys_mq_timedreceive(fd, buf, prio, None))
+}
+
+/// 最多等待 timeout，超时返回 ETIMEDOUT
+pub fn mq_timedreceive(
+    fd: usize,
+    buf: &mut [u8],
+    prio: Option<&mut u32>,
+    timeout: &TimeSpec,
+) -> Result<usize, Errno> {
+    Errno::result(sys_mq_timedreceive(fd, buf, prio, Some(timeout)))
+}
+
+pub fn mq_getattr(fd: usize, attr: &mut MqAttr) -> Result<(), Errno> {
+    Errno::unit(sys_mq_getattr(fd, attr))
+}
+
+/// 本地套接字的地址族
+pub const AF_UNIX: usize = 1;
+/// 面向连接的字节流
+pub const SOCK_STREAM: usize = 1;
+/// 保留边界的数据报
+pub const SOCK_DGRAM: usize = 2;
+/// sendmsg 一次最多附带的文件描述符数量
+pub const SCM_MAX_FD: usize = 8;
+
+/// sendmsg 和 recvmsg 的参数
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MsgHdr {
+    /// 数据缓冲区的地址
+    pub buf: usize,
+    /// 数据缓冲区的长度
+    pub len: usize,
+    /// 文件描述符数组的地址
+    pub fds: usize,
+    /// 发送时为附带的描述符数量；接收时传入数组的容量，返回收到的数量
+    pub nfds: usize,
+}
+
+pub fn socket(domain: usize, type_: usize) -> Result<usize, Errno> {
+    Errno::result(sys_socket(domain, type_, 0))
+}
+
+/// 创建一对互相连接的套接字，两个文件描述符写入 sv
+pub fn socketpair(domain: usize, type_: usize, sv: &mut [usize; 2]) -> Result<(), Errno> {
+    Errno::unit(sys_socketpair(domain, type_, 0, sv))
+}
+
+/// 把套接字绑定到 path，path 以 \0 结尾
+pub fn bind(fd: usize, path: &str) -> Result<(), Errno> {
+    Errno::unit(sys_bind(fd, path))
+}
+
+pub fn listen(fd: usize, backlog: usize) -> Result<(), Errno> {
+    Errno::unit(sys_listen(fd, backlog))
+}
+
+pub fn accept(fd: usize) -> Result<usize, Errno> {
+    Errno::result(sys_accept(fd))
+}
+
+/// 连接到绑定在 path 上的套接字，path 以 \0 结尾
+pub fn connect(fd: usize, path: &str) -> Result<(), Errno> {
+    Errno::unit(sys_connect(fd, path))
+}
+
+/// 发送 buf 中的数据，并把 fds 中的文件描述符传给对端
+pub fn sendmsg(fd: usize, buf: &[u8], fds: &[usize]) -> Result<usize, Errno> {
+    let msg = MsgHdr {
+        buf: buf.as_ptr() as usize,
+        len: buf.len(),
+        fds: fds.as_ptr() as usize,
+        nfds: fds.len(),
+    };
+    Errno::result(sys_sendmsg(fd, &msg))
+}
+
+/// 接收数据写入 buf，收到的文件描述符写入 fds，数量写入 nfds
+pub fn recvmsg(
+    fd: usize,
+    buf: &mut [u8],
+    fds: &mut [usize],
+    nfds: &mut usize,
+) -> Result<usize, Errno> {
+    let mut msg = MsgHdr {
+        buf: buf.as_mut_ptr() as usize,
+        len: buf.len(),
+        fds: fds.as_mut_ptr() as usize,
+        nfds: fds.len(),
+    };
+    let len = Errno::result(sys_recvmsg(fd, &mut msg))?;
+    *nfds = msg.nfds;
+    Ok(len)
 }
 
-pub fn pipe(pipe_fd: &mut [usize]) -> isize {
-    sys_pipe(pipe_fd)
+/// 开启或关闭当前进程的死锁检测，之后会导致死锁的 mutex_lock/semaphore_down 返回 EDEADLK
+pub fn enable_deadlock_detect(enabled: bool) -> Result<(), Errno> {
+    Errno::unit(sys_enable_deadlock_detect(enabled as usize))
 }
 
 /// Action for a signal
@@ -281,24 +940,24 @@ bitflags! {
     }
 }
 
-pub fn kill(pid: usize, signum: i32) -> isize {
-    sys_kill(pid, signum)
+pub fn kill(pid: usize, signum: i32) -> Result<(), Errno> {
+    Errno::unit(sys_kill(pid, signum))
 }
 
 pub fn sigaction(
     signum: i32,
     action: Option<&SignalAction>,
     old_action: Option<&mut SignalAction>,
-) -> isize {
-    sys_sigaction(
+) -> Result<(), Errno> {
+    Errno::unit(sys_sigaction(
         signum,
         action.map_or(core::ptr::null(), |a| a),
         old_action.map_or(core::ptr::null_mut(), |a| a),
-    )
+    ))
 }
 
-pub fn sigprocmask(mask: u32) -> isize {
-    sys_sigprocmask(mask)
+pub fn sigprocmask(mask: u32) -> Result<usize, Errno> {
+    Errno::result(sys_sigprocmask(mask))
 }
 
 pub fn sigreturn() -> isize {
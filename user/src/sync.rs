@@ -0,0 +1,98 @@
+//! 基于 futex 的互斥锁和条件变量
+//!
+//! 没有竞争时只需要在用户态完成一次原子操作，只有需要阻塞或者唤醒其他线程时才进入内核
+
+use crate::{futex_wait, futex_wake, TimeSpec, ETIMEDOUT};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// 未加锁
+const UNLOCKED: u32 = 0;
+/// 已加锁，没有线程在等待
+const LOCKED: u32 = 1;
+/// 已加锁，可能有线程在等待，释放时需要唤醒
+const CONTENDED: u32 = 2;
+
+/// 互斥锁，可以放在静态变量中或者在线程间共享
+pub struct Mutex {
+    state: AtomicU32,
+}
+
+impl Mutex {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+        }
+    }
+
+    pub fn lock(&self) {
+        if self
+            .state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            return;
+        }
+        // 标记为有等待者后睡眠，被唤醒的线程不知道是否还有其他等待者，同样标记为 CONTENDED
+        while self.state.swap(CONTENDED, Ordering::Acquire) != UNLOCKED {
+            futex_wait(&self.state, CONTENDED, None).ok();
+        }
+    }
+
+    pub fn unlock(&self) {
+        if self.state.swap(UNLOCKED, Ordering::Release) == CONTENDED {
+            futex_wake(&self.state, 1).ok();
+        }
+    }
+}
+
+impl Default for Mutex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 条件变量，每次通知都递增序号，等待者睡眠前记下序号，从而不会错过释放锁之后到来的通知
+pub struct Condvar {
+    seq: AtomicU32,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    /// 释放 mutex 并等待通知，返回前重新持有 mutex，可能出现虚假唤醒
+    pub fn wait(&self, mutex: &Mutex) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        mutex.unlock();
+        futex_wait(&self.seq, seq, None).ok();
+        mutex.lock();
+    }
+
+    /// 与 wait 相同，最多等待 timeout，超时返回 false
+    pub fn wait_timeout(&self, mutex: &Mutex, timeout: &TimeSpec) -> bool {
+        let seq = self.seq.load(Ordering::Relaxed);
+        mutex.unlock();
+        let ret = futex_wait(&self.seq, seq, Some(timeout));
+        mutex.lock();
+        ret != Err(ETIMEDOUT)
+    }
+
+    pub fn notify_one(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        futex_wake(&self.seq, 1).ok();
+    }
+
+    pub fn notify_all(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        futex_wake(&self.seq, i32::MAX as u32).ok();
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
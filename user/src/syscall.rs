@@ -1,37 +1,91 @@
-use crate::SignalAction;
+use crate::{
+    CpuStat, ITimerVal, MqAttr, MsgHdr, PollFd, RLimit, RUsage, SignalAction, ThreadAttr, TimeSpec,
+    Tms,
+};
 use core::arch::asm;
 
 const SYSCALL_DUP: usize = 24;
+const SYSCALL_FCNTL: usize = 25;
+const SYSCALL_MKFIFO: usize = 33;
 const SYSCALL_OPEN: usize = 56;
 const SYSCALL_CLOSE: usize = 57;
 const SYSCALL_PIPE: usize = 59;
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_PPOLL: usize = 73;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_NANOSLEEP: usize = 101;
+const SYSCALL_GETITIMER: usize = 102;
+const SYSCALL_SETITIMER: usize = 103;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
 const SYSCALL_YIELD: usize = 124;
 const SYSCALL_KILL: usize = 129;
 const SYSCALL_SIGACTION: usize = 134;
 const SYSCALL_SIGPROCMASK: usize = 135;
 const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_SETGID: usize = 144;
+const SYSCALL_SETUID: usize = 146;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_GETRLIMIT: usize = 163;
+const SYSCALL_SETRLIMIT: usize = 164;
+const SYSCALL_GETRUSAGE: usize = 165;
 const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_GETPID: usize = 172;
+const SYSCALL_GETUID: usize = 174;
+const SYSCALL_GETEUID: usize = 175;
+const SYSCALL_GETGID: usize = 176;
+const SYSCALL_GETEGID: usize = 177;
+const SYSCALL_MQ_OPEN: usize = 180;
+const SYSCALL_MQ_UNLINK: usize = 181;
+const SYSCALL_MQ_TIMEDSEND: usize = 182;
+const SYSCALL_MQ_TIMEDRECEIVE: usize = 183;
+const SYSCALL_MQ_GETATTR: usize = 185;
+const SYSCALL_SOCKET: usize = 198;
+const SYSCALL_SOCKETPAIR: usize = 199;
+const SYSCALL_BIND: usize = 200;
+const SYSCALL_LISTEN: usize = 201;
+const SYSCALL_ACCEPT: usize = 202;
+const SYSCALL_CONNECT: usize = 203;
+const SYSCALL_SENDMSG: usize = 211;
+const SYSCALL_RECVMSG: usize = 212;
 const SYSCALL_FORK: usize = 220;
 const SYSCALL_EXEC: usize = 221;
 const SYSCALL_WAITPID: usize = 260;
 const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_CPU_STAT: usize = 411;
+const SYSCALL_ENABLE_DEADLOCK_DETECT: usize = 469;
 
 const SYSCALL_THREAD_CREATE: usize = 1000;
 const SYSCALL_GETTID: usize = 1001;
 const SYSCALL_WAITTID: usize = 1002;
+const SYSCALL_THREAD_DETACH: usize = 1003;
 const SYSCALL_MUTEX_CREATE: usize = 1010;
 const SYSCALL_MUTEX_LOCK: usize = 1011;
 const SYSCALL_MUTEX_UNLOCK: usize = 1012;
+const SYSCALL_MUTEX_TRYLOCK: usize = 1013;
+const SYSCALL_MUTEX_LOCK_TIMEOUT: usize = 1014;
+const SYSCALL_MUTEX_DESTROY: usize = 1015;
 const SYSCALL_SEMAPHORE_CREATE: usize = 1020;
 const SYSCALL_SEMAPHORE_UP: usize = 1021;
 const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
+const SYSCALL_SEMAPHORE_TRYDOWN: usize = 1023;
+const SYSCALL_SEMAPHORE_DOWN_TIMEOUT: usize = 1024;
+const SYSCALL_SEMAPHORE_DESTROY: usize = 1025;
 const SYSCALL_CONDVAR_CREATE: usize = 1030;
 const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
 const SYSCALL_CONDVAR_WAIT: usize = 1032;
+const SYSCALL_CONDVAR_WAIT_TIMEOUT: usize = 1033;
+const SYSCALL_CONDVAR_DESTROY: usize = 1034;
+const SYSCALL_RWLOCK_CREATE: usize = 1040;
+const SYSCALL_RWLOCK_READ: usize = 1041;
+const SYSCALL_RWLOCK_WRITE: usize = 1042;
+const SYSCALL_RWLOCK_UNLOCK: usize = 1043;
+const SYSCALL_BARRIER_CREATE: usize = 1050;
+const SYSCALL_BARRIER_WAIT: usize = 1051;
+const SYSCALL_TRACE: usize = 1060;
+const SYSCALL_TRACE_READ: usize = 1061;
 const SYSCALL_FRAMEBUFFER: usize = 2000;
 const SYSCALL_FRAMEBUFFER_FLUSH: usize = 2001;
 const SYSCALL_EVENT_GET: usize = 3000;
@@ -63,22 +117,65 @@ fn syscall(id: usize, args: [usize; 3]) -> isize {
     ret
 }
 
+/// 功能: 与 syscall 相同，用于需要超过三个参数的系统调用
+/// 参数: 'args' 六个参数，依次放在 a0~a5 寄存器中
+pub fn syscall6(id: usize, args: [usize; 6]) -> isize {
+    let mut ret: isize;
+    unsafe {
+        asm!(
+            "ecall",
+            inlateout("x10") args[0] => ret,
+            in("x11") args[1],
+            in("x12") args[2],
+            in("x13") args[3],
+            in("x14") args[4],
+            in("x15") args[5],
+            in("x17") id
+        );
+    }
+    ret
+}
+
 pub fn sys_dup(fd: usize) -> isize {
     syscall(SYSCALL_DUP, [fd, 0, 0])
 }
 
+/// 功能: 读取或者修改打开的文件的标志
+/// 参数: cmd 为 F_GETFL 或 F_SETFL，F_SETFL 时 arg 为新的标志，目前只有 NONBLOCK 可以修改
+/// 返回值: F_GETFL 返回访问模式和 NONBLOCK，F_SETFL 返回 0，fd 不存在返回 -EBADF，cmd 不支持返回 -EINVAL
+/// syscall ID: 25
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    syscall(SYSCALL_FCNTL, [fd, cmd, arg])
+}
+
 /// 功能： 将内存中缓冲区中的数据写入文件
 /// 参数： 'fd' 表示要写入的文件描述符
 ///        'buf' 表示内存中缓冲区的起始地址 &[u8] 切片类型用来描述缓冲区，这是一个
 ///              胖指针，里面包含缓冲区的齐始地址，长度，通过 as_ptr, len 取出他们作为独立的系统参
 ///              数调用。
 ///        'len' 表示内存中缓冲区的长度
-/// 返回值： 返回成功写入的长度
+/// 返回值： 返回成功写入的长度，出错返回负的错误码，读端已经关闭时返回 -EPIPE
 /// syscall ID: 64
 pub fn sys_write(fd: usize, buffer: &[u8]) -> isize {
     syscall(SYSCALL_WRITE, [fd, buffer.as_ptr() as usize, buffer.len()])
 }
 
+/// 功能: 等待一组文件描述符中的任意一个就绪
+/// 参数: fds 中每一项的 events 为关心的事件，就绪的事件写回 revents，
+///       timeout 为最长的等待时间，为空表示一直等待
+/// 返回值: 返回就绪的文件描述符数量，超时返回 0，参数非法返回 -EINVAL，被信号打断返回 -EINTR
+/// syscall ID: 73
+pub fn sys_ppoll(fds: &mut [PollFd], timeout: Option<&TimeSpec>) -> isize {
+    syscall(
+        SYSCALL_PPOLL,
+        [
+            fds.as_mut_ptr() as usize,
+            fds.len(),
+            timeout.map_or(0, |timeout| timeout as *const _ as usize),
+        ],
+    )
+}
+
 /// 功能: 退出应用程序并将返回值告知批处理系统
 /// 参数: 'exit_code' 表示应用程序的返回值
 /// 返回值: 该系统调用不应该返回
@@ -101,13 +198,118 @@ pub fn sys_get_time() -> isize {
     syscall(SYSCALL_GET_TIME, [0, 0, 0])
 }
 
+/// 功能: 阻塞睡眠 req 指定的时间
+/// 参数: rem 不为空时，被信号提前唤醒会写入剩余的时间
+/// 返回值: 睡眠结束返回 0，被提前唤醒返回 -EINTR，参数非法返回 -EINVAL
+/// syscall ID: 101
+/// 功能: 在用户态的 32 位字上等待或唤醒
+/// 参数: uaddr 为 4 字节对齐的用户字的地址，op 为 FUTEX_WAIT 或 FUTEX_WAKE，
+///       FUTEX_WAIT 时 val 为期望的值，timeout 为等待的相对时间，为空表示一直等待；
+///       FUTEX_WAKE 时 val 为最多唤醒的线程数
+/// 返回值: FUTEX_WAIT 被唤醒返回 0，值不相等返回 -EAGAIN，超时返回 -ETIMEDOUT；
+///         FUTEX_WAKE 返回唤醒的线程数；uaddr 没有对齐返回 -EINVAL，没有被映射返回 -EFAULT
+/// syscall ID: 98
+pub fn sys_futex(uaddr: *const u32, op: usize, val: u32, timeout: Option<&TimeSpec>) -> isize {
+    syscall6(
+        SYSCALL_FUTEX,
+        [
+            uaddr as usize,
+            op,
+            val as usize,
+            timeout.map_or(0, |timeout| timeout as *const _ as usize),
+            0,
+            0,
+        ],
+    )
+}
+
+pub fn sys_nanosleep(req: &TimeSpec, rem: *mut TimeSpec) -> isize {
+    syscall(
+        SYSCALL_NANOSLEEP,
+        [req as *const _ as usize, rem as usize, 0],
+    )
+}
+
+/// 功能: 读取间隔定时器 which 的剩余时间和周期
+/// 返回值: 成功返回 0，which 非法返回 -EINVAL
+/// syscall ID: 102
+pub fn sys_getitimer(which: usize, curr_value: &mut ITimerVal) -> isize {
+    syscall(SYSCALL_GETITIMER, [which, curr_value as *mut _ as usize, 0])
+}
+
+/// 功能: 设置间隔定时器 which，old_value 不为空时写入原来的设置
+/// 返回值: 成功返回 0，参数非法返回 -EINVAL
+/// syscall ID: 103
+pub fn sys_setitimer(which: usize, new_value: &ITimerVal, old_value: *mut ITimerVal) -> isize {
+    syscall(
+        SYSCALL_SETITIMER,
+        [which, new_value as *const _ as usize, old_value as usize],
+    )
+}
+
+/// 功能: 读取时钟 clock_id 的当前时间，精确到纳秒
+/// 返回值: 成功返回 0，时钟不存在返回 -EINVAL
+/// syscall ID: 113
+pub fn sys_clock_gettime(clock_id: usize, tp: &mut TimeSpec) -> isize {
+    syscall(SYSCALL_CLOCK_GETTIME, [clock_id, tp as *mut _ as usize, 0])
+}
+
 #[allow(unused)]
 pub fn sys_get_task_info() -> isize {
     syscall(SYSCALL_TASK_INFO, [0, 0, 0])
 }
 
+/// 功能: 获取当前进程以及已回收的子进程在用户态和内核态运行的时间
+/// 返回值: 启动以来的时钟计数，每秒 100 个
+/// syscall ID: 153
+pub fn sys_times(tms: &mut Tms) -> isize {
+    syscall(SYSCALL_TIMES, [tms as *mut _ as usize, 0, 0])
+}
+
+/// 功能: 获取当前进程的资源限制
+/// 参数: resource 为 RLIMIT_* 之一
+/// 返回值: 成功返回 0，resource 非法返回 -EINVAL
+/// syscall ID: 163
+pub fn sys_getrlimit(resource: usize, rlim: &mut RLimit) -> isize {
+    syscall(SYSCALL_GETRLIMIT, [resource, rlim as *mut _ as usize, 0])
+}
+
+/// 功能: 设置当前进程的资源限制，fork 出的子进程继承这些限制
+/// 参数: resource 为 RLIMIT_* 之一，软限制不能超过硬限制，硬限制不能提高
+/// 返回值: 成功返回 0，参数非法返回 -EINVAL，提高硬限制返回 -EPERM
+/// syscall ID: 164
+pub fn sys_setrlimit(resource: usize, rlim: &RLimit) -> isize {
+    syscall(SYSCALL_SETRLIMIT, [resource, rlim as *const _ as usize, 0])
+}
+
+/// 功能: 获取资源使用统计
+/// 参数: who 为 RUSAGE_SELF、RUSAGE_CHILDREN 或 RUSAGE_THREAD
+/// 返回值: 成功返回 0，who 非法返回 -EINVAL
+/// syscall ID: 165
+pub fn sys_getrusage(who: isize, rusage: &mut RUsage) -> isize {
+    syscall(
+        SYSCALL_GETRUSAGE,
+        [who as usize, rusage as *mut _ as usize, 0],
+    )
+}
+
+/// 功能: 获取处理器的运行和空闲时间
+/// 返回值: 成功返回 0
+/// syscall ID: 411
+pub fn sys_cpu_stat(stat: &mut CpuStat) -> isize {
+    syscall(SYSCALL_CPU_STAT, [stat as *mut _ as usize, 0, 0])
+}
+
+/// 功能: 开启或关闭当前进程对互斥锁和信号量的死锁检测
+/// 参数: enabled 为 1 表示开启，为 0 表示关闭
+/// 返回值: 成功返回 0，enabled 非法返回 -EINVAL
+/// syscall ID: 469
+pub fn sys_enable_deadlock_detect(enabled: usize) -> isize {
+    syscall(SYSCALL_ENABLE_DEADLOCK_DETECT, [enabled, 0, 0])
+}
+
 /// 功能: 从当前进程 fork 出一个子进程来
-/// 返回值: 对于子进程方会0, 对于当前进程返回子进程 PID
+/// 返回值: 对于子进程方会0, 对于当前进程返回子进程 PID，子进程数量达到上限返回 -EAGAIN
 /// syscall ID: 220
 pub fn sys_fork() -> isize {
     syscall(SYSCALL_FORK, [0, 0, 0])
@@ -118,10 +320,183 @@ pub fn sys_getpid() -> isize {
     syscall(SYSCALL_GETPID, [0, 0, 0])
 }
 
+/// 功能: 获取当前进程的实际用户 id
+/// syscall ID: 174
+pub fn sys_getuid() -> isize {
+    syscall(SYSCALL_GETUID, [0, 0, 0])
+}
+
+/// 功能: 获取当前进程的有效用户 id，文件权限按有效用户 id 检查
+/// syscall ID: 175
+pub fn sys_geteuid() -> isize {
+    syscall(SYSCALL_GETEUID, [0, 0, 0])
+}
+
+/// 功能: 获取当前进程的实际组 id
+/// syscall ID: 176
+pub fn sys_getgid() -> isize {
+    syscall(SYSCALL_GETGID, [0, 0, 0])
+}
+
+/// 功能: 获取当前进程的有效组 id
+/// syscall ID: 177
+pub fn sys_getegid() -> isize {
+    syscall(SYSCALL_GETEGID, [0, 0, 0])
+}
+
+/// 功能: 按名字打开消息队列
+/// 参数: name 以 '/' 开头并以 \0 结尾，flags 为 OpenFlags，含有 CREATE 时在队列不存在时创建，
+///       attr 为创建时的容量和消息长度，为空时使用默认值
+/// 返回值: 成功返回文件描述符，名字、标志或者属性非法返回 -EINVAL，队列不存在且不允许创建时返回 -ENOENT
+/// syscall ID: 180
+pub fn sys_mq_open(name: &str, flags: u32, attr: Option<&MqAttr>) -> isize {
+    syscall(
+        SYSCALL_MQ_OPEN,
+        [
+            name.as_ptr() as usize,
+            flags as usize,
+            attr.map_or(0, |attr| attr as *const _ as usize),
+        ],
+    )
+}
+
+/// 功能: 删除消息队列的名字，已经打开的描述符仍然可用
+/// 返回值: 成功返回 0，名字不存在返回 -ENOENT
+/// syscall ID: 181
+pub fn sys_mq_unlink(name: &str) -> isize {
+    syscall(SYSCALL_MQ_UNLINK, [name.as_ptr() as usize, 0, 0])
+}
+
+/// 功能: 发送一条消息，队列已满时阻塞
+/// 参数: prio 为消息的优先级，timeout 为最长的等待时间，为空表示一直等待
+/// 返回值: 成功返回 0，fd 不可写返回 -EBADF，消息过长返回 -EMSGSIZE，参数非法返回 -EINVAL，
+///         非阻塞模式下队列已满返回 -EAGAIN，超时返回 -ETIMEDOUT
+/// syscall ID: 182
+pub fn sys_mq_timedsend(fd: usize, msg: &[u8], prio: u32, timeout: Option<&TimeSpec>) -> isize {
+    syscall6(
+        SYSCALL_MQ_TIMEDSEND,
+        [
+            fd,
+            msg.as_ptr() as usize,
+            msg.len(),
+            prio as usize,
+            timeout.map_or(0, |timeout| timeout as *const _ as usize),
+            0,
+        ],
+    )
+}
+
+/// 功能: 接收优先级最高的一条消息，队列为空时阻塞
+/// 参数: buf 的长度不能小于消息的最大长度，prio 用于保存消息的优先级，
+///       timeout 为最长的等待时间，为空表示一直等待
+/// 返回值: 成功返回消息的长度，fd 不可读返回 -EBADF，buf 过短返回 -EMSGSIZE，参数非法返回 -EINVAL，
+///         非阻塞模式下队列为空返回 -EAGAIN，超时返回 -ETIMEDOUT
+/// syscall ID: 183
+pub fn sys_mq_timedreceive(
+    fd: usize,
+    buf: &mut [u8],
+    prio: Option<&mut u32>,
+    timeout: Option<&TimeSpec>,
+) -> isize {
+    syscall6(
+        SYSCALL_MQ_TIMEDRECEIVE,
+        [
+            fd,
+            buf.as_mut_ptr() as usize,
+            buf.len(),
+            prio.map_or(0, |prio| prio as *mut _ as usize),
+            timeout.map_or(0, |timeout| timeout as *const _ as usize),
+            0,
+        ],
+    )
+}
+
+/// 功能: 获取消息队列的属性
+/// 返回值: 成功返回 0，fd 不是消息队列返回 -EBADF
+/// syscall ID: 185
+pub fn sys_mq_getattr(fd: usize, attr: &mut MqAttr) -> isize {
+    syscall(SYSCALL_MQ_GETATTR, [fd, attr as *mut _ as usize, 0])
+}
+
+/// 功能: 创建一个套接字
+/// 参数: domain 只支持 AF_UNIX，type_ 为 SOCK_STREAM 或 SOCK_DGRAM，protocol 必须为 0
+/// 返回值: 成功返回文件描述符，地址族不支持返回 -EAFNOSUPPORT，类型非法返回 -EINVAL
+/// syscall ID: 198
+pub fn sys_socket(domain: usize, type_: usize, protocol: usize) -> isize {
+    syscall(SYSCALL_SOCKET, [domain, type_, protocol])
+}
+
+/// 功能: 创建一对互相连接的套接字，两个文件描述符写入 sv
+/// 返回值: 成功返回 0，参数非法返回 -EINVAL
+/// syscall ID: 199
+pub fn sys_socketpair(domain: usize, type_: usize, protocol: usize, sv: &mut [usize]) -> isize {
+    syscall6(
+        SYSCALL_SOCKETPAIR,
+        [domain, type_, protocol, sv.as_mut_ptr() as usize, 0, 0],
+    )
+}
+
+/// 功能: 把套接字绑定到根目录下的 path，path 以 \0 结尾
+/// 返回值: 成功返回 0，套接字已经绑定返回 -EINVAL，path 正在使用返回 -EADDRINUSE，没有权限返回 -EACCES
+/// syscall ID: 200
+pub fn sys_bind(fd: usize, path: &str) -> isize {
+    syscall(SYSCALL_BIND, [fd, path.as_ptr() as usize, 0])
+}
+
+/// 功能: 开始在已经绑定的流式套接字上接受连接
+/// 返回值: 成功返回 0，数据报套接字返回 -EOPNOTSUPP，没有绑定或者已经连接返回 -EINVAL
+/// syscall ID: 201
+pub fn sys_listen(fd: usize, backlog: usize) -> isize {
+    syscall(SYSCALL_LISTEN, [fd, backlog, 0])
+}
+
+/// 功能: 接受一个连接，没有连接时阻塞
+/// 返回值: 成功返回新连接的文件描述符，fd 不是正在监听的套接字返回 -EINVAL
+/// syscall ID: 202
+pub fn sys_accept(fd: usize) -> isize {
+    syscall(SYSCALL_ACCEPT, [fd, 0, 0])
+}
+
+/// 功能: 连接到绑定在 path 上的套接字，path 以 \0 结尾
+/// 返回值: 成功返回 0，对方不存在返回 -ENOENT，类型不同返回 -EPROTOTYPE，
+///         没有在监听返回 -ECONNREFUSED，backlog 已满返回 -EAGAIN
+/// syscall ID: 203
+pub fn sys_connect(fd: usize, path: &str) -> isize {
+    syscall(SYSCALL_CONNECT, [fd, path.as_ptr() as usize, 0])
+}
+
+/// 功能: 发送数据并附带文件描述符
+/// 返回值: 成功返回发送的字节数，没有连接返回 -ENOTCONN，对端已经关闭返回 -EPIPE
+/// syscall ID: 211
+pub fn sys_sendmsg(fd: usize, msg: &MsgHdr) -> isize {
+    syscall(SYSCALL_SENDMSG, [fd, msg as *const _ as usize, 0])
+}
+
+/// 功能: 接收数据和附带的文件描述符，收到的描述符数量写回 msg.nfds
+/// 返回值: 成功返回读取的字节数，对端已经关闭返回 0，没有连接返回 -ENOTCONN
+/// syscall ID: 212
+pub fn sys_recvmsg(fd: usize, msg: &mut MsgHdr) -> isize {
+    syscall(SYSCALL_RECVMSG, [fd, msg as *mut _ as usize, 0])
+}
+
+/// 功能: 设置用户 id，root 同时设置实际和有效用户 id，普通用户只能把有效用户 id 设为实际用户 id
+/// 返回值: 成功返回 0，没有权限返回 -EPERM
+/// syscall ID: 146
+pub fn sys_setuid(uid: usize) -> isize {
+    syscall(SYSCALL_SETUID, [uid, 0, 0])
+}
+
+/// 功能: 设置组 id，规则与 sys_setuid 相同
+/// 返回值: 成功返回 0，没有权限返回 -EPERM
+/// syscall ID: 144
+pub fn sys_setgid(gid: usize) -> isize {
+    syscall(SYSCALL_SETGID, [gid, 0, 0])
+}
+
 /// 功能: 将当前进程的地址空间清空，并加载一个特定的可执行文件，返回用户态之后开始执行他
 /// 参数: path 给出了要加载的可执行文件的名
 /// 参数: args 数组总每一个元素都是命令行字符串的起始地址
-/// 返回值: 如果出错的话（如果找不到名字相符的可执行文件）则返回 -1, 否则不应该返回
+/// 返回值: 找不到名字相符的可执行文件返回 -ENOENT，没有执行权限返回 -EACCES，否则不应该返回
 /// syscall ID: 221
 pub fn sys_exec(path: &str, args: &[*const u8]) -> isize {
     syscall(
@@ -133,7 +508,7 @@ pub fn sys_exec(path: &str, args: &[*const u8]) -> isize {
 /// 功能: 当前进程等待一个子进程变为僵尸进程，回收其全部资源并收集其返回值
 /// 参数: pid 表示要等待的子进程的进程id，如果返回 -1 表示等待任意一个子进程
 /// exit_code 表示保存子进程返回值的地址，如果该值为 0 表示不必保存
-/// 返回值: 如果等待的子进程不存在则返回 -1; 否则如果等待的子进程均未结束则返回 -2
+/// 返回值: 如果等待的子进程不存在则返回 -ECHILD; 否则如果等待的子进程均未结束则返回 -EAGAIN
 /// 否则返回结束子进程的进程 pid
 /// syscall id: 260
 pub fn sys_waitpid(pid: isize, exit_code: *mut i32) -> isize {
@@ -142,7 +517,7 @@ pub fn sys_waitpid(pid: isize, exit_code: *mut i32) -> isize {
 
 /// 功能: 从文件中读出一段内容到缓冲区
 /// 参数: fd 带读取文件的文件描述符，buffer 切片给出的缓冲区
-/// 返回值: 如果出现错误返回 -1, 否则返回实际读到的字节数
+/// 返回值: 如果出现错误返回负的错误码, 否则返回实际读到的字节数
 /// syscall id: 63
 pub fn sys_read(fd: usize, buffer: &mut [u8]) -> isize {
     syscall(
@@ -151,10 +526,19 @@ pub fn sys_read(fd: usize, buffer: &mut [u8]) -> isize {
     )
 }
 
+/// 功能: 在根目录下创建一个命名管道，之后可以用 open 打开它的读端或写端
+/// 参数: path 描述命名管道的名字，mode 为它的权限位
+/// 返回值: 成功返回 0，同名文件已经存在返回 -EEXIST，没有根目录的写权限返回 -EACCES
+/// syscall id: 33
+pub fn sys_mkfifo(path: &str, mode: u32) -> isize {
+    syscall(SYSCALL_MKFIFO, [path.as_ptr() as usize, mode as usize, 0])
+}
+
 /// 功能: 打开一个文件，并返回可以访问它的文件描述符
 /// 参数: path 描述要打开的文件名（简单起见，文件系统不需要支持目录，所有文件都放在根目录下）
 /// flags: 描述打开文件的标志
-/// 返回值: 如果出现了错误则返回 -1，否则返回打开文件的文件描述符。可能的错误原因：文件不存在
+/// 返回值: 如果出现了错误则返回负的错误码，否则返回打开文件的文件描述符。可能的错误：文件不存在 -ENOENT，
+/// 没有权限 -EACCES
 /// syscall id: 56
 pub fn sys_open(path: &str, flags: u32) -> isize {
     syscall(SYSCALL_OPEN, [path.as_ptr() as usize, flags as usize, 0])
@@ -162,7 +546,7 @@ pub fn sys_open(path: &str, flags: u32) -> isize {
 
 /// 功能: 当前进程关闭一个文件
 /// 参数: fd 表示当前文件的文件描述符
-/// 返回值: 如果是 0 成功关闭，-1 关闭失败。可能错误的原因：传入的文件描述符不是一个对应的打开文件
+/// 返回值: 如果是 0 成功关闭，传入的文件描述符不是一个对应的打开文件时返回 -EBADF
 /// syscall id: 57
 pub fn sys_close(fd: usize) -> isize {
     syscall(SYSCALL_CLOSE, [fd, 0, 0])
@@ -171,10 +555,14 @@ pub fn sys_close(fd: usize) -> isize {
 /// 功能：为当前进程打开一个管道。
 /// 参数：pipe 表示应用地址空间中的一个长度为 2 的 usize 数组的起始地址，内核需要按顺序将管道读端
 /// 和写端的文件描述符写入到数组中。
-/// 返回值：如果出现了错误则返回 -1，否则返回 0 。可能的错误原因是：传入的地址不合法。
+/// flags 只能含有 NONBLOCK，此时管道的两端都处于非阻塞模式。
+/// 返回值：如果出现了错误则返回负的错误码，否则返回 0 。可能的错误：flags 非法 -EINVAL，没有空闲的文件描述符 -EMFILE。
 /// syscall ID：59
-pub fn sys_pipe(pipe: &mut [usize]) -> isize {
-    syscall(SYSCALL_PIPE, [pipe.as_mut_ptr() as usize, 0, 0])
+pub fn sys_pipe(pipe: &mut [usize], flags: u32) -> isize {
+    syscall(
+        SYSCALL_PIPE,
+        [pipe.as_mut_ptr() as usize, flags as usize, 0],
+    )
 }
 
 pub fn sys_kill(pid: usize, signal: i32) -> isize {
@@ -210,9 +598,20 @@ pub fn sys_sigreturn() -> isize {
     syscall(SYSCALL_SIGRETURN, [0, 0, 0])
 }
 
-/// 创建线程
-pub fn sys_thread_create(enrty: usize, arg: usize) -> isize {
-    syscall(SYSCALL_THREAD_CREATE, [enrty, arg, 0])
+/// 功能: 设置当前线程的优先级，数值越大越优先，默认为 16
+/// 参数: priority 为新的优先级，不能小于 2
+/// 返回值: 优先级非法返回 -EINVAL，否则返回设置的优先级
+/// syscall ID: 140
+pub fn sys_set_priority(priority: isize) -> isize {
+    syscall(SYSCALL_SET_PRIORITY, [priority as usize, 0, 0])
+}
+
+/// 功能: 在当前进程中创建一个线程，从 entry 开始执行，arg 通过 a0 传给线程
+/// 参数: attr 指定用户栈大小、TLS 块大小以及是否分离，为空指针时使用默认属性
+/// 返回值: 属性非法返回 -EINVAL，超过资源限制返回 -EAGAIN，否则返回新线程的 tid
+/// syscall ID: 1000
+pub fn sys_thread_create(enrty: usize, arg: usize, attr: *const ThreadAttr) -> isize {
+    syscall(SYSCALL_THREAD_CREATE, [enrty, arg, attr as usize])
 }
 
 /// 获取线程id
@@ -220,9 +619,18 @@ pub fn sys_gettid() -> isize {
     syscall(SYSCALL_GETTID, [0, 0, 0])
 }
 
-/// 获取等待线程id
-pub fn sys_waittid(tid: usize) -> isize {
-    syscall(SYSCALL_WAITTID, [tid, 0, 0])
+/// 功能: 阻塞等待同一进程中的线程 tid 退出并回收它，退出码写入 exit_code
+/// 返回值: 成功返回 0，等待自身返回 -EDEADLK，线程不存在返回 -ESRCH，线程已分离返回 -EINVAL
+/// syscall ID: 1002
+pub fn sys_waittid(tid: usize, exit_code: *mut i32) -> isize {
+    syscall(SYSCALL_WAITTID, [tid, exit_code as usize, 0])
+}
+
+/// 功能: 将线程 tid 设置为分离状态，它退出后资源由内核直接回收
+/// 返回值: 成功返回 0，线程不存在返回 -ESRCH，已经分离或者是主线程返回 -EINVAL
+/// syscall ID: 1003
+pub fn sys_thread_detach(tid: usize) -> isize {
+    syscall(SYSCALL_THREAD_DETACH, [tid, 0, 0])
 }
 /// 功 能: 为 当 前 进 程 新 增 一 把 互 斥 锁
 /// 参 数: blocking 为 true 表 示 互 斥 锁 基 于 阻 塞 机 制 实 现
@@ -235,19 +643,47 @@ pub fn sys_mutex_create(blocking: bool) -> isize {
 
 /// 功能: 当前线程尝试获取所属进程的一把互斥锁
 /// 参数: id 表示要获取锁的id
-/// 返回值: 0
+/// 返回值: 成功返回 0，开启死锁检测且加锁会导致死锁时返回 -EDEADLK，
+/// id 非法返回 -EINVAL，等待期间锁被销毁返回 -EIDRM
 /// syscall ID: 1011
 pub fn sys_mutex_lock(id: usize) -> isize {
     syscall(SYSCALL_MUTEX_LOCK, [id, 0, 0])
 }
 /// 功能: 当前线程尝试释放所属进程的一把互斥锁
 /// 参数: id 表示要释放锁的 ID
-/// 返回值: 0
+/// 返回值: 成功返回 0，id 非法返回 -EINVAL，锁没有被持有返回 -EPERM
 /// syscall Id: 1012
 pub fn sys_mutex_unlock(id: usize) -> isize {
     syscall(SYSCALL_MUTEX_UNLOCK, [id, 0, 0])
 }
 
+/// 功能: 尝试获取互斥锁，不会阻塞
+/// 参数: id 表示要获取锁的 ID
+/// 返回值: 成功返回 0，锁已经被持有返回 -EBUSY，id 非法返回 -EINVAL
+/// syscall ID: 1013
+pub fn sys_mutex_trylock(id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_TRYLOCK, [id, 0, 0])
+}
+
+/// 功能: 获取互斥锁，最多等待 timeout 指定的相对时间
+/// 参数: id 表示要获取锁的 ID，timeout 表示最长的等待时间
+/// 返回值: 成功返回 0，超时返回 -ETIMEDOUT，timeout 或 id 非法返回 -EINVAL，等待期间锁被销毁返回 -EIDRM
+/// syscall ID: 1014
+pub fn sys_mutex_lock_timeout(id: usize, timeout: &TimeSpec) -> isize {
+    syscall(
+        SYSCALL_MUTEX_LOCK_TIMEOUT,
+        [id, timeout as *const _ as usize, 0],
+    )
+}
+
+/// 功能: 销毁一把互斥锁，它的 ID 可以被之后创建的互斥锁复用
+/// 参数: id 表示要销毁的锁的 ID
+/// 返回值: 成功返回 0，id 非法返回 -EINVAL。阻塞在这把锁上的线程被唤醒，加锁操作返回 -EIDRM
+/// syscall ID: 1015
+pub fn sys_mutex_destroy(id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_DESTROY, [id, 0, 0])
+}
+
 /// 功能: 为当前进程新增一个信号量
 /// 参数: res_count 表示该信号量的初始资源可用数量，即 N，为一个非负数
 /// 返回值: 0
@@ -258,7 +694,7 @@ pub fn sys_semaphore_create(res_count: usize) -> isize {
 
 /// 功能: 对当前进程中的信号量进行 V 操作 (归还)
 /// 参数: sem_id 表示要进行 V 操作的信号量Id
-/// 返回值: 假定操作必成功，返回 0
+/// 返回值: 成功返回 0，sem_id 非法返回 -EINVAL
 /// syscall ID: 1021
 pub fn sys_semaphore_up(sem_id: usize) -> isize {
     syscall(SYSCALL_SEMAPHORE_UP, [sem_id, 0, 0])
@@ -266,12 +702,40 @@ pub fn sys_semaphore_up(sem_id: usize) -> isize {
 
 /// 功能: 对当前进程中的信号量进行 P 操作 (尝试占用)
 /// 参数: sem_id 表示要进行 V 操作的信号量Id
-/// 返回值: 假定操作必成功.返回 0
+/// 返回值: 成功返回 0，开启死锁检测且 P 操作会导致死锁时返回 -EDEADLK，
+/// sem_id 非法返回 -EINVAL，等待期间信号量被销毁返回 -EIDRM
 /// syscall ID: 1022
 pub fn sys_semaphore_down(sem_id: usize) -> isize {
     syscall(SYSCALL_SEMAPHORE_DOWN, [sem_id, 0, 0])
 }
 
+/// 功能: 对信号量进行 P 操作，没有可用资源时不阻塞
+/// 参数: sem_id 表示信号量的 ID
+/// 返回值: 成功返回 0，没有可用资源返回 -EAGAIN，sem_id 非法返回 -EINVAL
+/// syscall ID: 1023
+pub fn sys_semaphore_trydown(sem_id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_TRYDOWN, [sem_id, 0, 0])
+}
+
+/// 功能: 对信号量进行 P 操作，最多等待 timeout 指定的相对时间
+/// 参数: sem_id 表示信号量的 ID，timeout 表示最长的等待时间
+/// 返回值: 成功返回 0，超时返回 -ETIMEDOUT，timeout 或 sem_id 非法返回 -EINVAL，等待期间信号量被销毁返回 -EIDRM
+/// syscall ID: 1024
+pub fn sys_semaphore_down_timeout(sem_id: usize, timeout: &TimeSpec) -> isize {
+    syscall(
+        SYSCALL_SEMAPHORE_DOWN_TIMEOUT,
+        [sem_id, timeout as *const _ as usize, 0],
+    )
+}
+
+/// 功能: 销毁一个信号量，它的 ID 可以被之后创建的信号量复用
+/// 参数: sem_id 表示要销毁的信号量的 ID
+/// 返回值: 成功返回 0，sem_id 非法返回 -EINVAL。阻塞在信号量上的线程被唤醒，P 操作返回 -EIDRM
+/// syscall ID: 1025
+pub fn sys_semaphore_destroy(sem_id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_DESTROY, [sem_id, 0, 0])
+}
+
 /// 功能: 为当前进程新增一个条件变量
 /// 返回值: 假定操作必定成功，返回创建成功的条件变量
 /// syscall ID: 1030
@@ -282,7 +746,7 @@ pub fn sys_condvar_create() -> isize {
 /// 功能：对当前进程的指定条件变量进行 signal 操作，即
 /// 唤醒一个在该条件变量上阻塞的线程（如果存在）。
 /// 参数：condvar_id 表示要操作的条件变量的 ID 。
-/// 返回值：假定该操作必定成功，返回 0 。
+/// 返回值：成功返回 0 ，condvar_id 非法返回 -EINVAL 。
 /// syscall ID : 1031
 pub fn sys_condvar_signal(condvar_id: usize) -> isize {
     syscall(SYSCALL_CONDVAR_SIGNAL, [condvar_id, 0, 0])
@@ -294,8 +758,97 @@ pub fn sys_condvar_signal(condvar_id: usize) -> isize {
 /// 4. 重新获取当前线程之前持有的锁。
 /// 参数：mutex_id 表示当前线程持有的互斥锁的 ID ，而
 /// condvar_id 表示要操作的条件变量的 ID 。
-/// 返回值：假定该操作必定成功，返回 0 。
+/// 返回值：成功返回 0 ，ID 非法返回 -EINVAL ；
+/// 等待期间条件变量被销毁时，重新获取锁后返回 -EIDRM 。
 /// syscall ID : 1032
 pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
     syscall(SYSCALL_CONDVAR_WAIT, [condvar_id, mutex_id, 0])
 }
+
+/// 功能: 与 sys_condvar_wait 相同，最多等待 timeout 指定的相对时间
+/// 返回值: 被唤醒返回 0，超时返回 -ETIMEDOUT，timeout 或 ID 非法返回 -EINVAL，等待期间条件变量被销毁返回 -EIDRM，
+/// 开始等待后返回时都重新持有互斥锁
+/// syscall ID: 1033
+pub fn sys_condvar_wait_timeout(condvar_id: usize, mutex_id: usize, timeout: &TimeSpec) -> isize {
+    syscall(
+        SYSCALL_CONDVAR_WAIT_TIMEOUT,
+        [condvar_id, mutex_id, timeout as *const _ as usize],
+    )
+}
+
+/// 功能: 销毁一个条件变量，它的 ID 可以被之后创建的条件变量复用
+/// 参数: condvar_id 表示要销毁的条件变量的 ID
+/// 返回值: 成功返回 0，condvar_id 非法返回 -EINVAL。等待的线程被唤醒，重新获取锁后返回 -EIDRM
+/// syscall ID: 1034
+pub fn sys_condvar_destroy(condvar_id: usize) -> isize {
+    syscall(SYSCALL_CONDVAR_DESTROY, [condvar_id, 0, 0])
+}
+
+/// 功能: 为当前进程新增一把写者优先的读写锁
+/// 返回值: 创建的读写锁的 ID
+/// syscall ID: 1040
+pub fn sys_rwlock_create() -> isize {
+    syscall(SYSCALL_RWLOCK_CREATE, [0, 0, 0])
+}
+
+/// 功能: 获取读锁，有写者持有或者等待该锁时阻塞
+/// 参数: rwlock_id 表示读写锁的 ID
+/// 返回值: 0
+/// syscall ID: 1041
+pub fn sys_rwlock_read(rwlock_id: usize) -> isize {
+    syscall(SYSCALL_RWLOCK_READ, [rwlock_id, 0, 0])
+}
+
+/// 功能: 获取写锁，有其他线程持有该锁时阻塞
+/// 参数: rwlock_id 表示读写锁的 ID
+/// 返回值: 0
+/// syscall ID: 1042
+pub fn sys_rwlock_write(rwlock_id: usize) -> isize {
+    syscall(SYSCALL_RWLOCK_WRITE, [rwlock_id, 0, 0])
+}
+
+/// 功能: 释放当前线程持有的读锁或写锁
+/// 参数: rwlock_id 表示读写锁的 ID
+/// 返回值: 成功返回 0，rwlock_id 非法返回 -EINVAL，锁没有被持有返回 -EPERM
+/// syscall ID: 1043
+pub fn sys_rwlock_unlock(rwlock_id: usize) -> isize {
+    syscall(SYSCALL_RWLOCK_UNLOCK, [rwlock_id, 0, 0])
+}
+
+/// 功能: 为当前进程新增一个屏障
+/// 参数: count 表示每一轮需要到达屏障的线程数
+/// 返回值: 成功返回屏障的 ID，count 为 0 返回 -EINVAL
+/// syscall ID: 1050
+pub fn sys_barrier_create(count: usize) -> isize {
+    syscall(SYSCALL_BARRIER_CREATE, [count, 0, 0])
+}
+
+/// 功能: 等待本轮的所有线程到达屏障
+/// 参数: barrier_id 表示屏障的 ID
+/// 返回值: 最后到达的线程返回 1，其他线程返回 0
+/// syscall ID: 1051
+pub fn sys_barrier_wait(barrier_id: usize) -> isize {
+    syscall(SYSCALL_BARRIER_WAIT, [barrier_id, 0, 0])
+}
+
+/// 功能: 开启或关闭当前进程的系统调用跟踪，开启后 fork 出的子进程共享同一个跟踪缓冲区
+/// 参数: enable 非 0 表示开启，为 0 表示关闭
+/// 返回值: 0
+/// syscall ID: 1060
+pub fn sys_trace(enable: usize) -> isize {
+    syscall(SYSCALL_TRACE, [enable, 0, 0])
+}
+
+/// 功能: 从子进程的跟踪缓冲区读出整条记录，每条记录占一行，字段以制表符分隔：
+/// pid、tid、系统调用名、解码后的参数、返回值（不返回时为 ?）、耗时（微秒）；
+/// 有记录被丢弃时先读出一行 dropped 与丢弃的条数
+/// 参数: pid 表示子进程的进程号，buffer 表示接收记录的缓冲区
+/// 返回值: 读出的字节数，缓冲区为空且子进程已经退出返回 0，子进程尚未退出返回 -EAGAIN；
+/// pid 不是子进程返回 -ECHILD，子进程没有开启跟踪或者 buffer 放不下一条记录返回 -EINVAL
+/// syscall ID: 1061
+pub fn sys_trace_read(pid: usize, buffer: &mut [u8]) -> isize {
+    syscall(
+        SYSCALL_TRACE_READ,
+        [pid, buffer.as_mut_ptr() as usize, buffer.len()],
+    )
+}